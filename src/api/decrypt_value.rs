@@ -6,6 +6,43 @@ use aes_gcm::{
 use rsa::{Oaep, Pkcs1v15Encrypt, RsaPrivateKey};
 use tracing::warn;
 use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+// Sensitive values decrypted or not by the connectors listing of a platform
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DecryptTally {
+    pub decrypted: u64,
+    pub failed: u64,
+}
+
+impl DecryptTally {
+    // Not a single value could be decrypted, a strong signal of a wrong credentials key
+    pub fn all_failed(&self) -> bool {
+        self.failed > 0 && self.decrypted == 0
+    }
+}
+
+// Accumulated per platform until the next orchestration cycle takes it
+static DECRYPT_TALLIES: Mutex<BTreeMap<&str, DecryptTally>> = Mutex::new(BTreeMap::new());
+
+pub fn record_decrypt(platform: &'static str, decrypted: bool) {
+    let mut tallies = DECRYPT_TALLIES.lock().unwrap();
+    let tally = tallies.entry(platform).or_default();
+    if decrypted {
+        tally.decrypted += 1;
+    } else {
+        tally.failed += 1;
+    }
+}
+
+pub fn take_decrypt_tally(platform: &str) -> DecryptTally {
+    DECRYPT_TALLIES
+        .lock()
+        .unwrap()
+        .remove(platform)
+        .unwrap_or_default()
+}
 
 pub fn parse_aes_encrypted_value(
     private_key: &RsaPrivateKey,
@@ -46,4 +83,32 @@ pub fn parse_aes_encrypted_value(
             Ok(String::from(""))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(decrypted: u64, failed: u64) -> DecryptTally {
+        DecryptTally { decrypted, failed }
+    }
+
+    #[test]
+    fn tally_is_taken_once_per_platform() {
+        record_decrypt("decrypt-test", false);
+        record_decrypt("decrypt-test", false);
+        record_decrypt("decrypt-other", true);
+
+        let taken = take_decrypt_tally("decrypt-test");
+        assert_eq!(taken, tally(0, 2));
+        assert!(taken.all_failed());
+        assert_eq!(take_decrypt_tally("decrypt-test"), DecryptTally::default());
+        assert!(!take_decrypt_tally("decrypt-other").all_failed());
+    }
+
+    #[test]
+    fn partial_failures_are_not_a_wrong_key() {
+        assert!(!tally(1, 5).all_failed());
+        assert!(!DecryptTally::default().all_failed());
+    }
+}
@@ -2,7 +2,7 @@ use rsa::{RsaPrivateKey};
 use serde::Deserialize;
 use tracing::warn;
 use crate::api::{ApiConnector, ApiContractConfig};
-use crate::api::decrypt_value::parse_aes_encrypted_value;
+use crate::api::decrypt_value::{parse_aes_encrypted_value, record_decrypt};
 
 pub mod get_connector_instances;
 pub mod patch_health;
@@ -24,7 +24,9 @@ pub struct ConnectorInstances {
     pub connector_image: String,
     pub connector_instance_current_status: String,
     pub connector_instance_requested_status: String,
-    pub connector_instance_configurations: Vec<ConnectorContractConfiguration>,
+    // Missing or null for connectors without configuration, deployed with the injected env vars only
+    #[serde(default)]
+    pub connector_instance_configurations: Option<Vec<ConnectorContractConfiguration>>,
 }
 
 impl ConnectorInstances {
@@ -33,11 +35,13 @@ impl ConnectorInstances {
         let contract_configuration = self
             .connector_instance_configurations
             .iter()
+            .flatten()
             .map(|c| {
                 let is_sensitive = c.configuration_is_encrypted;
                 if is_sensitive {
                     let encrypted_value = c.configuration_value.clone().unwrap_or_default();
                     let decoded_value_result = parse_aes_encrypted_value(private_key, encrypted_value);
+                    record_decrypt("openaev", decoded_value_result.is_ok());
                     match decoded_value_result {
                         Ok(decoded_value) => ApiContractConfig {
                             key: c.configuration_key.clone(),
@@ -73,4 +77,45 @@ impl ConnectorInstances {
             contract_configuration,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::rand_core::OsRng;
+    use serde_json::json;
+
+    fn instance(configurations: Option<serde_json::Value>) -> ConnectorInstances {
+        let mut instance = json!({
+            "connector_instance_id": "instance-1",
+            "connector_instance_name": "Nmap",
+            "connector_instance_hash": "hash",
+            "connector_image": "openaev/injector-nmap:1.18.0",
+            "connector_instance_current_status": "stopped",
+            "connector_instance_requested_status": "starting",
+        });
+        if let Some(configurations) = configurations {
+            instance["connector_instance_configurations"] = configurations;
+        }
+        serde_json::from_value(instance).unwrap()
+    }
+
+    #[test]
+    fn instances_without_configuration_map_to_an_empty_one() {
+        let private_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        for configurations in [None, Some(serde_json::Value::Null)] {
+            let connector = instance(configurations).to_api_connector(&private_key);
+            assert_eq!(connector.id, "instance-1");
+            assert!(connector.contract_configuration.is_empty());
+        }
+
+        let configured = instance(Some(json!([{
+            "configuration_key": "NMAP_SCAN",
+            "configuration_value": "fast",
+            "configuration_is_encrypted": false,
+        }])));
+        let connector = configured.to_api_connector(&private_key);
+        assert_eq!(connector.contract_configuration.len(), 1);
+        assert_eq!(connector.contract_configuration[0].value, "fast");
+    }
 }
\ No newline at end of file
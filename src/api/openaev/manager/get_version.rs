@@ -3,5 +3,5 @@ use crate::api::openaev::ApiOpenAEV;
 
 pub async fn get_version(api: &ApiOpenAEV) -> Option<String> {
     let response = api.get("/settings/version").send().await;
-    handle_api_text_response(response, "fetch version").await
+    handle_api_text_response(response, "fetch OpenAEV version").await
 }
\ No newline at end of file
@@ -2,7 +2,10 @@ mod connector;
 mod manager;
 mod api_handler;
 
-use crate::api::{ApiConnector, ComposerApi, ConnectorStatus, HttpClientConfig, build_http_client};
+use crate::api::{
+    ApiConnector, ComposerApi, ConnectorStatus, HttpClientConfig, api_uri, build_http_client,
+};
+use crate::config::credentials::resolve_token;
 use crate::config::settings::Daemon;
 use async_trait::async_trait;
 use std::time::Duration;
@@ -23,8 +26,13 @@ pub struct ApiOpenAEV {
 impl ApiOpenAEV {
     pub fn new() -> Self {
         let settings = crate::settings();
-        let bearer = format!("{} {}", BEARER, settings.openaev.token);
-        let api_uri = format!("{}/api", &settings.openaev.url);
+        let token = resolve_token(
+            &settings.openaev.token,
+            settings.openaev.token_filepath.as_deref(),
+        )
+        .unwrap_or_else(|e| panic!("Failed to read token for platform 'openaev': {}", e));
+        let bearer = format!("{} {}", BEARER, token);
+        let api_uri = api_uri(&settings.openaev.url, &settings.openaev.api_path);
         let daemon = settings.openaev.daemon.clone();
         let logs_schedule = settings.openaev.logs_schedule;
 
@@ -35,6 +43,10 @@ impl ApiOpenAEV {
             with_proxy: settings.openaev.with_proxy,
             http_proxy: settings.openaev.http_proxy.clone(),
             https_proxy: settings.openaev.https_proxy.clone(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            compression: settings.openaev.compression,
+            user_agent: settings.manager.user_agent(),
             platform_name: "openaev".into(),
         })
         .unwrap_or_else(|e| panic!("Failed to build HTTP client for platform 'openaev': {}", e));
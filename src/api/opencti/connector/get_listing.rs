@@ -1,8 +1,9 @@
-use crate::api::ApiConnector;
+use crate::api::ConnectorListing;
 use crate::api::opencti::ApiOpenCTI;
-use crate::api::opencti::connector::ManagedConnector;
-use crate::api::opencti::error_handler::{extract_optional_field, handle_graphql_response};
-use tracing::error;
+use crate::api::opencti::connector::{ManagedConnector, to_listing};
+use crate::api::opencti::error_handler::{
+    extract_optional_field, handle_fetch_error, handle_graphql_response,
+};
 
 // region schema
 use crate::api::opencti::opencti as schema;
@@ -15,7 +16,7 @@ pub struct GetConnectors {
 }
 // endregion
 
-pub async fn list(api: &ApiOpenCTI) -> Option<Vec<ApiConnector>> {
+pub async fn list(api: &ApiOpenCTI) -> Option<ConnectorListing> {
     use cynic::QueryBuilder;
 
     let query = GetConnectors::build({});
@@ -31,17 +32,9 @@ pub async fn list(api: &ApiOpenCTI) -> Option<Vec<ApiConnector>> {
                     data.connectors_for_managers,
                     "connectors_for_managers",
                     "connectors_for_managers"
-                ).map(|connectors| {
-                    connectors
-                        .into_iter()
-                        .map(|managed_connector| managed_connector.to_api_connector(&api.private_key))
-                        .collect()
-                })
+                ).map(|connectors| to_listing(connectors, &api.private_key))
             })
         }
-        Err(e) => {
-            error!(error = e.to_string(), "Fail to fetch connectors");
-            None
-        }
+        Err(e) => handle_fetch_error(e, "connectors_for_managers", "Fail to fetch connectors"),
     }
 }
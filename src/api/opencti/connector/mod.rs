@@ -1,5 +1,5 @@
 use serde::Serialize;
-use crate::api::{ApiConnector, ApiContractConfig};
+use crate::api::{ApiConnector, ApiContractConfig, ConnectorListing};
 use rsa::{RsaPrivateKey};
 use tracing::{warn};
 use std::str;
@@ -11,7 +11,7 @@ pub mod post_health;
 
 use cynic;
 use crate::api::opencti::opencti as schema;
-use crate::api::decrypt_value::parse_aes_encrypted_value;
+use crate::api::decrypt_value::{parse_aes_encrypted_value, record_decrypt};
 
 #[derive(cynic::QueryFragment, Debug, Clone, Serialize)]
 pub struct ConnectorContractConfiguration {
@@ -36,19 +36,52 @@ pub struct ManagedConnector {
     pub manager_contract_configuration: Option<Vec<ConnectorContractConfiguration>>,
 }
 
+// Required field of a managed connector, None is reported and the connector skipped
+fn required_field(id: &str, field: &str, value: &Option<String>) -> Option<String> {
+    if value.is_none() {
+        warn!(id, field, "Skipping connector, required field is missing");
+    }
+    value.clone()
+}
+
+// Map the listed connectors, the ids of the skipped ones are kept so their containers are not orphaned
+pub fn to_listing(
+    connectors: Vec<ManagedConnector>,
+    private_key: &RsaPrivateKey,
+) -> ConnectorListing {
+    let mut listing = ConnectorListing::default();
+    for managed_connector in connectors {
+        match managed_connector.to_api_connector(private_key) {
+            Some(connector) => listing.connectors.push(connector),
+            None => listing.skipped.push(managed_connector.id.into_inner()),
+        }
+    }
+    listing
+}
+
 impl ManagedConnector {
 
-    pub fn to_api_connector(&self, private_key: &RsaPrivateKey) -> ApiConnector {
-        let contract_configuration = self
+    pub fn to_api_connector(&self, private_key: &RsaPrivateKey) -> Option<ApiConnector> {
+        let id = self.id.clone().into_inner();
+        let image = required_field(&id, "manager_contract_image", &self.manager_contract_image)?;
+        let contract_hash =
+            required_field(&id, "manager_contract_hash", &self.manager_contract_hash)?;
+        let requested_status = required_field(
+            &id,
+            "manager_requested_status",
+            &self.manager_requested_status,
+        )?;
+        let contract_configuration: Vec<ApiContractConfig> = self
             .manager_contract_configuration
             .clone()
-            .unwrap()
+            .unwrap_or_default()
             .into_iter()
             .map(|c| {
                 let is_sensitive = c.encrypted.unwrap_or_default();
                 if is_sensitive {
                     let encrypted_value = c.value.unwrap_or_default();
                     let decoded_value_result = parse_aes_encrypted_value(private_key, encrypted_value);
+                    record_decrypt("opencti", decoded_value_result.is_ok());
                     match decoded_value_result {
                         Ok(decoded_value) => ApiContractConfig {
                             key: c.key,
@@ -73,15 +106,82 @@ impl ManagedConnector {
                 }
             })
             .collect();
-        ApiConnector {
-            id: self.id.clone().into_inner(),
+        let contract_configuration = if crate::settings().manager.flattens_json_config(&id) {
+            contract_configuration
+                .into_iter()
+                .flat_map(ApiContractConfig::flatten_json)
+                .collect()
+        } else {
+            contract_configuration
+        };
+        Some(ApiConnector {
+            id,
             platform: "opencti".to_string(),
             name: self.name.clone(),
-            image: self.manager_contract_image.clone().unwrap(),
-            contract_hash: self.manager_contract_hash.clone().unwrap(),
+            image,
+            contract_hash,
             current_status: self.manager_current_status.clone(),
-            requested_status: self.manager_requested_status.clone().unwrap(),
+            requested_status,
             contract_configuration,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::rand_core::OsRng;
+
+    fn managed_connector(id: &str, image: Option<&str>, hash: Option<&str>) -> ManagedConnector {
+        ManagedConnector {
+            id: cynic::Id::new(id),
+            name: format!("Connector {}", id),
+            manager_contract_hash: hash.map(String::from),
+            manager_contract_image: image.map(String::from),
+            manager_current_status: None,
+            manager_requested_status: Some("starting".to_string()),
+            manager_contract_configuration: None,
         }
     }
+
+    #[test]
+    fn connectors_with_missing_required_fields_are_skipped() {
+        let private_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let connectors = vec![
+            managed_connector("no-image", None, Some("hash")),
+            managed_connector("valid", Some("opencti/connector-misp:6.8.0"), Some("hash")),
+            managed_connector("no-hash", Some("opencti/connector-misp:6.8.0"), None),
+        ];
+
+        let listing = to_listing(connectors, &private_key);
+        let mapped = listing.connectors;
+
+        assert_eq!(
+            listing.skipped,
+            vec!["no-image".to_string(), "no-hash".to_string()]
+        );
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(mapped[0].id, "valid");
+        assert_eq!(mapped[0].image, "opencti/connector-misp:6.8.0");
+        assert!(mapped[0].contract_configuration.is_empty());
+    }
+
+    #[test]
+    fn null_configuration_from_the_listing_maps_to_an_empty_one() {
+        let private_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let managed: ManagedConnector = serde_json::from_value(serde_json::json!({
+            "id": "connector-1",
+            "name": "MISP",
+            "manager_contract_hash": "hash",
+            "manager_contract_image": "opencti/connector-misp:6.8.0",
+            "manager_current_status": null,
+            "manager_requested_status": "starting",
+            "manager_contract_configuration": null,
+        }))
+        .unwrap();
+
+        let connector = managed.to_api_connector(&private_key).unwrap();
+        assert_eq!(connector.id, "connector-1");
+        assert!(connector.contract_configuration.is_empty());
+    }
 }
@@ -1,6 +1,5 @@
 use crate::api::opencti::ApiOpenCTI;
-use crate::api::opencti::error_handler::handle_graphql_response;
-use tracing::error;
+use crate::api::opencti::error_handler::{handle_fetch_error, handle_graphql_response};
 
 // region schema
 use crate::api::opencti::opencti as schema;
@@ -46,7 +45,7 @@ pub async fn health(
         },
     };
     let mutation = UpdateConnectorHealth::build(vars);
-    let mutation_response = api.query_fetch(mutation).await;
+    let mutation_response = api.traced_fetch(mutation).await;
     match mutation_response {
         Ok(response) => {
             handle_graphql_response(
@@ -55,9 +54,6 @@ pub async fn health(
                 "OpenCTI backend does not support XTM composer health updates. The connector will continue to run but health metrics won't be sent to OpenCTI."
             ).map(|data| data.update_connector_health.inner().to_string())
         }
-        Err(e) => {
-            error!(error = e.to_string(), "Fail to push health metrics");
-            None
-        }
+        Err(e) => handle_fetch_error(e, "update_connector_health", "Fail to push health metrics"),
     }
 }
@@ -1,6 +1,6 @@
 use crate::api::opencti::ApiOpenCTI;
-use crate::api::opencti::error_handler::handle_graphql_response;
-use tracing::error;
+use crate::api::opencti::error_handler::{handle_fetch_error, handle_graphql_response};
+use tracing::debug;
 
 // region schema
 use crate::api::opencti::opencti as schema;
@@ -25,17 +25,46 @@ pub struct LogsConnectorStatusInput<'a> {
 }
 // endregion
 
-pub async fn logs(id: String, logs: Vec<String>, api: &ApiOpenCTI) -> Option<String> {
+// Serialized size of a line in the logs array, quotes / escapes and separator included
+fn serialized_len(line: &str) -> usize {
+    serde_json::to_string(line).map_or(line.len(), |value| value.len()) + 1
+}
+
+// Split the logs so each serialized array stays under max_bytes, a line
+// larger than the limit on its own is still sent in its own batch
+pub fn batch_by_size(logs: &[String], max_bytes: Option<usize>) -> Vec<Vec<&str>> {
+    let Some(max_bytes) = max_bytes else {
+        return vec![logs.iter().map(String::as_str).collect()];
+    };
+    let mut batches: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    // Array brackets
+    let mut current_len = 2;
+    for line in logs {
+        let line_len = serialized_len(line);
+        if !current.is_empty() && current_len + line_len > max_bytes {
+            batches.push(std::mem::take(&mut current));
+            current_len = 2;
+        }
+        current.push(line);
+        current_len += line_len;
+    }
+    if !current.is_empty() || batches.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+async fn send_logs(id: &str, logs: Vec<&str>, api: &ApiOpenCTI) -> Option<String> {
     use cynic::MutationBuilder;
-    let str_logs = logs.iter().map(|c| c.as_str()).collect();
     let vars = ReportConnectorLogsVariables {
         input: LogsConnectorStatusInput {
             id: &cynic::Id::new(id),
-            logs: str_logs,
+            logs,
         },
     };
     let mutation = ReportConnectorLogs::build(vars);
-    let mutation_response = api.query_fetch(mutation).await;
+    let mutation_response = api.logs_fetch(mutation).await;
     match mutation_response {
         Ok(response) => {
             handle_graphql_response(
@@ -44,9 +73,69 @@ pub async fn logs(id: String, logs: Vec<String>, api: &ApiOpenCTI) -> Option<Str
                 "OpenCTI backend does not support XTM composer log updates. The connector will continue to run but logs won't be sent to OpenCTI."
             ).map(|data| data.update_connector_logs.inner().to_string())
         }
-        Err(e) => {
-            error!(error = e.to_string(), "Fail to push logs");
-            None
+        Err(e) => handle_fetch_error(e, "update_connector_logs", "Fail to push logs"),
+    }
+}
+
+pub async fn logs(
+    id: String,
+    logs: Vec<String>,
+    max_request_bytes: Option<usize>,
+    api: &ApiOpenCTI,
+) -> Option<String> {
+    let batches = batch_by_size(&logs, max_request_bytes);
+    if batches.len() > 1 {
+        debug!(id, batches = batches.len(), "Splitting connector logs");
+    }
+    let mut result = None;
+    for batch in batches {
+        result = send_logs(&id, batch, api).await;
+        // Later batches would fail the same way
+        result.as_ref()?;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialized_size(batch: &[&str]) -> usize {
+        serde_json::to_string(batch).unwrap().len()
+    }
+
+    #[test]
+    fn long_lines_are_split_by_serialized_size() {
+        let logs: Vec<String> = (0..4)
+            .map(|index| format!("ERROR {index} {}", "x".repeat(3000)))
+            .collect();
+
+        // Under any line count cap, but 4 lines of 3KB can't fit in 7KB
+        let batches = batch_by_size(&logs, Some(7000));
+        assert_eq!(batches.len(), 2);
+        for batch in &batches {
+            assert_eq!(batch.len(), 2);
+            assert!(serialized_size(batch) <= 7000);
         }
+        let sent: Vec<&str> = batches.concat();
+        assert_eq!(sent, logs.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn escapes_count_in_the_batch_size() {
+        let logs = vec!["\"".repeat(100), "\"".repeat(100)];
+        // 200 bytes of raw content, 400 once escaped
+        let batches = batch_by_size(&logs, Some(300));
+        assert_eq!(batches.len(), 2);
+    }
+
+    #[test]
+    fn oversized_line_and_no_limit_keep_a_single_batch() {
+        let logs = vec!["x".repeat(5000)];
+        assert_eq!(batch_by_size(&logs, Some(1000)).len(), 1);
+
+        let logs: Vec<String> = (0..100).map(|_| "x".repeat(5000)).collect();
+        assert_eq!(batch_by_size(&logs, None).len(), 1);
+        assert_eq!(batch_by_size(&[], Some(1000)), vec![Vec::<&str>::new()]);
     }
 }
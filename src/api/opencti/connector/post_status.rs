@@ -1,11 +1,12 @@
 use crate::api::opencti::ApiOpenCTI;
 use crate::api::opencti::connector::ManagedConnector;
-use crate::api::opencti::error_handler::{extract_optional_field, handle_graphql_response};
+use crate::api::opencti::error_handler::{
+    extract_optional_field, handle_fetch_error, handle_graphql_response,
+};
 use crate::api::{ApiConnector, ConnectorStatus};
 
 use crate::api::opencti::opencti as schema;
 use cynic;
-use tracing::error;
 
 // region schema
 #[derive(cynic::QueryVariables, Debug)]
@@ -53,7 +54,7 @@ pub async fn status(id: String, status: ConnectorStatus, api: &ApiOpenCTI) -> Op
         },
     };
     let mutation = UpdateConnectorCurrentStatus::build(vars);
-    let mutation_response = api.query_fetch(mutation).await;
+    let mutation_response = api.traced_fetch(mutation).await;
     match mutation_response {
         Ok(response) => {
             handle_graphql_response(
@@ -65,12 +66,9 @@ pub async fn status(id: String, status: ConnectorStatus, api: &ApiOpenCTI) -> Op
                     data.update_connector_current_status,
                     "update_connector_current_status",
                     "update_connector_current_status"
-                ).map(|connector| connector.to_api_connector(&api.private_key))
+                ).and_then(|connector| connector.to_api_connector(&api.private_key))
             })
         }
-        Err(e) => {
-            error!(error = e.to_string(), "Fail to modify status");
-            None
-        }
+        Err(e) => handle_fetch_error(e, "update_connector_current_status", "Fail to modify status"),
     }
 }
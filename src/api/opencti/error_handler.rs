@@ -1,28 +1,40 @@
 use cynic::GraphQlResponse;
-use tracing::error;
+use cynic::http::CynicReqwestError;
+use tracing::{error, warn};
+
+const SCHEMA_MISMATCH_MESSAGE: &str = "OpenCTI response does not match the composer GraphQL schema, check that the composer and platform versions are compatible";
 
 /// Generic error handler for GraphQL responses
-/// Returns the data if successful, None if there are errors or no data
+/// Returns the data if present, None if there is no data
+/// Errors returned alongside data (partial success) are logged but the data is kept
 pub fn handle_graphql_response<T>(
     response: GraphQlResponse<T>,
     operation_name: &str,
     unsupported_message: &str,
 ) -> Option<T> {
-    // Check for GraphQL errors first
     let query_errors = response.errors.unwrap_or_default();
-    if !query_errors.is_empty() {
-        let errors: Vec<String> = query_errors.iter().map(|err| err.to_string()).collect();
-        error!(
-            error = errors.join(","),
-            operation = operation_name,
-            "GraphQL operation failed"
-        );
-        return None;
-    }
+    let errors: Vec<String> = query_errors.iter().map(|err| err.to_string()).collect();
 
-    // Check if data is present
     match response.data {
-        Some(data) => Some(data),
+        Some(data) => {
+            // Partial success, some fields failed to resolve
+            if !errors.is_empty() {
+                warn!(
+                    error = errors.join(","),
+                    operation = operation_name,
+                    "GraphQL operation returned partial data with errors"
+                );
+            }
+            Some(data)
+        }
+        None if !errors.is_empty() => {
+            error!(
+                error = errors.join(","),
+                operation = operation_name,
+                "GraphQL operation failed"
+            );
+            None
+        }
         None => {
             error!(
                 operation = operation_name,
@@ -34,6 +46,35 @@ pub fn handle_graphql_response<T>(
     }
 }
 
+/// A successful response that cannot be decoded means the platform schema drifted
+pub fn is_schema_mismatch(error: &CynicReqwestError) -> bool {
+    matches!(error, CynicReqwestError::ReqwestError(err) if err.is_decode())
+}
+
+/// Generic error handler for failed GraphQL requests, always returns None
+pub fn handle_fetch_error<T>(
+    error: CynicReqwestError,
+    operation_name: &str,
+    failure_message: &str,
+) -> Option<T> {
+    if is_schema_mismatch(&error) {
+        error!(
+            error = error.to_string(),
+            operation = operation_name,
+            "{}",
+            SCHEMA_MISMATCH_MESSAGE
+        );
+    } else {
+        error!(
+            error = error.to_string(),
+            operation = operation_name,
+            "{}",
+            failure_message
+        );
+    }
+    None
+}
+
 /// Helper to extract a nested optional field with error handling
 pub fn extract_optional_field<T>(
     field: Option<T>,
@@ -52,3 +93,101 @@ pub fn extract_optional_field<T>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::opencti::manager::get_version::GetVersion;
+    use cynic::QueryBuilder;
+    use cynic::http::ReqwestExt;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn response(payload: serde_json::Value) -> GraphQlResponse<serde_json::Value> {
+        serde_json::from_value(payload).unwrap()
+    }
+
+    #[test]
+    fn partial_response_keeps_data_and_errors() {
+        let partial = response(json!({
+            "data": { "connectorsForManagers": [{ "id": "connector-1" }] },
+            "errors": [{ "message": "Cannot resolve manager_contract_image", "path": ["connectorsForManagers", 1] }]
+        }));
+        assert!(partial.errors.as_ref().is_some_and(|errors| errors.len() == 1));
+
+        let data = handle_graphql_response(partial, "connectors_for_managers", "unsupported");
+        assert_eq!(
+            data,
+            Some(json!({ "connectorsForManagers": [{ "id": "connector-1" }] }))
+        );
+    }
+
+    #[test]
+    fn errors_without_data_return_none() {
+        let failed = response(json!({
+            "data": null,
+            "errors": [{ "message": "Unknown field" }]
+        }));
+        assert_eq!(handle_graphql_response(failed, "about", "unsupported"), None);
+    }
+
+    #[test]
+    fn missing_data_without_errors_returns_none() {
+        let empty = response(json!({ "data": null }));
+        assert_eq!(handle_graphql_response(empty, "about", "unsupported"), None);
+    }
+
+    // Answer a single GraphQL request with the given status and body
+    async fn fetch_version(
+        status_line: &str,
+        body: &str,
+    ) -> Result<GraphQlResponse<GetVersion>, CynicReqwestError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status_line,
+            body.len(),
+            body
+        );
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        reqwest::Client::new()
+            .post(format!("http://{}/graphql", address))
+            .run_graphql(GetVersion::build({}))
+            .await
+    }
+
+    #[tokio::test]
+    async fn unexpected_response_shape_is_a_schema_mismatch() {
+        let error = fetch_version("200 OK", r#"{"data": {"about": {"release": "6.8.0"}}}"#)
+            .await
+            .unwrap_err();
+        assert!(is_schema_mismatch(&error), "{error}");
+        assert_eq!(
+            handle_fetch_error::<String>(error, "about", "Fail to fetch version"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_response_body_is_a_schema_mismatch() {
+        let error = fetch_version("200 OK", "<html>not graphql</html>")
+            .await
+            .unwrap_err();
+        assert!(is_schema_mismatch(&error), "{error}");
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_not_schema_mismatches() {
+        let error = fetch_version("502 Bad Gateway", "upstream unavailable")
+            .await
+            .unwrap_err();
+        assert!(!is_schema_mismatch(&error), "{error}");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::api::opencti::ApiOpenCTI;
+use crate::api::opencti::connector::get_listing::GetConnectors;
+use cynic::GraphQlResponse;
+use cynic::http::CynicReqwestError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+// Codes of the OpenCTI errors raised for a missing or insufficient token
+const UNAUTHORIZED_CODES: [&str; 2] = ["AUTH_REQUIRED", "FORBIDDEN_ACCESS"];
+
+#[derive(Debug, Deserialize)]
+pub struct ErrorExtensions {
+    pub code: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TokenScope {
+    Authorized,
+    Unauthorized(String),
+    // Platform unreachable or unexpected answer, nothing known about the token
+    Unverified(String),
+}
+
+pub fn token_scope<T>(
+    result: Result<GraphQlResponse<T, ErrorExtensions>, CynicReqwestError>,
+) -> TokenScope {
+    match result {
+        Ok(response) => {
+            let response_errors = response.errors.unwrap_or_default();
+            let unauthorized = response_errors.iter().any(|error| {
+                error
+                    .extensions
+                    .as_ref()
+                    .and_then(|extensions| extensions.code.as_deref())
+                    .is_some_and(|code| UNAUTHORIZED_CODES.contains(&code))
+            });
+            let errors: Vec<String> = response_errors.iter().map(|err| err.to_string()).collect();
+            if unauthorized {
+                TokenScope::Unauthorized(errors.join(","))
+            } else if response.data.is_some() {
+                TokenScope::Authorized
+            } else {
+                TokenScope::Unverified(errors.join(","))
+            }
+        }
+        Err(CynicReqwestError::ErrorResponse(status, body))
+            if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN =>
+        {
+            TokenScope::Unauthorized(format!("{status}: {body}"))
+        }
+        Err(err) => TokenScope::Unverified(err.to_string()),
+    }
+}
+
+// The connectors listing is a harmless read requiring the connector manager capability
+pub async fn scope(api: &ApiOpenCTI) -> TokenScope {
+    use cynic::QueryBuilder;
+
+    let query = GetConnectors::build({});
+    token_scope(api.query_fetch_with_extensions(query).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cynic::QueryBuilder;
+    use cynic::http::ReqwestExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Answer a single connectors listing with the given status and body
+    async fn list_connectors(status_line: &str, body: &str) -> TokenScope {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status_line,
+            body.len(),
+            body
+        );
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        let result = reqwest::Client::new()
+            .post(format!("http://{}/graphql", address))
+            .run_graphql(GetConnectors::build({}))
+            .retain_extensions::<ErrorExtensions>()
+            .await;
+        token_scope(result)
+    }
+
+    #[tokio::test]
+    async fn forbidden_response_is_unauthorized() {
+        let forbidden = r#"{"errors":[{"message":"You are not allowed to do this.","extensions":{"code":"FORBIDDEN_ACCESS"}}],"data":{"connectorsForManagers":null}}"#;
+        let scope = list_connectors("200 OK", forbidden).await;
+        assert!(
+            matches!(&scope, TokenScope::Unauthorized(error) if error.contains("not allowed")),
+            "{scope:?}"
+        );
+
+        let scope = list_connectors("401 Unauthorized", "invalid token").await;
+        assert!(matches!(scope, TokenScope::Unauthorized(_)), "{scope:?}");
+    }
+
+    #[tokio::test]
+    async fn error_code_wins_over_the_message() {
+        let localized = r#"{"errors":[{"message":"Vous devez être connecté","extensions":{"code":"AUTH_REQUIRED"}}],"data":null}"#;
+        let scope = list_connectors("200 OK", localized).await;
+        assert!(matches!(scope, TokenScope::Unauthorized(_)), "{scope:?}");
+
+        // Same message without the code, an unexpected answer
+        let uncoded = r#"{"errors":[{"message":"You are not allowed to do this."}],"data":null}"#;
+        let scope = list_connectors("200 OK", uncoded).await;
+        assert!(matches!(scope, TokenScope::Unverified(_)), "{scope:?}");
+    }
+
+    #[tokio::test]
+    async fn listing_answer_is_authorized() {
+        let scope = list_connectors("200 OK", r#"{"data":{"connectorsForManagers":[]}}"#).await;
+        assert_eq!(scope, TokenScope::Authorized);
+    }
+
+    #[tokio::test]
+    async fn unreachable_platform_is_unverified() {
+        let scope = list_connectors("502 Bad Gateway", "upstream unavailable").await;
+        assert!(matches!(scope, TokenScope::Unverified(_)), "{scope:?}");
+    }
+}
@@ -1,5 +1,6 @@
-use crate::api::opencti::error_handler::{handle_graphql_response, extract_optional_field};
-use tracing::error;
+use crate::api::opencti::error_handler::{
+    extract_optional_field, handle_fetch_error, handle_graphql_response,
+};
 
 // region schema
 use crate::api::opencti::{ApiOpenCTI, opencti as schema};
@@ -36,12 +37,6 @@ pub async fn version(api: &ApiOpenCTI) -> Option<String> {
                 ).map(|about| about.version)
             })
         }
-        Err(e) => {
-            error!(
-                error = e.to_string(),
-                "Fail to fetch version, check your configuration"
-            );
-            None
-        }
+        Err(e) => handle_fetch_error(e, "about", "Fail to fetch version, check your configuration"),
     }
 }
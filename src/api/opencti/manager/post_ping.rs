@@ -1,8 +1,9 @@
 use crate::api::opencti::ApiOpenCTI;
 use crate::api::opencti::manager::ConnectorManager;
-use crate::api::opencti::error_handler::{handle_graphql_response, extract_optional_field};
+use crate::api::opencti::error_handler::{
+    extract_optional_field, handle_fetch_error, handle_graphql_response,
+};
 use crate::settings;
-use tracing::error;
 
 use crate::api::opencti::opencti as schema;
 use cynic;
@@ -54,9 +55,6 @@ pub async fn ping(api: &ApiOpenCTI) -> Option<String> {
                 ).map(|manager| manager.about_version)
             })
         }
-        Err(err) => {
-            error!(error = err.to_string(), "Fail to ping api");
-            None
-        }
+        Err(err) => handle_fetch_error(err, "update_connector_manager_status", "Fail to ping api"),
     }
 }
@@ -1,9 +1,11 @@
 use crate::api::opencti::ApiOpenCTI;
 use crate::api::opencti::manager::ConnectorManager;
-use crate::api::opencti::error_handler::{handle_graphql_response, extract_optional_field};
+use crate::api::opencti::error_handler::{
+    extract_optional_field, handle_fetch_error, handle_graphql_response,
+};
 use crate::api::opencti::opencti as schema;
 use cynic;
-use tracing::{error, info};
+use tracing::info;
 use rsa::{RsaPublicKey, pkcs1::EncodeRsaPublicKey};
 
 // region schema
@@ -66,7 +68,11 @@ pub async fn register(api: &ApiOpenCTI) {
             }
         }
         Err(e) => {
-            error!(error = e.to_string(), "Error registering connector manager");
+            handle_fetch_error::<()>(
+                e,
+                "register_connectors_manager",
+                "Error registering connector manager",
+            );
         }
     }
 }
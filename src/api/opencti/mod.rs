@@ -1,11 +1,22 @@
-use crate::api::{ApiConnector, ComposerApi, ConnectorStatus, HttpClientConfig, build_http_client};
+use crate::api::{
+    ApiConnector, ComposerApi, ConnectorListing, ConnectorStatus, HttpClientConfig, api_uri,
+    build_http_client,
+};
+use crate::api::rate_limit::{RateLimiter, retry_after};
+use crate::config::credentials::resolve_token;
 use crate::config::settings::Daemon;
 use async_trait::async_trait;
-use cynic::Operation;
 use cynic::http::CynicReqwestError;
+use cynic::{GraphQlResponse, Operation};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use std::time::Duration;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use rsa::RsaPrivateKey;
 
 pub mod connector;
@@ -14,6 +25,7 @@ pub mod error_handler;
 
 const BEARER: &str = "Bearer";
 const AUTHORIZATION_HEADER: &str = "Authorization";
+const TRACE_ID_HEADER: &str = "X-OpenCTI-Trace-Id";
 
 #[cynic::schema("opencti")]
 pub mod opencti {}
@@ -25,13 +37,92 @@ pub struct ApiOpenCTI {
     daemon: Daemon,
     logs_schedule: u64,
     private_key: RsaPrivateKey,
+    send_trace_header: bool,
+    max_request_bytes: Option<usize>,
+    compress_logs: bool,
+    cycle_id: Mutex<Option<String>>,
+    rate_limiter: RateLimiter,
+}
+
+// Link the request to the composer cycle in the OpenCTI audit logs
+fn with_trace_header(
+    request: reqwest::RequestBuilder,
+    trace_id: Option<String>,
+) -> reqwest::RequestBuilder {
+    match trace_id {
+        Some(trace_id) => request.header(TRACE_ID_HEADER, trace_id),
+        None => request,
+    }
+}
+
+// Gzip encoded JSON body, for the high volume mutations (connector logs)
+fn gzip_json<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, value).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn with_json_body<T: Serialize>(
+    request: reqwest::RequestBuilder,
+    value: &T,
+    compress: bool,
+) -> reqwest::RequestBuilder {
+    if !compress {
+        return request.json(value);
+    }
+    request
+        .header(CONTENT_TYPE, "application/json")
+        .header(CONTENT_ENCODING, "gzip")
+        .body(gzip_json(value))
+}
+
+// Send the operation once the platform rate limit allows it, a 429 backs off every later call.
+// Other responses are decoded the same way as cynic's ReqwestExt
+async fn send_graphql<R, V, E>(
+    request: reqwest::RequestBuilder,
+    query: Operation<R, V>,
+    rate_limiter: &RateLimiter,
+    compress: bool,
+) -> Result<GraphQlResponse<R, E>, CynicReqwestError>
+where
+    V: Serialize,
+    R: DeserializeOwned + 'static,
+    E: DeserializeOwned + 'static,
+{
+    rate_limiter.wait().await;
+    let response = with_json_body(request, &query, compress)
+        .send()
+        .await
+        .map_err(CynicReqwestError::ReqwestError)?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let delay = retry_after(response.headers(), chrono::Utc::now());
+        rate_limiter.record(delay, Instant::now());
+    }
+    if !status.is_success() {
+        let body = response
+            .text()
+            .await
+            .map_err(CynicReqwestError::ReqwestError)?;
+        return serde_json::from_str(&body)
+            .map_err(|_| CynicReqwestError::ErrorResponse(status, body));
+    }
+    response
+        .json()
+        .await
+        .map_err(CynicReqwestError::ReqwestError)
 }
 
 impl ApiOpenCTI {
     pub fn new() -> Self {
         let settings = crate::settings();
-        let bearer = format!("{} {}", BEARER, settings.opencti.token);
-        let api_uri = format!("{}/graphql", &settings.opencti.url);
+        let token = resolve_token(
+            &settings.opencti.token,
+            settings.opencti.token_filepath.as_deref(),
+        )
+        .unwrap_or_else(|e| panic!("Failed to read token for platform 'opencti': {}", e));
+        let bearer = format!("{} {}", BEARER, token);
+        let api_uri = api_uri(&settings.opencti.url, &settings.opencti.graphql_path);
         let daemon = settings.opencti.daemon.clone();
         let logs_schedule = settings.opencti.logs_schedule;
         // Use the singleton private key
@@ -44,6 +135,10 @@ impl ApiOpenCTI {
             with_proxy: settings.opencti.with_proxy,
             http_proxy: settings.opencti.http_proxy.clone(),
             https_proxy: settings.opencti.https_proxy.clone(),
+            pool_max_idle_per_host: settings.opencti.pool_max_idle_per_host,
+            pool_idle_timeout: settings.opencti.pool_idle_timeout,
+            compression: settings.opencti.compression,
+            user_agent: settings.manager.user_agent(),
             platform_name: "opencti".into(),
         })
         .unwrap_or_else(|e| panic!("Failed to build HTTP client for platform 'opencti': {}", e));
@@ -54,10 +149,24 @@ impl ApiOpenCTI {
             bearer,
             daemon,
             logs_schedule,
-            private_key
+            private_key,
+            send_trace_header: settings.opencti.send_trace_header,
+            max_request_bytes: settings.opencti.max_request_bytes,
+            compress_logs: settings.opencti.compress_logs,
+            cycle_id: Mutex::new(None),
+            rate_limiter: RateLimiter::new(
+                "opencti",
+                Duration::from_secs(settings.opencti.rate_limit_backoff),
+            ),
         }
     }
 
+    fn graphql_request(&self) -> reqwest::RequestBuilder {
+        self.http_client
+            .post(self.api_uri.clone())
+            .header(AUTHORIZATION_HEADER, self.bearer.clone().as_str())
+    }
+
     pub async fn query_fetch<R, V>(
         &self,
         query: Operation<R, V>,
@@ -66,12 +175,62 @@ impl ApiOpenCTI {
         V: Serialize,
         R: DeserializeOwned + 'static,
     {
-        use cynic::http::ReqwestExt;
-        self.http_client
-            .post(self.api_uri.clone())
-            .header(AUTHORIZATION_HEADER, self.bearer.clone().as_str())
-            .run_graphql(query)
-            .await
+        send_graphql(self.graphql_request(), query, &self.rate_limiter, false).await
+    }
+
+    // Same query, keeping the extensions (error code) of the answered errors
+    pub async fn query_fetch_with_extensions<R, V, E>(
+        &self,
+        query: Operation<R, V>,
+    ) -> Result<cynic::GraphQlResponse<R, E>, CynicReqwestError>
+    where
+        V: Serialize,
+        R: DeserializeOwned + 'static,
+        E: DeserializeOwned + 'static,
+    {
+        send_graphql(self.graphql_request(), query, &self.rate_limiter, false).await
+    }
+
+    // Connector mutations (status, logs, health), traced with the cycle id when enabled
+    pub async fn traced_fetch<R, V>(
+        &self,
+        query: Operation<R, V>,
+    ) -> Result<cynic::GraphQlResponse<R>, CynicReqwestError>
+    where
+        V: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        self.traced_send(query, false).await
+    }
+
+    // Logs mutation, compressed when enabled
+    pub async fn logs_fetch<R, V>(
+        &self,
+        query: Operation<R, V>,
+    ) -> Result<cynic::GraphQlResponse<R>, CynicReqwestError>
+    where
+        V: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        self.traced_send(query, self.compress_logs).await
+    }
+
+    async fn traced_send<R, V>(
+        &self,
+        query: Operation<R, V>,
+        compress: bool,
+    ) -> Result<cynic::GraphQlResponse<R>, CynicReqwestError>
+    where
+        V: Serialize,
+        R: DeserializeOwned + 'static,
+    {
+        let trace_id = if self.send_trace_header {
+            self.cycle_id.lock().unwrap().clone()
+        } else {
+            None
+        };
+        let request = with_trace_header(self.graphql_request(), trace_id);
+        send_graphql(request, query, &self.rate_limiter, compress).await
     }
 }
 
@@ -89,6 +248,10 @@ impl ComposerApi for ApiOpenCTI {
         Duration::from_secs(self.logs_schedule)
     }
 
+    fn set_cycle_id(&self, cycle_id: &str) {
+        *self.cycle_id.lock().unwrap() = Some(cycle_id.to_string());
+    }
+
     async fn version(&self) -> Option<String> {
         manager::get_version::version(self).await
     }
@@ -102,6 +265,10 @@ impl ComposerApi for ApiOpenCTI {
     }
 
     async fn connectors(&self) -> Option<Vec<ApiConnector>> {
+        self.listing().await.map(|listing| listing.connectors)
+    }
+
+    async fn listing(&self) -> Option<ConnectorListing> {
         connector::get_listing::list(self).await
     }
 
@@ -110,10 +277,114 @@ impl ComposerApi for ApiOpenCTI {
     }
 
     async fn patch_logs(&self, id: String, logs: Vec<String>) -> Option<String> {
-        connector::post_logs::logs(id, logs, self).await
+        connector::post_logs::logs(id, logs, self.max_request_bytes, self).await
     }
 
     async fn patch_health(&self, id: String, restart_count: u32, started_at: String, is_in_reboot_loop: bool) -> Option<String> {
         connector::post_health::health(id, restart_count, started_at, is_in_reboot_loop, self).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(request: reqwest::RequestBuilder) -> Option<String> {
+        request
+            .build()
+            .unwrap()
+            .headers()
+            .get(TRACE_ID_HEADER)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn trace_header_is_set_with_the_cycle_id() {
+        let client = reqwest::Client::new();
+        let request = client.post("http://opencti.local/graphql");
+
+        let traced = with_trace_header(request, Some("manager-1-20250101T100000000Z".to_string()));
+
+        assert_eq!(
+            header(traced),
+            Some("manager-1-20250101T100000000Z".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limited_response_backs_off_with_retry_after() {
+        use crate::api::opencti::manager::get_version::GetVersion;
+        use cynic::QueryBuilder;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let _ = stream.read(&mut buffer).await;
+            let body = "Too Many Requests";
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        let rate_limiter = RateLimiter::new("opencti", Duration::from_secs(30));
+        let request = reqwest::Client::new().post(format!("http://{}/graphql", address));
+
+        let result: Result<GraphQlResponse<_>, _> =
+            send_graphql(request, GetVersion::build({}), &rate_limiter, false).await;
+
+        match result {
+            Err(CynicReqwestError::ErrorResponse(status, _)) => {
+                assert_eq!(status, StatusCode::TOO_MANY_REQUESTS)
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        // Retry-After wins over the default backoff
+        let remaining = rate_limiter.remaining(Instant::now()).unwrap();
+        assert!(remaining > Duration::from_secs(110), "{remaining:?}");
+    }
+
+    #[test]
+    fn logs_body_is_gzipped_when_enabled() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let logs = serde_json::json!({ "logs": vec!["ERROR connector failed"; 200] });
+        let client = reqwest::Client::new();
+        let request = with_json_body(client.post("http://opencti.local/graphql"), &logs, true)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.headers()[CONTENT_ENCODING], "gzip");
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/json");
+        let body = request.body().unwrap().as_bytes().unwrap();
+        assert!(body.len() < serde_json::to_vec(&logs).unwrap().len());
+        let mut decoded = String::new();
+        GzDecoder::new(body).read_to_string(&mut decoded).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&decoded).unwrap(),
+            logs
+        );
+
+        let plain = with_json_body(client.post("http://opencti.local/graphql"), &logs, false)
+            .build()
+            .unwrap();
+        assert!(plain.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(
+            plain.body().unwrap().as_bytes().unwrap(),
+            serde_json::to_vec(&logs).unwrap()
+        );
+    }
+
+    #[test]
+    fn trace_header_is_absent_without_cycle_id() {
+        let client = reqwest::Client::new();
+        let request = client.post("http://opencti.local/graphql");
+        assert_eq!(header(with_trace_header(request, None)), None);
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// Counter xtm_api_rate_limited_total: platform requests rejected with a 429
+static API_RATE_LIMITED: AtomicU64 = AtomicU64::new(0);
+
+// A Retry-After above this many default backoffs would freeze the orchestration, it is clamped
+const MAX_RETRY_AFTER_FACTOR: u32 = 10;
+
+// Retry-After as delay seconds or HTTP date, None when absent or unreadable
+pub fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((date - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+// Holds every call to a platform once it answered 429, until its Retry-After is over
+#[derive(Debug)]
+pub struct RateLimiter {
+    platform: &'static str,
+    default_backoff: Duration,
+    until: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(platform: &'static str, default_backoff: Duration) -> Self {
+        Self {
+            platform,
+            default_backoff,
+            until: Mutex::new(None),
+        }
+    }
+
+    // Time left before the next call is allowed
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.until
+            .lock()
+            .unwrap()
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    pub async fn wait(&self) {
+        if let Some(remaining) = self.remaining(Instant::now()) {
+            tokio::time::sleep(remaining).await;
+        }
+    }
+
+    // Backoff applied for a 429, the default one when the platform gives no Retry-After
+    pub fn record(&self, retry_after: Option<Duration>, now: Instant) -> Duration {
+        let max_backoff = self.default_backoff * MAX_RETRY_AFTER_FACTOR;
+        let backoff = match retry_after {
+            Some(retry_after) if retry_after > max_backoff => {
+                warn!(
+                    platform = self.platform,
+                    retry_after_secs = retry_after.as_secs(),
+                    max_backoff_secs = max_backoff.as_secs(),
+                    "Platform Retry-After too long, clamping it"
+                );
+                max_backoff
+            }
+            Some(retry_after) => retry_after,
+            None => self.default_backoff,
+        };
+        let total = API_RATE_LIMITED.fetch_add(1, Ordering::Relaxed) + 1;
+        warn!(
+            platform = self.platform,
+            backoff_secs = backoff.as_secs(),
+            xtm_api_rate_limited_total = total,
+            "Platform rate limit reached, backing off"
+        );
+        let mut until = self.until.lock().unwrap();
+        let deadline = now + backoff;
+        // Never shorten a backoff already in progress
+        if until.is_none_or(|current| current < deadline) {
+            *until = Some(deadline);
+        }
+        backoff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            retry_after(&headers("120"), now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:29:30 GMT"), now),
+            Some(Duration::from_secs(90))
+        );
+        // A date already passed means no wait
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:00:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers("soon"), now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn backoff_holds_calls_until_over() {
+        let limiter = RateLimiter::new("opencti", Duration::from_secs(30));
+        let now = Instant::now();
+        assert_eq!(limiter.remaining(now), None);
+
+        assert_eq!(limiter.record(None, now), Duration::from_secs(30));
+        assert_eq!(limiter.remaining(now), Some(Duration::from_secs(30)));
+
+        // A shorter Retry-After does not cut the current backoff
+        limiter.record(Some(Duration::from_secs(5)), now);
+        assert_eq!(
+            limiter.remaining(now + Duration::from_secs(10)),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(limiter.remaining(now + Duration::from_secs(30)), None);
+        assert!(API_RATE_LIMITED.load(Ordering::Relaxed) >= 2);
+    }
+
+    #[test]
+    fn excessive_retry_after_is_clamped() {
+        let limiter = RateLimiter::new("opencti", Duration::from_secs(30));
+        let now = Instant::now();
+
+        // A day long Retry-After would stop every orchestration cycle
+        assert_eq!(
+            limiter.record(Some(Duration::from_secs(86400)), now),
+            Duration::from_secs(300)
+        );
+        assert_eq!(limiter.remaining(now), Some(Duration::from_secs(300)));
+        assert_eq!(
+            limiter.record(Some(Duration::from_secs(120)), now),
+            Duration::from_secs(120)
+        );
+    }
+}
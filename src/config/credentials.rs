@@ -0,0 +1,156 @@
+use crate::config::settings::{CredentialsKeySecret, Kubernetes};
+use crate::orchestrator::kubernetes::kubernetes::client_config;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client};
+use std::fs;
+use tracing::warn;
+
+#[derive(Debug, PartialEq)]
+pub enum CredentialsKeySource<'a> {
+    Secret(&'a CredentialsKeySecret),
+    File(&'a str),
+    Value(&'a str),
+}
+
+// Priority: kubernetes secret (kubernetes selector only) > file > environment variable
+pub fn credentials_key_source<'a>(
+    secret: Option<&'a CredentialsKeySecret>,
+    filepath: Option<&'a str>,
+    key: Option<&'a str>,
+    kubernetes: bool,
+) -> Option<CredentialsKeySource<'a>> {
+    let secret = match secret {
+        Some(secret) if kubernetes => Some(secret),
+        Some(_) => {
+            warn!(
+                "credentials_key_secret is only supported with the kubernetes selector, ignoring it."
+            );
+            None
+        }
+        None => None,
+    };
+    let configured = [secret.is_some(), filepath.is_some(), key.is_some()]
+        .iter()
+        .filter(|set| **set)
+        .count();
+    if configured > 1 {
+        warn!(
+            "Several credentials key sources are set. Using the first one by priority (secret > filepath > key)."
+        );
+    }
+    secret
+        .map(CredentialsKeySource::Secret)
+        .or(filepath.map(CredentialsKeySource::File))
+        .or(key.map(CredentialsKeySource::Value))
+}
+
+// Priority: file (mounted secret) > environment variable / config value
+pub fn resolve_token(token: &str, token_filepath: Option<&str>) -> Result<String, String> {
+    match token_filepath {
+        Some(filepath) => {
+            let content = fs::read_to_string(filepath)
+                .map_err(|err| format!("unable to read token file '{filepath}': {err}"))?;
+            let token = content.trim();
+            if token.is_empty() {
+                return Err(format!("token file '{filepath}' is empty"));
+            }
+            Ok(token.to_string())
+        }
+        None => Ok(token.to_string()),
+    }
+}
+
+// Read the credentials key from a kubernetes secret with the client of the kubernetes orchestrator
+pub async fn fetch_credentials_key_secret(
+    secret: &CredentialsKeySecret,
+    config: &Kubernetes,
+) -> Result<String, String> {
+    let client = Client::try_from(client_config(config).await?)
+        .map_err(|err| format!("unable to create kubernetes client: {err}"))?;
+    let secrets: Api<Secret> = match &secret.namespace {
+        Some(namespace) => Api::namespaced(client, namespace),
+        None => Api::default_namespaced(client),
+    };
+    let fetched = secrets
+        .get(&secret.name)
+        .await
+        .map_err(|err| format!("unable to read secret '{}': {err}", secret.name))?;
+    let value = fetched
+        .data
+        .and_then(|mut data| data.remove(&secret.key))
+        .ok_or_else(|| format!("key '{}' not found in secret '{}'", secret.key, secret.name))?;
+    String::from_utf8(value.0)
+        .map_err(|err| format!("key '{}' is not valid UTF-8: {err}", secret.key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret() -> CredentialsKeySecret {
+        CredentialsKeySecret {
+            namespace: Some("opencti".to_string()),
+            name: "xtm-composer-credentials".to_string(),
+            key: "private_key.pem".to_string(),
+        }
+    }
+
+    #[test]
+    fn secret_has_priority_on_kubernetes() {
+        let secret = secret();
+        let source = credentials_key_source(Some(&secret), Some("/key.pem"), Some("key"), true);
+        assert_eq!(source, Some(CredentialsKeySource::Secret(&secret)));
+    }
+
+    #[test]
+    fn secret_is_ignored_without_kubernetes() {
+        let secret = secret();
+        let source = credentials_key_source(Some(&secret), Some("/key.pem"), Some("key"), false);
+        assert_eq!(source, Some(CredentialsKeySource::File("/key.pem")));
+
+        let source = credentials_key_source(Some(&secret), None, Some("key"), false);
+        assert_eq!(source, Some(CredentialsKeySource::Value("key")));
+    }
+
+    #[test]
+    fn filepath_has_priority_over_value() {
+        let source = credentials_key_source(None, Some("/key.pem"), Some("key"), true);
+        assert_eq!(source, Some(CredentialsKeySource::File("/key.pem")));
+    }
+
+    #[test]
+    fn value_is_used_as_last_resort() {
+        let source = credentials_key_source(None, None, Some("key"), true);
+        assert_eq!(source, Some(CredentialsKeySource::Value("key")));
+    }
+
+    #[test]
+    fn token_file_has_priority_over_value() {
+        let path = std::env::temp_dir().join(format!("xtm-composer-token-{}", std::process::id()));
+        fs::write(&path, "file-token\n").unwrap();
+        let token = resolve_token("inline-token", path.to_str());
+        let _ = fs::remove_file(&path);
+        assert_eq!(token, Ok("file-token".to_string()));
+
+        assert_eq!(
+            resolve_token("inline-token", None),
+            Ok("inline-token".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_token_file_is_an_error() {
+        let error =
+            resolve_token("inline-token", Some("/nonexistent/xtm-composer/token")).unwrap_err();
+        assert!(error.contains("/nonexistent/xtm-composer/token"), "{error}");
+    }
+
+    #[test]
+    fn no_source_configured() {
+        assert_eq!(credentials_key_source(None, None, None, true), None);
+        assert_eq!(
+            credentials_key_source(Some(&secret()), None, None, false),
+            None
+        );
+    }
+}
@@ -1 +1,3 @@
+pub mod credentials;
+pub mod overrides;
 pub mod settings;
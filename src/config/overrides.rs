@@ -0,0 +1,259 @@
+use crate::config::settings::Manager;
+use config::{Config, ConfigError, File};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+// Per connector maps of the manager section, also accepted from the manager.overrides_path file
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct ConnectorOverrides {
+    pub image_override: Option<HashMap<String, String>>,
+    pub connector_hostnames: Option<HashMap<String, String>>,
+    pub connector_log_patterns: Option<HashMap<String, String>>,
+}
+
+// Entries of the file win over the same connector ids of the manager section
+fn merge(
+    configured: Option<&HashMap<String, String>>,
+    file: Option<HashMap<String, String>>,
+) -> Option<HashMap<String, String>> {
+    match (configured, file) {
+        (None, file) => file,
+        (Some(configured), None) => Some(configured.clone()),
+        (Some(configured), Some(file)) => {
+            let mut merged = configured.clone();
+            merged.extend(file);
+            Some(merged)
+        }
+    }
+}
+
+impl ConnectorOverrides {
+    pub fn from_manager(manager: &Manager) -> Self {
+        Self {
+            image_override: manager.image_override.clone(),
+            connector_hostnames: manager.connector_hostnames.clone(),
+            connector_log_patterns: manager.connector_log_patterns.clone(),
+        }
+    }
+
+    // Overrides file in any format of the configuration (yaml, json or toml, from its extension)
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        Config::builder()
+            .add_source(File::from(path))
+            .build()?
+            .try_deserialize()
+    }
+
+    fn merged(manager: &Manager, file: Self) -> Self {
+        Self {
+            image_override: merge(manager.image_override.as_ref(), file.image_override),
+            connector_hostnames: merge(
+                manager.connector_hostnames.as_ref(),
+                file.connector_hostnames,
+            ),
+            connector_log_patterns: merge(
+                manager.connector_log_patterns.as_ref(),
+                file.connector_log_patterns,
+            ),
+        }
+    }
+
+    // Regex extracting the timestamp / level / message groups of the connector log lines
+    pub fn log_pattern(&self, connector_id: &str) -> Option<&str> {
+        self.connector_log_patterns
+            .as_ref()
+            .and_then(|patterns| patterns.get(connector_id))
+            .map(String::as_str)
+    }
+}
+
+// Overrides in effect, swapped on each successful reload and read by the next cycle
+#[derive(Debug, Default)]
+pub struct OverridesStore {
+    current: RwLock<Option<Arc<ConnectorOverrides>>>,
+}
+
+impl OverridesStore {
+    pub const fn new() -> Self {
+        Self {
+            current: RwLock::new(None),
+        }
+    }
+
+    // The manager section alone until a file is loaded
+    pub fn current(&self, manager: &Manager) -> Arc<ConnectorOverrides> {
+        if let Some(current) = self.current.read().unwrap().as_ref() {
+            return current.clone();
+        }
+        self.current
+            .write()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(ConnectorOverrides::from_manager(manager)))
+            .clone()
+    }
+
+    // A missing or malformed file keeps the overrides in effect
+    pub fn reload(&self, manager: &Manager, path: &Path) -> bool {
+        match ConnectorOverrides::load(path) {
+            Ok(file) => {
+                let overrides = ConnectorOverrides::merged(manager, file);
+                *self.current.write().unwrap() = Some(Arc::new(overrides));
+                info!(path = %path.display(), "Connector overrides loaded");
+                true
+            }
+            Err(err) => {
+                warn!(
+                    path = %path.display(),
+                    error = err.to_string(),
+                    "Unable to load the connector overrides, keeping the current ones"
+                );
+                false
+            }
+        }
+    }
+}
+
+static OVERRIDES: OverridesStore = OverridesStore::new();
+
+pub fn current() -> Arc<ConnectorOverrides> {
+    OVERRIDES.current(&crate::settings().manager)
+}
+
+pub fn reload(path: &Path) -> bool {
+    OVERRIDES.reload(&crate::settings().manager, path)
+}
+
+// Reload the overrides file on every interval (zero for SIGHUP only) and on SIGHUP
+pub async fn watch(path: String, period: Duration) {
+    let mut reload_signal = crate::system::signals::ReloadSignal::install();
+    // Loaded at startup already, the first reload waits a full period
+    let mut interval = (!period.is_zero())
+        .then(|| tokio::time::interval_at(tokio::time::Instant::now() + period, period));
+    loop {
+        let tick = async {
+            match interval.as_mut() {
+                Some(interval) => {
+                    interval.tick().await;
+                }
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = tick => {}
+            _ = reload_signal.recv() => {
+                info!(path, "SIGHUP received, reloading the connector overrides");
+            }
+        }
+        reload(Path::new(&path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn manager() -> Manager {
+        let mut manager = crate::settings().manager.clone();
+        manager.image_override = Some(HashMap::from([
+            (
+                "connector-1".to_string(),
+                "opencti/connector-misp:configured".to_string(),
+            ),
+            (
+                "connector-2".to_string(),
+                "opencti/connector-otx:configured".to_string(),
+            ),
+        ]));
+        manager.connector_hostnames = None;
+        manager.connector_log_patterns = None;
+        manager
+    }
+
+    fn overrides_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "xtm-composer-overrides-{name}-{}.yaml",
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn file_entries_win_over_the_manager_section() {
+        let path = overrides_file(
+            "load",
+            "image_override:\n  connector-1: opencti/connector-misp:patched\n\
+             connector_hostnames:\n  connector-1: misp-primary\n",
+        );
+        let store = OverridesStore::new();
+        let manager = manager();
+        assert_eq!(
+            *store.current(&manager),
+            ConnectorOverrides::from_manager(&manager)
+        );
+
+        assert!(store.reload(&manager, &path));
+        let _ = fs::remove_file(&path);
+        let current = store.current(&manager);
+        let images = current.image_override.as_ref().unwrap();
+        assert_eq!(images["connector-1"], "opencti/connector-misp:patched");
+        assert_eq!(images["connector-2"], "opencti/connector-otx:configured");
+        assert_eq!(
+            current.connector_hostnames.as_ref().unwrap()["connector-1"],
+            "misp-primary"
+        );
+        assert_eq!(current.log_pattern("connector-1"), None);
+    }
+
+    #[test]
+    fn reload_applies_the_new_file_content() {
+        let path = overrides_file(
+            "reload",
+            "connector_log_patterns:\n  connector-1: '^(?P<message>.*)$'\n",
+        );
+        let store = OverridesStore::new();
+        let manager = manager();
+        assert!(store.reload(&manager, &path));
+        assert_eq!(
+            store.current(&manager).log_pattern("connector-1"),
+            Some("^(?P<message>.*)$")
+        );
+
+        fs::write(
+            &path,
+            "connector_log_patterns:\n  connector-2: '^(?P<level>[A-Z]+) (?P<message>.*)$'\n",
+        )
+        .unwrap();
+        assert!(store.reload(&manager, &path));
+        let _ = fs::remove_file(&path);
+        let current = store.current(&manager);
+        assert_eq!(current.log_pattern("connector-1"), None);
+        assert!(current.log_pattern("connector-2").is_some());
+    }
+
+    #[test]
+    fn malformed_or_missing_file_keeps_the_current_overrides() {
+        let path = overrides_file("malformed", "connector_hostnames:\n  connector-1: misp\n");
+        let store = OverridesStore::new();
+        let manager = manager();
+        assert!(store.reload(&manager, &path));
+
+        fs::write(&path, "connector_hostnames: [not, a, map\n").unwrap();
+        assert!(!store.reload(&manager, &path));
+        let _ = fs::remove_file(&path);
+        assert!(!store.reload(&manager, &path));
+        assert_eq!(
+            store
+                .current(&manager)
+                .connector_hostnames
+                .as_ref()
+                .unwrap()["connector-1"],
+            "misp"
+        );
+    }
+}
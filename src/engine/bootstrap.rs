@@ -0,0 +1,155 @@
+use crate::api::ComposerApi;
+use crate::engine::status;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Retry delays of the version bootstrap, doubling from the initial delay up to the max one
+#[derive(Debug, Clone, Copy)]
+pub struct BootstrapRetry {
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl BootstrapRetry {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            max_delay: max_delay.max(initial_delay),
+        }
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.initial_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay)
+    }
+}
+
+// Fetch the platform version until it answers, each failed attempt is reported with its retry delay
+pub async fn bootstrap_version(
+    api: &(dyn ComposerApi + Send + Sync),
+    retry: BootstrapRetry,
+) -> String {
+    let mut attempt: u32 = 0;
+    loop {
+        attempt += 1;
+        if let Some(version) = api.version().await {
+            if attempt > 1 {
+                info!(
+                    platform = api.platform(),
+                    attempts = attempt,
+                    version,
+                    "Platform reachable"
+                );
+            }
+            return version;
+        }
+        let delay = retry.delay(attempt);
+        warn!(
+            platform = api.platform(),
+            attempt,
+            retry_in_secs = delay.as_secs(),
+            xtm_seconds_since_last_successful_ping =
+                status::seconds_since_last_successful_ping(api.platform(), Instant::now()),
+            "Platform unreachable, unable to fetch its version"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ApiConnector, ConnectorStatus};
+    use crate::config::settings::Daemon;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // Platform answering its version after a number of failures
+    struct FlakyVersionApi {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl ComposerApi for FlakyVersionApi {
+        fn daemon(&self) -> &Daemon {
+            unimplemented!()
+        }
+
+        fn platform(&self) -> &'static str {
+            "openaev"
+        }
+
+        fn post_logs_schedule(&self) -> Duration {
+            Duration::from_secs(3600)
+        }
+
+        async fn version(&self) -> Option<String> {
+            let calls = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+            (calls > self.failures).then(|| "1.18.0".to_string())
+        }
+
+        async fn ping_alive(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn register(&self) {
+            unimplemented!()
+        }
+
+        async fn connectors(&self) -> Option<Vec<ApiConnector>> {
+            unimplemented!()
+        }
+
+        async fn patch_status(
+            &self,
+            _id: String,
+            _status: ConnectorStatus,
+        ) -> Option<ApiConnector> {
+            unimplemented!()
+        }
+
+        async fn patch_logs(&self, _id: String, _logs: Vec<String>) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn patch_health(
+            &self,
+            _id: String,
+            _restart_count: u32,
+            _started_at: String,
+            _is_in_reboot_loop: bool,
+        ) -> Option<String> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_max() {
+        let retry = BootstrapRetry::new(Duration::from_secs(5), Duration::from_secs(60));
+        assert_eq!(retry.delay(1), Duration::from_secs(5));
+        assert_eq!(retry.delay(2), Duration::from_secs(10));
+        assert_eq!(retry.delay(4), Duration::from_secs(40));
+        assert_eq!(retry.delay(5), Duration::from_secs(60));
+        assert_eq!(retry.delay(100), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn version_is_retried_until_the_platform_answers() {
+        let api = FlakyVersionApi {
+            failures: 3,
+            calls: AtomicU32::new(0),
+        };
+        let retry = BootstrapRetry::new(Duration::from_millis(5), Duration::from_millis(20));
+
+        let started = Instant::now();
+        let version = bootstrap_version(&api, retry).await;
+
+        assert_eq!(version, "1.18.0");
+        assert_eq!(api.calls.load(Ordering::Relaxed), 4);
+        // 5 + 10 + 20 ms of backoff between the attempts
+        assert!(started.elapsed() >= Duration::from_millis(35));
+    }
+}
@@ -0,0 +1,88 @@
+// Consecutive orchestrator backend failures, opened after the threshold to slow the
+// orchestration loop down until the backend answers again (a zero threshold disables it)
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    consecutive_failures: u32,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.threshold > 0 && self.consecutive_failures >= self.threshold
+    }
+
+    // Returns true when this failure opens the breaker
+    pub fn record_failure(&mut self) -> bool {
+        let was_open = self.is_open();
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        !was_open && self.is_open()
+    }
+
+    // Returns true when the backend recovers from an open breaker
+    pub fn record_success(&mut self) -> bool {
+        let was_open = self.is_open();
+        self.consecutive_failures = 0;
+        was_open
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_opens_once_after_threshold() {
+        let mut breaker = CircuitBreaker::new(3);
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(!breaker.is_open());
+
+        assert!(breaker.record_failure());
+        assert!(breaker.is_open());
+        // Already open, not reported again
+        assert!(!breaker.record_failure());
+        assert_eq!(breaker.consecutive_failures(), 4);
+    }
+
+    #[test]
+    fn success_closes_the_breaker() {
+        let mut breaker = CircuitBreaker::new(2);
+        breaker.record_failure();
+        breaker.record_failure();
+
+        assert!(breaker.record_success());
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.consecutive_failures(), 0);
+        // Nothing to recover from
+        assert!(!breaker.record_success());
+    }
+
+    #[test]
+    fn success_resets_the_consecutive_failures() {
+        let mut breaker = CircuitBreaker::new(2);
+        breaker.record_failure();
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn zero_threshold_never_opens() {
+        let mut breaker = CircuitBreaker::new(0);
+        for _ in 0..10 {
+            assert!(!breaker.record_failure());
+        }
+        assert!(!breaker.is_open());
+    }
+}
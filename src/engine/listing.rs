@@ -0,0 +1,194 @@
+use crate::api::openaev::ApiOpenAEV;
+use crate::api::opencti::ApiOpenCTI;
+use crate::api::{ApiConnector, ComposerApi, REDACTED_VALUE};
+use crate::settings;
+use tracing::error;
+
+const HEADERS: [&str; 6] = ["ID", "NAME", "IMAGE", "REQUESTED", "CURRENT", "CONFIGURATION"];
+
+fn connector_row(connector: &ApiConnector) -> [String; 6] {
+    let configuration = connector
+        .contract_configuration
+        .iter()
+        .map(|config| {
+            let value = if config.is_sensitive {
+                REDACTED_VALUE
+            } else {
+                config.value.as_str()
+            };
+            format!("{}={}", config.key, value)
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    [
+        connector.id.clone(),
+        connector.name.clone(),
+        connector.image.clone(),
+        connector.requested_status.clone(),
+        connector.current_status.clone().unwrap_or("-".into()),
+        configuration,
+    ]
+}
+
+// Render connectors as an aligned text table, sensitive configuration values redacted
+pub fn render_connectors_table(connectors: &[ApiConnector]) -> String {
+    let rows: Vec<[String; 6]> = connectors.iter().map(connector_row).collect();
+    let mut widths = HEADERS.map(|header| header.len());
+    for row in &rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.len());
+        }
+    }
+    let format_line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| format!("{:width$}", cell, width = widths[index]))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![format_line(HEADERS.to_vec())];
+    for row in &rows {
+        lines.push(format_line(row.iter().map(|cell| cell.as_str()).collect()));
+    }
+    lines.join("\n")
+}
+
+pub async fn list_platform_connectors(api: &(dyn ComposerApi + Send + Sync)) -> Option<String> {
+    match api.connectors().await {
+        Some(connectors) => Some(render_connectors_table(&connectors)),
+        None => {
+            error!(platform = api.platform(), "Unable to list connectors");
+            None
+        }
+    }
+}
+
+// Diagnostic mode, print the connectors of every enabled platform and return
+pub async fn list_connectors() {
+    let settings = settings();
+    let mut apis: Vec<Box<dyn ComposerApi + Send + Sync>> = Vec::new();
+    if settings.opencti.enable {
+        apis.push(Box::new(ApiOpenCTI::new()));
+    }
+    if settings.openaev.enable {
+        apis.push(Box::new(ApiOpenAEV::new()));
+    }
+    for api in apis {
+        if let Some(table) = list_platform_connectors(api.as_ref()).await {
+            println!("[{}]\n{}\n", api.platform(), table);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ApiContractConfig, ConnectorStatus};
+    use crate::config::settings::Daemon;
+    use std::time::Duration;
+
+    struct MockApi {
+        connectors: Option<Vec<ApiConnector>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ComposerApi for MockApi {
+        fn daemon(&self) -> &Daemon {
+            unimplemented!()
+        }
+
+        fn platform(&self) -> &'static str {
+            "opencti"
+        }
+
+        fn post_logs_schedule(&self) -> Duration {
+            Duration::from_secs(3600)
+        }
+
+        async fn version(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn ping_alive(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn register(&self) -> () {
+            unimplemented!()
+        }
+
+        async fn connectors(&self) -> Option<Vec<ApiConnector>> {
+            self.connectors.clone()
+        }
+
+        async fn patch_status(&self, _id: String, _status: ConnectorStatus) -> Option<ApiConnector> {
+            unimplemented!()
+        }
+
+        async fn patch_logs(&self, _id: String, _logs: Vec<String>) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn patch_health(
+            &self,
+            _id: String,
+            _restart_count: u32,
+            _started_at: String,
+            _is_in_reboot_loop: bool,
+        ) -> Option<String> {
+            unimplemented!()
+        }
+    }
+
+    fn connector() -> ApiConnector {
+        ApiConnector {
+            id: "connector-1".to_string(),
+            platform: "opencti".to_string(),
+            name: "MISP".to_string(),
+            image: "opencti/connector-misp:6.8.0".to_string(),
+            contract_hash: "hash-1".to_string(),
+            current_status: Some("started".to_string()),
+            requested_status: "starting".to_string(),
+            contract_configuration: vec![
+                ApiContractConfig {
+                    key: "MISP_URL".to_string(),
+                    value: "https://misp.local".to_string(),
+                    is_sensitive: false,
+                },
+                ApiContractConfig {
+                    key: "MISP_KEY".to_string(),
+                    value: "super-secret".to_string(),
+                    is_sensitive: true,
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn list_connectors_renders_table_with_redacted_values() {
+        let api = MockApi {
+            connectors: Some(vec![connector()]),
+        };
+
+        let table = list_platform_connectors(&api).await.unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID"));
+        for expected in ["connector-1", "MISP", "opencti/connector-misp:6.8.0", "starting", "started"] {
+            assert!(lines[1].contains(expected), "missing {expected}: {}", lines[1]);
+        }
+        assert!(lines[1].contains("MISP_URL=https://misp.local"));
+        assert!(lines[1].contains(&format!("MISP_KEY={REDACTED_VALUE}")));
+        assert!(!table.contains("super-secret"), "sensitive value leaked: {table}");
+    }
+
+    #[tokio::test]
+    async fn list_connectors_returns_none_when_api_fails() {
+        let api = MockApi { connectors: None };
+        assert!(list_platform_connectors(&api).await.is_none());
+    }
+}
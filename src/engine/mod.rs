@@ -1,17 +1,33 @@
+pub mod bootstrap;
+pub mod breaker;
+pub mod listing;
 pub mod openaev;
 pub mod opencti;
+pub mod register;
+pub mod status;
 
 use crate::api::ComposerApi;
+use crate::config::settings::Settings;
+use crate::engine::bootstrap::{BootstrapRetry, bootstrap_version};
+use crate::engine::breaker::CircuitBreaker;
+use crate::engine::register::RegisterThrottle;
+use crate::orchestrator::composer::OrchestrationState;
 use crate::orchestrator::docker::DockerOrchestrator;
 use crate::orchestrator::kubernetes::KubeOrchestrator;
+use crate::orchestrator::plan::{PlanApi, PlanOrchestrator, ReconcilePlan, emit_plan};
 use crate::orchestrator::portainer::docker::PortainerDockerOrchestrator;
 use crate::orchestrator::swarm::SwarmOrchestrator;
+use crate::orchestrator::timeout::TimeoutOrchestrator;
 use crate::orchestrator::{Orchestrator, composer};
 use crate::settings;
 use crate::system::signals;
+use chrono::Utc;
+use std::env;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 use tokio::time::interval;
+use tracing::{debug, error, info, warn};
 
 async fn orchestration(api: Box<dyn ComposerApi + Send + Sync>) {
     let settings = settings();
@@ -37,27 +53,183 @@ async fn orchestration(api: Box<dyn ComposerApi + Send + Sync>) {
             },
             def => panic!("Invalid daemon configuration: {}", def),
         };
-    // Init scheduler interval
-    let mut interval = interval(Duration::from_secs(settings.manager.execute_schedule));
+    // Bound every backend operation so a misbehaving orchestrator can't freeze the loop
+    let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(
+        TimeoutOrchestrator::new(
+            orchestrator,
+            Duration::from_secs(settings.manager.operation_timeout),
+        )
+        .with_deploy_timeout(Duration::from_secs(settings.manager.deploy_timeout())),
+    );
+    // Plan only, the mutations are recorded and reported instead of being performed
+    let plan = settings
+        .manager
+        .reconcile_plan
+        .then(|| Arc::new(Mutex::new(ReconcilePlan::default())));
+    let orchestrator: Box<dyn Orchestrator + Send + Sync> = match &plan {
+        Some(plan) => Box::new(PlanOrchestrator::new(orchestrator, Arc::clone(plan))),
+        None => orchestrator,
+    };
+    // Nor are the connector status, logs and health reported to the platform
+    let api: Box<dyn ComposerApi + Send + Sync> = match &plan {
+        Some(_) => Box::new(PlanApi::new(api)),
+        None => api,
+    };
+    let period = Duration::from_secs(settings.manager.execute_schedule);
+    let startup_delay = Duration::from_secs(settings.manager.startup_delay);
     // Start scheduling
     tokio::select! {
         _ = signals::handle_stop_signals() => {}
-        _ = async {
-            let mut tick = Instant::now();
-            let mut health_tick = Instant::now();
-            loop {
-                interval.tick().await; // Wait for period
-                composer::orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
-            }
+        cycles = async {
+            wait_startup_delay(api.platform(), startup_delay).await;
+            run_cycles(max_cycles(), period, &orchestrator, &api, plan.as_deref()).await
         } => {
-            // This branch will never be reached due to the infinite loop.
+            info!(cycles, "Maximum orchestration cycles reached, stopping");
         }
     }
 }
 
+// Leave the backend and the platform time to come up before the first cycle
+async fn wait_startup_delay(platform: &str, startup_delay: Duration) {
+    if !startup_delay.is_zero() {
+        info!(
+            platform,
+            delay_secs = startup_delay.as_secs(),
+            "Delaying the first orchestration cycle"
+        );
+        tokio::time::sleep(startup_delay).await;
+    }
+}
+
+// Both orchestrations run with the same manager id, unlabelled containers are claimed by both
+fn platforms_overlap(opencti_enabled: bool, openaev_enabled: bool, exclusive: bool) -> bool {
+    opencti_enabled && openaev_enabled && !exclusive
+}
+
+// False when the startup must be refused, a shared manager id with refuse_shared_manager_id
+fn platform_exclusion_allowed(settings: &Settings) -> bool {
+    if !platforms_overlap(
+        settings.opencti.enable,
+        settings.openaev.enable,
+        settings.manager.exclusive_platforms,
+    ) {
+        return true;
+    }
+    error!(
+        manager_id = settings.manager.id,
+        refused = settings.manager.refuse_shared_manager_id,
+        "OpenCTI and OpenAEV share the manager id, containers without the opencti-platform \
+         label are managed by both platforms. Enable manager.exclusive_platforms once every \
+         container carries the label"
+    );
+    !settings.manager.refuse_shared_manager_id
+}
+
+pub fn check_platform_exclusion() -> bool {
+    platform_exclusion_allowed(settings())
+}
+
+// One-shot runs (CI, Kubernetes Job) stop after a given number of cycles
+fn max_cycles() -> Option<u64> {
+    if env::args().any(|arg| arg == crate::ONCE_FLAG) {
+        Some(1)
+    } else {
+        settings().manager.max_cycles
+    }
+}
+
+// Unique per manager, sent along the platform mutations of the cycle
+fn cycle_id(manager_id: &str) -> String {
+    format!("{}-{}", manager_id, Utc::now().format("%Y%m%dT%H%M%S%3fZ"))
+}
+
+// Check the orchestrator backend before a cycle, reporting only the breaker transitions
+async fn backend_available(
+    breaker: &mut CircuitBreaker,
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    backoff_period: Duration,
+) -> bool {
+    match orchestrator.ping().await {
+        Ok(()) => {
+            if breaker.record_success() {
+                info!("Orchestrator backend recovered, resuming normal cadence");
+            }
+            true
+        }
+        Err(err) => {
+            if breaker.record_failure() {
+                error!(
+                    error = err,
+                    failures = breaker.consecutive_failures(),
+                    backoff = backoff_period.as_secs(),
+                    "Orchestrator backend unavailable, backing off the orchestration loop"
+                );
+            } else if breaker.is_open() {
+                debug!(
+                    error = err,
+                    failures = breaker.consecutive_failures(),
+                    "Orchestrator backend still unavailable"
+                );
+            } else {
+                warn!(
+                    error = err,
+                    failures = breaker.consecutive_failures(),
+                    "Orchestrator backend unavailable, skipping cycle"
+                );
+            }
+            false
+        }
+    }
+}
+
+// Orchestration loop, endless unless a maximum number of cycles is given
+async fn run_cycles(
+    max_cycles: Option<u64>,
+    period: Duration,
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    api: &Box<dyn ComposerApi + Send + Sync>,
+    plan: Option<&Mutex<ReconcilePlan>>,
+) -> u64 {
+    // Init scheduler interval
+    let mut interval = interval(period);
+    let mut state = OrchestrationState::new(&settings().manager, Instant::now());
+    let mut breaker = CircuitBreaker::new(settings().manager.backend_failure_threshold);
+    let backoff_period = Duration::from_secs(settings().manager.backend_backoff_interval);
+    let mut cycles: u64 = 0;
+    while max_cycles.is_none_or(|max_cycles| cycles < max_cycles) {
+        interval.tick().await; // Wait for period
+        if !backend_available(&mut breaker, orchestrator, backoff_period).await {
+            cycles += 1;
+            if breaker.is_open() {
+                tokio::time::sleep(backoff_period).await;
+                interval.reset();
+            }
+            continue;
+        }
+        let cycle_start = Instant::now();
+        api.set_cycle_id(&cycle_id(&settings().manager.id));
+        composer::orchestrate(&mut state, orchestrator, api).await;
+        status::record_cycle(api.platform(), cycle_start.elapsed());
+        if let Some(plan) = plan {
+            let cycle_plan = std::mem::take(&mut *plan.lock().unwrap());
+            let plan_file = settings().manager.reconcile_plan_file.as_deref();
+            emit_plan(&cycle_plan, api.platform(), plan_file);
+        }
+        cycles += 1;
+    }
+    cycles
+}
+
 pub async fn alive(api: Box<dyn ComposerApi + Send + Sync>) -> JoinHandle<()> {
     let settings = settings();
     let mut interval = interval(Duration::from_secs(settings.manager.ping_alive_schedule));
+    let mut register_throttle =
+        RegisterThrottle::new(Duration::from_secs(settings.manager.register_min_interval));
+    let bootstrap_retry = BootstrapRetry::new(
+        Duration::from_secs(settings.manager.bootstrap_retry_interval),
+        Duration::from_secs(settings.manager.ping_alive_schedule),
+    );
+    status::register_platform(api.platform());
     tokio::spawn(async move {
         // Start scheduling
         tokio::select! {
@@ -65,34 +237,43 @@ pub async fn alive(api: Box<dyn ComposerApi + Send + Sync>) -> JoinHandle<()> {
             _ = async {
                 // Infinite retry loop for initial connection
                 loop {
-                    let version = api.version().await;
-                    match version {
-                        Some(version) => {
-                            // Connection successful - register and start ping loop
-                            api.register().await;
-                            let mut detected_version: String = version.clone();
-                            loop {
-                                let ping_response = api.ping_alive().await;
-                                match ping_response {
-                                    Some(platform_version) => {
-                                        // Register when version changes
-                                        if platform_version != detected_version {
-                                            api.register().await;
-                                            detected_version = platform_version;
-                                        }
-                                    }
-                                    _ => {
-                                        // Connection lost - break to outer retry loop
-                                        break;
-                                    }
+                    // Retried with backoff until the platform answers
+                    let version = bootstrap_version(&*api, bootstrap_retry).await;
+                    // Connection successful - register and start ping loop
+                    api.register().await;
+                    register_throttle.record(Instant::now());
+                    status::record_ping(api.platform(), &version);
+                    // Ticks missed during the outage would fire the pings back to back
+                    interval.reset();
+                    let mut detected_version: String = version.clone();
+                    loop {
+                        let ping_response = api.ping_alive().await;
+                        match ping_response {
+                            Some(platform_version) => {
+                                status::record_ping(api.platform(), &platform_version);
+                                // Register when version changes
+                                if register_throttle.should_register(&detected_version, &platform_version, Instant::now()) {
+                                    api.register().await;
+                                    detected_version = platform_version;
+                                } else if platform_version != detected_version {
+                                    warn!(
+                                        detected = detected_version,
+                                        reported = platform_version,
+                                        "Platform version changed, re-registration throttled"
+                                    );
                                 }
-                                interval.tick().await;
                             }
-                        },
-                        None => {
-                            // Connection failed - wait and retry
-                            interval.tick().await;
+                            _ => {
+                                warn!(
+                                    platform = api.platform(),
+                                    xtm_seconds_since_last_successful_ping = status::seconds_since_last_successful_ping(api.platform(), Instant::now()),
+                                    "Platform contact lost"
+                                );
+                                // Connection lost - break to outer retry loop
+                                break;
+                            }
                         }
+                        interval.tick().await;
                     }
                 }
             } => {
@@ -101,3 +282,229 @@ pub async fn alive(api: Box<dyn ComposerApi + Send + Sync>) -> JoinHandle<()> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ApiConnector, ConnectorStatus};
+    use crate::config::settings::Daemon;
+    use crate::orchestrator::OrchestratorContainer;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Platform unreachable: every cycle only lists the connectors
+    struct CountingApi {
+        listings: Arc<AtomicU64>,
+    }
+
+    #[async_trait]
+    impl ComposerApi for CountingApi {
+        fn daemon(&self) -> &Daemon {
+            unimplemented!()
+        }
+
+        fn platform(&self) -> &'static str {
+            "cycles-test"
+        }
+
+        fn post_logs_schedule(&self) -> Duration {
+            Duration::from_secs(3600)
+        }
+
+        async fn version(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn ping_alive(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn register(&self) -> () {
+            unimplemented!()
+        }
+
+        async fn connectors(&self) -> Option<Vec<ApiConnector>> {
+            self.listings.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+
+        async fn patch_status(
+            &self,
+            _id: String,
+            _status: ConnectorStatus,
+        ) -> Option<ApiConnector> {
+            unimplemented!()
+        }
+
+        async fn patch_logs(&self, _id: String, _logs: Vec<String>) -> Option<String> {
+            unimplemented!()
+        }
+
+        async fn patch_health(
+            &self,
+            _id: String,
+            _restart_count: u32,
+            _started_at: String,
+            _is_in_reboot_loop: bool,
+        ) -> Option<String> {
+            unimplemented!()
+        }
+    }
+
+    struct UnusedOrchestrator {
+        reachable: bool,
+    }
+
+    #[async_trait]
+    impl Orchestrator for UnusedOrchestrator {
+        async fn get(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            unimplemented!()
+        }
+
+        async fn ping(&self) -> Result<(), String> {
+            if self.reachable {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }
+
+        async fn list(&self) -> Vec<OrchestratorContainer> {
+            unimplemented!()
+        }
+
+        async fn start(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
+            unimplemented!()
+        }
+
+        async fn stop(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
+            unimplemented!()
+        }
+
+        async fn remove(&self, _container: &OrchestratorContainer) -> () {
+            unimplemented!()
+        }
+
+        async fn refresh(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            unimplemented!()
+        }
+
+        async fn deploy(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            unimplemented!()
+        }
+
+        async fn logs(
+            &self,
+            _container: &OrchestratorContainer,
+            _connector: &ApiConnector,
+        ) -> Option<Vec<String>> {
+            unimplemented!()
+        }
+
+        fn state_converter(&self, _container: &OrchestratorContainer) -> ConnectorStatus {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn orchestration_loop_exits_after_max_cycles() {
+        let listings = Arc::new(AtomicU64::new(0));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(CountingApi {
+            listings: Arc::clone(&listings),
+        });
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(UnusedOrchestrator { reachable: true });
+
+        let cycles = run_cycles(Some(3), Duration::from_millis(1), &orchestrator, &api, None).await;
+
+        assert_eq!(cycles, 3);
+        assert_eq!(listings.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn orchestration_loop_runs_until_stopped_without_max_cycles() {
+        let listings = Arc::new(AtomicU64::new(0));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(CountingApi {
+            listings: Arc::clone(&listings),
+        });
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(UnusedOrchestrator { reachable: true });
+
+        let run = run_cycles(None, Duration::from_millis(1), &orchestrator, &api, None);
+        let timed_out = tokio::time::timeout(Duration::from_millis(50), run).await;
+
+        assert!(timed_out.is_err());
+        assert!(listings.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn shared_manager_id_is_reported_unless_platforms_are_exclusive() {
+        assert!(platforms_overlap(true, true, false));
+        assert!(!platforms_overlap(true, true, true));
+        assert!(!platforms_overlap(true, false, false));
+        assert!(!platforms_overlap(false, true, false));
+    }
+
+    #[test]
+    fn shared_manager_id_refuses_the_startup_when_configured() {
+        let mut settings = settings().clone();
+        settings.opencti.enable = true;
+        settings.openaev.enable = true;
+        settings.manager.exclusive_platforms = false;
+        settings.manager.refuse_shared_manager_id = true;
+        assert!(!platform_exclusion_allowed(&settings));
+
+        settings.manager.refuse_shared_manager_id = false;
+        assert!(platform_exclusion_allowed(&settings));
+
+        settings.manager.refuse_shared_manager_id = true;
+        settings.manager.exclusive_platforms = true;
+        assert!(platform_exclusion_allowed(&settings));
+
+        settings.manager.exclusive_platforms = false;
+        settings.openaev.enable = false;
+        assert!(platform_exclusion_allowed(&settings));
+    }
+
+    #[tokio::test]
+    async fn first_cycle_waits_for_the_startup_delay() {
+        let listings = Arc::new(AtomicU64::new(0));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(CountingApi {
+            listings: Arc::clone(&listings),
+        });
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(UnusedOrchestrator { reachable: true });
+
+        let delayed = async {
+            wait_startup_delay("cycles-test", Duration::from_millis(200)).await;
+            run_cycles(Some(1), Duration::from_millis(1), &orchestrator, &api, None).await
+        };
+        let timed_out = tokio::time::timeout(Duration::from_millis(50), delayed).await;
+        assert!(timed_out.is_err());
+        assert_eq!(listings.load(Ordering::Relaxed), 0);
+
+        let started = Instant::now();
+        wait_startup_delay("cycles-test", Duration::from_millis(40)).await;
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        // No delay by default
+        let started = Instant::now();
+        wait_startup_delay("cycles-test", Duration::ZERO).await;
+        assert!(started.elapsed() < Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn unreachable_backend_skips_the_orchestration() {
+        let listings = Arc::new(AtomicU64::new(0));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(CountingApi {
+            listings: Arc::clone(&listings),
+        });
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(UnusedOrchestrator { reachable: false });
+
+        let cycles = run_cycles(Some(2), Duration::from_millis(1), &orchestrator, &api, None).await;
+
+        assert_eq!(cycles, 2);
+        assert_eq!(listings.load(Ordering::Relaxed), 0);
+    }
+}
@@ -1,7 +1,8 @@
 use tokio::task::JoinHandle;
-use tracing::info;
+use tracing::{error, info, warn};
 use crate::api::ComposerApi;
 use crate::api::opencti::ApiOpenCTI;
+use crate::api::opencti::manager::get_token_scope::{TokenScope, scope};
 use crate::engine::{alive, orchestration};
 
 pub fn opencti_alive() -> JoinHandle<()> {
@@ -12,10 +13,34 @@ pub fn opencti_alive() -> JoinHandle<()> {
     })
 }
 
+// A token without the connector manager capability makes every mutation fail, checked before starting
+pub async fn verify_token_scope() -> bool {
+    match scope(&ApiOpenCTI::new()).await {
+        TokenScope::Authorized => {
+            info!("OpenCTI token scope verified");
+            true
+        }
+        TokenScope::Unauthorized(error) => {
+            error!(
+                error,
+                "OpenCTI token is not allowed to manage connectors, check the capabilities of its user"
+            );
+            false
+        }
+        TokenScope::Unverified(error) => {
+            warn!(
+                error,
+                "Unable to verify the OpenCTI token scope, starting anyway"
+            );
+            true
+        }
+    }
+}
+
 pub fn opencti_orchestration() -> JoinHandle<()> {
     info!("Starting OpenCTI connectors orchestration");
     tokio::spawn(async move {
         let api: Box<dyn ComposerApi + Send + Sync> = Box::new(ApiOpenCTI::new());
         orchestration(api).await;
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+// Bounds the re-registrations triggered by version changes, a platform behind a load
+// balancer with mixed versions would otherwise be re-registered on every ping
+#[derive(Debug)]
+pub struct RegisterThrottle {
+    min_interval: Duration,
+    last_register: Option<Instant>,
+}
+
+impl RegisterThrottle {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_register: None,
+        }
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        self.last_register = Some(now);
+    }
+
+    pub fn can_register(&self, now: Instant) -> bool {
+        self.last_register
+            .is_none_or(|last| now.saturating_duration_since(last) >= self.min_interval)
+    }
+
+    // Version seen at this ping, returns true if a re-registration must happen now
+    pub fn should_register(&mut self, detected: &str, reported: &str, now: Instant) -> bool {
+        if detected == reported || !self.can_register(now) {
+            return false;
+        }
+        self.record(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternating_versions_register_at_most_once_per_interval() {
+        let mut throttle = RegisterThrottle::new(Duration::from_secs(300));
+        let start = Instant::now();
+        throttle.record(start);
+
+        // Mixed versions behind a load balancer, pinged every minute
+        let mut detected = "6.8.0".to_string();
+        let mut registrations = 0;
+        for minute in 1..=10 {
+            let reported = if minute % 2 == 0 { "6.8.0" } else { "6.9.0" };
+            let now = start + Duration::from_secs(60 * minute);
+            if throttle.should_register(&detected, reported, now) {
+                registrations += 1;
+                detected = reported.to_string();
+            }
+        }
+        // Minutes 5 and 10 only
+        assert_eq!(registrations, 2);
+    }
+
+    #[test]
+    fn same_version_never_registers() {
+        let mut throttle = RegisterThrottle::new(Duration::ZERO);
+        assert!(!throttle.should_register("6.8.0", "6.8.0", Instant::now()));
+    }
+
+    #[test]
+    fn version_change_registers_once_interval_elapsed() {
+        let mut throttle = RegisterThrottle::new(Duration::from_secs(300));
+        let start = Instant::now();
+        assert!(throttle.should_register("6.8.0", "6.9.0", start));
+        assert!(!throttle.should_register("6.9.0", "7.0.0", start + Duration::from_secs(299)));
+        assert!(throttle.should_register("6.9.0", "7.0.0", start + Duration::from_secs(300)));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::system::signals;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{error, info};
+
+const STATUS_PATH: &str = "/status";
+
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct PlatformStatus {
+    pub last_ping: Option<String>,
+    pub version: Option<String>,
+    pub connectors: Option<usize>,
+    pub last_cycle_ms: Option<u64>,
+    // Gauge xtm_seconds_since_last_successful_ping, computed when reported
+    pub seconds_since_last_successful_ping: Option<u64>,
+    // Gauge xtm_connector_last_log_timestamp: unix seconds of the last logs forwarded per connector
+    pub last_log_timestamps: BTreeMap<String, i64>,
+    // Gauge xtm_orphaned_containers: managed containers unknown to the platform, set every cycle
+    pub orphaned_containers: Option<u64>,
+    #[serde(skip)]
+    last_contact: Option<Instant>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    platforms: BTreeMap<String, PlatformStatus>,
+}
+
+// Per platform state shared between the engine loops and the status server
+static PLATFORMS: Mutex<BTreeMap<String, PlatformStatus>> = Mutex::new(BTreeMap::new());
+
+fn update(platform: &str, apply: impl FnOnce(&mut PlatformStatus)) {
+    let mut platforms = PLATFORMS.lock().unwrap();
+    apply(platforms.entry(platform.to_string()).or_default());
+}
+
+pub fn register_platform(platform: &str) {
+    update(platform, |_| {});
+}
+
+pub fn record_ping(platform: &str, version: &str) {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    update(platform, |status| {
+        status.last_ping = Some(now);
+        status.version = Some(version.to_string());
+        status.last_contact = Some(Instant::now());
+    });
+}
+
+// Staleness of the platform contact, keeps growing while version / ping calls fail
+pub fn seconds_since_last_successful_ping(platform: &str, now: Instant) -> Option<u64> {
+    let platforms = PLATFORMS.lock().unwrap();
+    platforms
+        .get(platform)
+        .and_then(|status| status.last_contact)
+        .map(|last_contact| now.saturating_duration_since(last_contact).as_secs())
+}
+
+pub fn record_connectors(platform: &str, count: usize) {
+    update(platform, |status| status.connectors = Some(count));
+}
+
+pub fn record_cycle(platform: &str, duration: Duration) {
+    update(platform, |status| {
+        status.last_cycle_ms = Some(duration.as_millis() as u64)
+    });
+}
+
+pub fn record_orphaned_containers(platform: &str, count: u64) {
+    update(platform, |status| status.orphaned_containers = Some(count));
+}
+
+pub fn record_logs_forwarded(platform: &str, connector_id: &str, timestamp: i64) {
+    update(platform, |status| {
+        status
+            .last_log_timestamps
+            .insert(connector_id.to_string(), timestamp);
+    });
+}
+
+// Forget the connectors that left the platform listing
+pub fn retain_log_timestamps(platform: &str, listed: impl Fn(&str) -> bool) {
+    update(platform, |status| {
+        status
+            .last_log_timestamps
+            .retain(|connector_id, _| listed(connector_id))
+    });
+}
+
+pub fn status_json() -> String {
+    let now = Instant::now();
+    let mut platforms = PLATFORMS.lock().unwrap().clone();
+    for status in platforms.values_mut() {
+        status.seconds_since_last_successful_ping = status
+            .last_contact
+            .map(|last_contact| now.saturating_duration_since(last_contact).as_secs());
+    }
+    let report = StatusReport { platforms };
+    serde_json::to_string(&report).unwrap()
+}
+
+fn http_response(request: &str) -> String {
+    let path = request
+        .lines()
+        .next()
+        .and_then(|request_line| request_line.split_whitespace().nth(1));
+    let (status_line, body) = match path {
+        Some(STATUS_PATH) => ("200 OK", status_json()),
+        _ => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status_line,
+        body.len(),
+        body
+    )
+}
+
+async fn handle_connection(mut stream: TcpStream) {
+    let mut buffer = vec![0u8; 4096];
+    let read = stream.read(&mut buffer).await.unwrap_or(0);
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let _ = stream.write_all(http_response(&request).as_bytes()).await;
+}
+
+pub async fn serve(listener: TcpListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(stream));
+            }
+            Err(err) => {
+                error!(error = err.to_string(), "Status server failed to accept");
+            }
+        }
+    }
+}
+
+pub async fn start_status_server(address: &str) {
+    match TcpListener::bind(address).await {
+        Ok(listener) => {
+            info!(address, "Starting status server");
+            // Stop with the engine loops so the composer can exit
+            tokio::select! {
+                _ = signals::handle_stop_signals() => {}
+                _ = serve(listener) => {}
+            }
+        }
+        Err(err) => {
+            error!(
+                address,
+                error = err.to_string(),
+                "Unable to start status server"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn status_endpoint_reports_platforms_as_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener));
+        record_ping("status-test", "6.8.0");
+        record_connectors("status-test", 4);
+        record_cycle("status-test", Duration::from_millis(250));
+
+        let response = reqwest::get(format!("http://{}/status", address))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let json: serde_json::Value = response.json().await.unwrap();
+
+        let platform = &json["platforms"]["status-test"];
+        assert_eq!(platform["version"], "6.8.0");
+        assert_eq!(platform["connectors"], 4);
+        assert_eq!(platform["last_cycle_ms"], 250);
+        assert!(platform["last_ping"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(platform["seconds_since_last_successful_ping"], 0);
+    }
+
+    #[test]
+    fn staleness_resets_on_success_and_grows_on_failure() {
+        assert_eq!(
+            seconds_since_last_successful_ping("status-staleness", Instant::now()),
+            None
+        );
+        record_ping("status-staleness", "6.8.0");
+
+        // Failed pings leave the last contact untouched
+        let later = Instant::now() + Duration::from_secs(90);
+        let stale = seconds_since_last_successful_ping("status-staleness", later).unwrap();
+        assert!(stale >= 90, "{stale}");
+
+        record_ping("status-staleness", "6.8.0");
+        let fresh = seconds_since_last_successful_ping("status-staleness", Instant::now()).unwrap();
+        assert!(fresh < 90, "{fresh}");
+    }
+
+    #[tokio::test]
+    async fn status_server_answers_not_found_on_other_paths() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener));
+
+        let response = reqwest::get(format!("http://{}/metrics", address))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[test]
+    fn registered_platform_is_reported_before_any_update() {
+        register_platform("status-registered");
+
+        let json: serde_json::Value = serde_json::from_str(&status_json()).unwrap();
+        let platform = &json["platforms"]["status-registered"];
+        assert!(platform["last_ping"].is_null());
+        assert!(platform["version"].is_null());
+        assert!(platform["connectors"].is_null());
+        assert!(platform["last_cycle_ms"].is_null());
+        assert!(platform["seconds_since_last_successful_ping"].is_null());
+        assert_eq!(platform["last_log_timestamps"], serde_json::json!({}));
+        assert!(platform["orphaned_containers"].is_null());
+    }
+
+    #[test]
+    fn log_timestamps_of_unlisted_connectors_are_pruned() {
+        record_logs_forwarded("status-log-timestamps", "A", 100);
+        record_logs_forwarded("status-log-timestamps", "B", 200);
+        retain_log_timestamps("status-log-timestamps", |connector_id| connector_id == "B");
+
+        let json: serde_json::Value = serde_json::from_str(&status_json()).unwrap();
+        let platform = &json["platforms"]["status-log-timestamps"];
+        assert_eq!(
+            platform["last_log_timestamps"],
+            serde_json::json!({"B": 200})
+        );
+    }
+}
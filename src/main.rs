@@ -4,16 +4,26 @@ mod engine;
 mod orchestrator;
 mod system;
 
+use crate::config::credentials::{
+    CredentialsKeySource, credentials_key_source, fetch_credentials_key_secret,
+};
 use crate::config::settings::Settings;
+use crate::engine::check_platform_exclusion;
+use crate::engine::listing::list_connectors;
 use crate::engine::openaev::{openaev_alive, openaev_orchestration};
-use crate::engine::opencti::{opencti_alive, opencti_orchestration};
+use crate::engine::opencti::{opencti_alive, opencti_orchestration, verify_token_scope};
+use crate::engine::status::start_status_server;
+use crate::system::logfmt::Logfmt;
 use futures::future::join_all;
 use rolling_file::{BasicRollingFileAppender, RollingConditionBasic};
 use std::str::FromStr;
+use std::path::Path;
 use std::sync::OnceLock;
-use std::{env, fs};
+use std::time::Duration;
+use std::{env, fs, process};
+use tokio::runtime::Handle;
 use tokio::task::JoinHandle;
-use tracing::{Level, info, warn};
+use tracing::{Level, info};
 use tracing_subscriber::fmt::Layer;
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -26,6 +36,9 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 const BASE_DIRECTORY_LOG: &str = "logs";
 const BASE_DIRECTORY_SIZE: usize = 5;
 const PREFIX_LOG_NAME: &str = "xtm-composer.log";
+const LIST_CONNECTORS_FLAG: &str = "--list-connectors";
+const DUMP_CONFIG_FLAG: &str = "--dump-config";
+const ONCE_FLAG: &str = "--once";
 
 // Singleton settings for all application
 fn settings() -> &'static Settings {
@@ -54,9 +67,9 @@ fn init_logger() {
     };
 
     // Validate log format
-    if logger_config.format != "json" && logger_config.format != "pretty" {
+    if !logger_config.is_valid_format() {
         panic!(
-            "Invalid log format: '{}'. Valid values are: json, pretty",
+            "Invalid log format: '{}'. Valid values are: json, pretty, logfmt",
             logger_config.format
         );
     }
@@ -82,6 +95,17 @@ fn init_logger() {
             .with(logger_config.directory.then(|| console_layer))
             .with(logger_config.console.then(|| file_layer))
             .init();
+    } else if logger_config.format == "logfmt" {
+        let console_layer = Layer::new()
+            .with_writer(std::io::stdout.with_max_level(log_level))
+            .event_format(Logfmt);
+        let file_layer = Layer::new()
+            .with_writer(file_writer.with_max_level(log_level))
+            .event_format(Logfmt);
+        Registry::default()
+            .with(logger_config.directory.then(|| console_layer))
+            .with(logger_config.console.then(|| file_layer))
+            .init();
     } else {
         let console_layer = Layer::new()
             .with_writer(std::io::stdout.with_max_level(log_level))
@@ -100,25 +124,43 @@ fn init_logger() {
 pub fn load_and_verify_credentials_key() -> RsaPrivateKey {
     let setting = settings();
     
-    // Priority: file > environment variable
-    let key_content = if let Some(filepath) = &setting.manager.credentials_key_filepath {
-        // Warning if both are set
-        if setting.manager.credentials_key.is_some() {
-            warn!("Both credentials_key and credentials_key_filepath are set. Using filepath (priority).");
+    // Priority: kubernetes secret > file > environment variable
+    // The secret is read with the kubernetes configuration of the first enabled platform using it
+    let kubernetes = [
+        (setting.opencti.enable, &setting.opencti.daemon),
+        (setting.openaev.enable, &setting.openaev.daemon),
+    ]
+    .into_iter()
+    .find(|(enable, daemon)| *enable && daemon.selector == "kubernetes")
+    .and_then(|(_, daemon)| daemon.kubernetes.as_ref());
+    let source = credentials_key_source(
+        setting.manager.credentials_key_secret.as_ref(),
+        setting.manager.credentials_key_filepath.as_deref(),
+        setting.manager.credentials_key.as_deref(),
+        kubernetes.is_some(),
+    );
+    let key_content = match source {
+        Some(CredentialsKeySource::Secret(secret)) => {
+            let kubernetes = kubernetes.expect("secret source requires a kubernetes platform");
+            // Key is loaded lazily from sync code, wait for the kubernetes call in place
+            let fetched = tokio::task::block_in_place(|| {
+                Handle::current().block_on(fetch_credentials_key_secret(secret, kubernetes))
+            });
+            match fetched {
+                Ok(content) => content,
+                Err(e) => panic!("Failed to read credentials key secret '{}': {}", secret.name, e)
+            }
         }
-        
         // Read key from file
-        match fs::read_to_string(filepath) {
+        Some(CredentialsKeySource::File(filepath)) => match fs::read_to_string(filepath) {
             Ok(content) => content,
             Err(e) => panic!("Failed to read credentials key file '{}': {}", filepath, e)
-        }
-    } else if let Some(key) = &setting.manager.credentials_key {
+        },
         // Use environment variable or config value
-        key.clone()
-    } else {
-        panic!(
-            "No credentials key provided! Set either 'manager.credentials_key' or 'manager.credentials_key_filepath' in configuration."
-        );
+        Some(CredentialsKeySource::Value(key)) => key.to_string(),
+        None => panic!(
+            "No credentials key provided! Set either 'manager.credentials_key', 'manager.credentials_key_filepath' or 'manager.credentials_key_secret' in configuration."
+        ),
     };
     
     // Validate key format (trim to handle trailing whitespace)
@@ -140,13 +182,19 @@ pub fn load_and_verify_credentials_key() -> RsaPrivateKey {
     }
 }
 
-fn opencti_orchestrate(orchestrations: &mut Vec<JoinHandle<()>>) {
+async fn opencti_orchestrate(
+    orchestrations: &mut Vec<JoinHandle<()>>,
+    services: &mut Vec<JoinHandle<()>>,
+) {
     let setting = settings();
     if setting.opencti.enable {
         // Initialize private key singleton
         let _ = private_key();
+        if setting.opencti.verify_token_scope && !verify_token_scope().await {
+            process::exit(1);
+        }
         let opencti_alive = opencti_alive();
-        orchestrations.push(opencti_alive);
+        services.push(opencti_alive);
         let opencti_orchestration = opencti_orchestration();
         orchestrations.push(opencti_orchestration);
     } else {
@@ -155,11 +203,14 @@ fn opencti_orchestrate(orchestrations: &mut Vec<JoinHandle<()>>) {
 }
 
 // Init openaev
-fn openaev_orchestrate(orchestrations: &mut Vec<JoinHandle<()>>) {
+fn openaev_orchestrate(
+    orchestrations: &mut Vec<JoinHandle<()>>,
+    services: &mut Vec<JoinHandle<()>>,
+) {
     let setting = settings();
     if setting.openaev.enable {
         let openaev_alive = openaev_alive();
-        orchestrations.push(openaev_alive);
+        services.push(openaev_alive);
         let openaev_orchestration = openaev_orchestration();
         orchestrations.push(openaev_orchestration);
     } else {
@@ -173,15 +224,45 @@ async fn main() {
     // Required since reqwest 0.13 switched from native-tls to rustls.
     // Ignore error if a provider was already installed by another dependency.
     let _ = CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider());
+    // Diagnostic mode, print the effective configuration and exit
+    if env::args().any(|arg| arg == DUMP_CONFIG_FLAG) {
+        match settings().to_redacted_json() {
+            Ok(dump) => println!("{}", dump),
+            Err(err) => eprintln!("Unable to serialize configuration: {}", err),
+        }
+        return;
+    }
     // Initialize the global logging system
     init_logger();
+    // Diagnostic mode, list the managed connectors and exit
+    if env::args().any(|arg| arg == LIST_CONNECTORS_FLAG) {
+        list_connectors().await;
+        return;
+    }
     // Log the start
     let env = Settings::mode();
     info!(version = VERSION, env, "Starting XTM composer");
     // Start orchestration threads
+    if !check_platform_exclusion() {
+        process::exit(1);
+    }
     let mut orchestrations = Vec::new();
-    opencti_orchestrate(&mut orchestrations);
-    openaev_orchestrate(&mut orchestrations);
-    // Wait for threads to terminate
+    let mut services = Vec::new();
+    // The first cycles already run with the connector overrides
+    if let Some(path) = settings().manager.overrides_path.clone() {
+        crate::config::overrides::reload(Path::new(&path));
+        let period = Duration::from_secs(settings().manager.overrides_reload_interval);
+        services.push(tokio::spawn(crate::config::overrides::watch(path, period)));
+    }
+    opencti_orchestrate(&mut orchestrations, &mut services).await;
+    openaev_orchestrate(&mut orchestrations, &mut services);
+    if let Some(address) = settings().manager.status_listen.clone() {
+        services.push(tokio::spawn(async move {
+            start_status_server(&address).await;
+        }));
+    }
+    // Wait for the orchestrations to terminate (stop signal or maximum cycles reached)
     join_all(orchestrations).await;
+    // Ping alive and status server only end on stop signals, not after one-shot runs
+    services.iter().for_each(|service| service.abort());
 }
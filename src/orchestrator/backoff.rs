@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(600);
+const DEFAULT_RESET_AFTER: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+struct BackoffEntry {
+    attempts: u32,
+    restart_count: u32,
+    next_start: Instant,
+}
+
+// In memory restart backoff of crash looping connectors, kept across orchestration cycles
+#[derive(Debug)]
+pub struct RestartBackoff {
+    base_delay: Duration,
+    max_delay: Duration,
+    reset_after: Duration,
+    entries: HashMap<String, BackoffEntry>,
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_DELAY, DEFAULT_MAX_DELAY, DEFAULT_RESET_AFTER)
+    }
+}
+
+impl RestartBackoff {
+    pub fn new(base_delay: Duration, max_delay: Duration, reset_after: Duration) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            reset_after,
+            entries: HashMap::new(),
+        }
+    }
+
+    // Delay doubling with each attempt, capped to the max delay
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(16);
+        self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay)
+    }
+
+    pub fn attempts(&self, connector_id: &str) -> u32 {
+        self.entries
+            .get(connector_id)
+            .map(|entry| entry.attempts)
+            .unwrap_or(0)
+    }
+
+    // Record a crash observed through the restart count, returns true if the backoff escalated
+    pub fn record_crash(&mut self, connector_id: &str, restart_count: u32, now: Instant) -> bool {
+        let attempts = match self.entries.get(connector_id) {
+            Some(entry) if restart_count <= entry.restart_count => return false,
+            Some(entry) => entry.attempts + 1,
+            None => 1,
+        };
+        let next_start = now + self.delay(attempts);
+        self.entries.insert(
+            connector_id.to_string(),
+            BackoffEntry {
+                attempts,
+                restart_count,
+                next_start,
+            },
+        );
+        true
+    }
+
+    pub fn can_start(&self, connector_id: &str, now: Instant) -> bool {
+        self.entries
+            .get(connector_id)
+            .is_none_or(|entry| now >= entry.next_start)
+    }
+
+    // Forget the connector once it stayed up long enough, returns true if a backoff was reset
+    pub fn reset_if_stable(&mut self, connector_id: &str, uptime: Duration) -> bool {
+        if uptime >= self.reset_after {
+            self.entries.remove(connector_id).is_some()
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoff() -> RestartBackoff {
+        RestartBackoff::new(
+            Duration::from_secs(10),
+            Duration::from_secs(60),
+            Duration::from_secs(300),
+        )
+    }
+
+    #[test]
+    fn delay_doubles_and_caps() {
+        let backoff = backoff();
+        assert_eq!(backoff.delay(1), Duration::from_secs(10));
+        assert_eq!(backoff.delay(2), Duration::from_secs(20));
+        assert_eq!(backoff.delay(3), Duration::from_secs(40));
+        assert_eq!(backoff.delay(4), Duration::from_secs(60));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn crash_escalates_and_holds_start_until_delay_elapsed() {
+        let mut backoff = backoff();
+        let now = Instant::now();
+        assert!(backoff.can_start("A", now));
+
+        assert!(backoff.record_crash("A", 4, now));
+        assert_eq!(backoff.attempts("A"), 1);
+        assert!(!backoff.can_start("A", now + Duration::from_secs(9)));
+        assert!(backoff.can_start("A", now + Duration::from_secs(10)));
+
+        let later = now + Duration::from_secs(10);
+        assert!(backoff.record_crash("A", 5, later));
+        assert_eq!(backoff.attempts("A"), 2);
+        assert!(!backoff.can_start("A", later + Duration::from_secs(19)));
+        assert!(backoff.can_start("A", later + Duration::from_secs(20)));
+        // Other connectors are not affected
+        assert!(backoff.can_start("B", later));
+    }
+
+    #[test]
+    fn same_restart_count_does_not_escalate() {
+        let mut backoff = backoff();
+        let now = Instant::now();
+        assert!(backoff.record_crash("A", 4, now));
+        assert!(!backoff.record_crash("A", 4, now + Duration::from_secs(30)));
+        assert_eq!(backoff.attempts("A"), 1);
+    }
+
+    #[test]
+    fn reset_once_connector_stays_up() {
+        let mut backoff = backoff();
+        let now = Instant::now();
+        backoff.record_crash("A", 4, now);
+        backoff.record_crash("A", 5, now);
+
+        assert!(!backoff.reset_if_stable("A", Duration::from_secs(299)));
+        assert_eq!(backoff.attempts("A"), 2);
+        assert!(backoff.reset_if_stable("A", Duration::from_secs(300)));
+        assert_eq!(backoff.attempts("A"), 0);
+        assert!(backoff.can_start("A", now));
+        assert!(!backoff.reset_if_stable("A", Duration::from_secs(300)));
+    }
+}
@@ -1,37 +1,394 @@
-use crate::api::{ApiConnector, ComposerApi, ConnectorStatus, RequestedStatus};
-use crate::orchestrator::{Orchestrator, OrchestratorContainer};
-use std::collections::HashMap;
+use crate::api::{
+    ApiConnector, ComposerApi, ConnectorListing, ConnectorStatus, DecryptTally, EnvVariable,
+    REDACTED_VALUE, RequestedStatus,
+};
+use crate::config::settings::{LogLevel, LogSink, Manager, RebootLoopAction};
+use crate::engine::status;
+use crate::orchestrator::backoff::RestartBackoff;
+use crate::orchestrator::cooldown::RedeployCooldown;
+use crate::orchestrator::hooks::{HookStage, run_deploy_hook};
+use crate::orchestrator::locks::ConnectorLock;
+use crate::orchestrator::log_filter::filter_logs;
+use crate::orchestrator::log_parser::parse_logs;
+use crate::orchestrator::log_sink::{ForwardedLogs, forward_logs};
+use crate::orchestrator::log_timers::LogTimers;
+use crate::orchestrator::stopping::PendingStops;
+use crate::orchestrator::transitions::ConnectorTransitions;
+use crate::orchestrator::{
+    Orchestrator, OrchestratorContainer, StreamLine, filter_platform_containers,
+};
+use crate::settings;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
-use tracing::{info, warn};
+use tokio::time::timeout;
+use tracing::{debug, error, info, warn};
+
+#[derive(Debug, Default, PartialEq)]
+struct EnvDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+impl EnvDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Compare the deployed container envs with the requested ones, masking sensitive values.
+// Only keys of the previous contract can be removed, the container also holds the image envs
+// and the Kubernetes annotations
+fn diff_container_envs(
+    current: &HashMap<String, String>,
+    previous_keys: &HashSet<&str>,
+    requested: &[EnvVariable],
+) -> EnvDiff {
+    let mut diff = EnvDiff::default();
+    for env in requested {
+        let displayed = |value: &str| {
+            if env.is_sensitive {
+                REDACTED_VALUE.to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        match current.get(&env.key) {
+            None => diff
+                .added
+                .push(format!("{}={}", env.key, displayed(&env.value))),
+            Some(value) if *value != env.value => diff.changed.push(format!(
+                "{}: {} -> {}",
+                env.key,
+                displayed(value),
+                displayed(&env.value)
+            )),
+            Some(_) => {}
+        }
+    }
+    // Removed values are not part of the contract anymore, only report the keys
+    diff.removed = previous_keys
+        .iter()
+        .filter(|key| !requested.iter().any(|env| env.key == **key))
+        .map(|key| key.to_string())
+        .collect();
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    diff
+}
+
+// Forward the last logs of a container about to be removed or stopped, they would be lost otherwise
+async fn preserve_logs(
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    api: &Box<dyn ComposerApi + Send + Sync>,
+    container: &OrchestratorContainer,
+    connector: Option<&ApiConnector>,
+) {
+    let fetch_timeout = Duration::from_secs(settings().manager.logs_fetch_timeout);
+    let logs = match timeout(fetch_timeout, orchestrator.removal_logs(container)).await {
+        Ok(logs) => logs,
+        Err(_) => {
+            warn!(
+                name = container.name,
+                timeout = fetch_timeout.as_secs(),
+                "Logs fetch timed out, last logs not preserved"
+            );
+            None
+        }
+    };
+    let Some(logs) = logs else {
+        return;
+    };
+    let connector_id = container.extract_opencti_id();
+    let log_sink = &settings().manager.log_sink;
+    match connector {
+        Some(_) => {
+            forward_logs(log_sink, api.platform(), &connector_id, &logs).await;
+            if log_sink.reports_to_platform() {
+                info!(id = connector_id, "Reporting last logs");
+                api.patch_logs(connector_id, logs).await;
+            }
+        }
+        // The platform no longer knows the connector, keep the logs on the composer output at least
+        None => {
+            let log_sink = match log_sink {
+                LogSink::Opencti => &LogSink::Stdout,
+                log_sink => log_sink,
+            };
+            forward_logs(log_sink, api.platform(), &connector_id, &logs).await;
+        }
+    }
+}
+
+// Apply the configured reboot loop action, returns true if the connector must be held stopped
+async fn handle_reboot_loop(
+    action: RebootLoopAction,
+    logs_on_remove: bool,
+    restart_backoff: &mut RestartBackoff,
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    api: &Box<dyn ComposerApi + Send + Sync>,
+    connector: &ApiConnector,
+    container: &OrchestratorContainer,
+    container_status: ConnectorStatus,
+) -> bool {
+    let now = Instant::now();
+    let hold = match action {
+        RebootLoopAction::ReportOnly => false,
+        RebootLoopAction::Stop => true,
+        // A running connector that crashed again escalates the backoff
+        RebootLoopAction::Backoff if container_status == ConnectorStatus::Started => {
+            restart_backoff.record_crash(&connector.id, container.restart_count, now)
+        }
+        RebootLoopAction::Backoff => !restart_backoff.can_start(&connector.id, now),
+    };
+    if hold && container_status == ConnectorStatus::Started {
+        warn!(
+            id = connector.id,
+            action = ?action,
+            attempts = restart_backoff.attempts(&connector.id),
+            "Stopping connector stuck in reboot loop"
+        );
+        if logs_on_remove {
+            preserve_logs(orchestrator, api, container, Some(connector)).await;
+        }
+        orchestrator.stop(container, connector).await;
+    }
+    hold
+}
+
+// Number of container state changes not initiated by the composer since startup
+static EXTERNAL_STATE_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+fn record_logs_forwarded(platform: &str, connector_id: &str, timestamp: i64) {
+    status::record_logs_forwarded(platform, connector_id, timestamp);
+    debug!(
+        id = connector_id,
+        xtm_connector_last_log_timestamp = timestamp,
+        "Connector logs forwarded"
+    );
+}
+
+// Containers whose connector is no longer part of the platform listing
+fn count_orphaned_containers(
+    containers: &[OrchestratorContainer],
+    connectors_by_id: &HashMap<String, ApiConnector>,
+) -> u64 {
+    containers
+        .iter()
+        .filter(|container| !connectors_by_id.contains_key(&container.extract_opencti_id()))
+        .count() as u64
+}
+
+// The platform believes the connector is in the requested state but the container is not
+fn is_external_state_change(
+    requested: RequestedStatus,
+    reported: ConnectorStatus,
+    actual: ConnectorStatus,
+) -> bool {
+    matches!(
+        (requested, reported, actual),
+        (
+            RequestedStatus::Starting,
+            ConnectorStatus::Started,
+            ConnectorStatus::Stopped
+        ) | (
+            RequestedStatus::Stopping,
+            ConnectorStatus::Stopped,
+            ConnectorStatus::Started
+        )
+    )
+}
+
+// Fetch the connector logs, a slow backend must not block the orchestration cycle
+async fn fetch_connector_logs(
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    container: &OrchestratorContainer,
+    connector: &ApiConnector,
+    fetch_timeout: Duration,
+) -> Option<Vec<String>> {
+    match timeout(fetch_timeout, orchestrator.logs(container, connector)).await {
+        Ok(logs) => logs,
+        Err(_) => {
+            warn!(
+                id = connector.id,
+                timeout = fetch_timeout.as_secs(),
+                "Logs fetch timed out, skipping logs"
+            );
+            None
+        }
+    }
+}
+
+// Apply the connector log pattern and the minimum level, stream tags are put back afterwards
+fn prepare_logs(
+    logs: Vec<String>,
+    pattern: Option<&str>,
+    min_level: Option<LogLevel>,
+    connector_id: &str,
+) -> Vec<String> {
+    let (tags, lines): (Vec<_>, Vec<_>) = logs
+        .into_iter()
+        .map(|line| {
+            let split = StreamLine::split(line);
+            (split.tag, split.line)
+        })
+        .unzip();
+    let lines = match pattern {
+        Some(pattern) => parse_logs(lines, pattern, connector_id),
+        None => lines,
+    };
+    let lines: Vec<StreamLine> = tags
+        .into_iter()
+        .zip(lines)
+        .map(|(tag, line)| StreamLine { tag, line })
+        .collect();
+    let lines = match min_level {
+        Some(min_level) => filter_logs(lines, min_level),
+        None => lines,
+    };
+    lines.into_iter().map(StreamLine::joined).collect()
+}
+
+// Connectors resolving to the same container name would fight over one container, skip them
+fn colliding_connectors(connectors: &[ApiConnector]) -> HashSet<String> {
+    let mut by_name: HashMap<String, &ApiConnector> = HashMap::new();
+    let mut colliding = HashSet::new();
+    for connector in connectors {
+        let container_name = connector.container_name();
+        match by_name.get(&container_name) {
+            Some(first) => {
+                error!(
+                    container_name,
+                    id = connector.id,
+                    colliding_id = first.id,
+                    "Connectors resolve to the same container name, skipping both. Rename one of them or enable manager.unique_container_names"
+                );
+                colliding.insert(first.id.clone());
+                colliding.insert(connector.id.clone());
+            }
+            None => {
+                by_name.insert(container_name, connector);
+            }
+        }
+    }
+    colliding
+}
+
+// Spread the backend calls of a cycle, no wait before the first connector
+async fn wait_between_connectors(index: usize, per_connector_delay: Duration) {
+    if index > 0 && !per_connector_delay.is_zero() {
+        tokio::time::sleep(per_connector_delay).await;
+    }
+}
+
+// Every sensitive value of the cycle failed to decrypt, reported once per cycle.
+// Returns true when the deploys must be skipped rather than run with empty secrets
+fn secrets_unusable(platform: &str, tally: DecryptTally, fail_on_decrypt_errors: bool) -> bool {
+    if !tally.all_failed() {
+        return false;
+    }
+    error!(
+        platform,
+        failed = tally.failed,
+        skip_deploys = fail_on_decrypt_errors,
+        "Unable to decrypt any sensitive value, check the credentials key"
+    );
+    fail_on_decrypt_errors
+}
+
+// An unknown state right after the deployment is left to the next cycles
+async fn report_deployed_status(
+    api: &Box<dyn ComposerApi + Send + Sync>,
+    id: String,
+    status: ConnectorStatus,
+) {
+    match status.reported() {
+        Some(status) => {
+            api.patch_status(id, status).await;
+        }
+        None => {
+            warn!(
+                id = id,
+                "Deployed container in an unknown state, status not reported"
+            );
+        }
+    }
+}
 
 async fn orchestrate_missing(
     orchestrator: &Box<dyn Orchestrator + Send + Sync>,
     api: &Box<dyn ComposerApi + Send + Sync>,
     connector: &ApiConnector,
+    verify_deploy: bool,
+    secrets_unusable: bool,
 ) {
     // Connector is not provisioned, deploy the images
     let id = connector.id.clone();
+    if secrets_unusable {
+        warn!(
+            id = id,
+            "Deployment skipped, sensitive values can't be decrypted"
+        );
+        return;
+    }
+    let manager = &settings().manager;
+    if !run_deploy_hook(manager, HookStage::PreDeploy, connector).await {
+        warn!(id = id, "Deployment canceled by the pre deploy hook");
+        return;
+    }
     info!(id = id, "Deploying the container");
     let deploy_action = orchestrator.deploy(connector).await;
+    let deployed = deploy_action.is_some();
     match deploy_action {
-        // Update the connector status
-        Some(_) => {
-            api.patch_status(id, ConnectorStatus::Stopped).await;
+        // Confirm the backend really created the container before reporting it
+        Some(_) if verify_deploy => match orchestrator.get(connector).await {
+            Some(container) => {
+                let status = orchestrator.state_converter(&container);
+                report_deployed_status(api, id, status).await;
+            }
+            None => {
+                error!(
+                    id = id,
+                    name = connector.container_name(),
+                    "Deployment accepted but the container can't be found"
+                );
+            }
+        },
+        // Report the actual state, a backend may start the container right after returning it
+        Some(container) => {
+            let starting = matches!(
+                RequestedStatus::from_str(&connector.requested_status),
+                Ok(RequestedStatus::Starting)
+            );
+            let container = if starting {
+                orchestrator.get(connector).await.unwrap_or(container)
+            } else {
+                container
+            };
+            let status = orchestrator.state_converter(&container);
+            report_deployed_status(api, id, status).await;
         }
         None => {
             warn!(id = id, "Deployment canceled");
         }
     }
+    if deployed {
+        run_deploy_hook(manager, HookStage::PostDeploy, connector).await;
+    }
 }
 
 async fn orchestrate_existing(
-    tick: &mut Instant,
-    health_tick: &mut Instant,
+    state: &mut OrchestrationState,
     orchestrator: &Box<dyn Orchestrator + Send + Sync>,
     api: &Box<dyn ComposerApi + Send + Sync>,
     connector: &ApiConnector,
     container: OrchestratorContainer,
+    reboot_loop_action: RebootLoopAction,
+    logs_on_remove: bool,
+    secrets_unusable: bool,
 ) {
     // Connector is provisioned
     let connector_id = connector.id.clone();
@@ -41,21 +398,47 @@ async fn orchestrate_existing(
     let container_status = orchestrator.state_converter(&container);
     // Check for reboot loop and send health metrics
     let is_in_reboot_loop = container.is_in_reboot_loop();
-    let final_status = if is_in_reboot_loop {
+    let hold_stopped = if is_in_reboot_loop {
         warn!(
             id = connector_id,
             restart_count = container.restart_count,
             "Reboot loop detected"
         );
-        // For now, we still report it as Started but with a warning log
-        // In the future, we could add a new status like ConnectorStatus::Critical
-        container_status
+        handle_reboot_loop(
+            reboot_loop_action,
+            logs_on_remove,
+            &mut state.restart_backoff,
+            orchestrator,
+            api,
+            connector,
+            &container,
+            container_status,
+        )
+        .await
+    } else {
+        // Connector stayed up long enough, forget its previous crashes
+        if container_status == ConnectorStatus::Started {
+            if let Some(uptime) = container.uptime() {
+                if state.restart_backoff.reset_if_stable(&connector_id, uptime) {
+                    info!(id = connector_id, "Restart backoff reset");
+                }
+            }
+        }
+        false
+    };
+    // A connector held by the reboot loop action is reported as stopped
+    let final_status = if hold_stopped {
+        ConnectorStatus::Stopped
     } else {
         container_status
     };
+    state.transitions.record(&connector_id, final_status);
     
-    // Update the connector status if needed
-    let container_status_not_aligned = final_status != connector_status;
+    // Update the connector status if needed, an unknown state keeps the last reported one
+    let status_to_report = final_status
+        .reported()
+        .filter(|reported| *reported != connector_status);
+    let container_status_not_aligned = status_to_report.is_some();
     
     // Detect if connector just started
     let just_started = container_status_not_aligned && 
@@ -68,11 +451,11 @@ async fn orchestrate_existing(
     let now = Instant::now();
     let should_send_health = just_started || 
         (final_status == ConnectorStatus::Started && 
-         now.duration_since(health_tick.clone()) >= Duration::from_secs(30));
+         now.duration_since(state.health_tick) >= Duration::from_secs(30));
     
     if should_send_health {
         if let Some(started_at) = &container.started_at {
-            info!(id = connector_id, "Reporting health metrics");
+            debug!(id = connector_id, "Reporting health metrics");
             api.patch_health(
                 connector_id.clone(),
                 container.restart_count,
@@ -82,78 +465,293 @@ async fn orchestrate_existing(
         }
         // Reset timer only for running connectors
         if final_status == ConnectorStatus::Started {
-            *health_tick = now;
+            state.health_tick = now;
         }
     }
-    if container_status_not_aligned {
-        api.patch_status(connector.id.clone(), final_status)
-            .await;
+    // Container state changed out of band (manual stop / start on the orchestrator side)
+    let requested_status = RequestedStatus::from_str(requested_status_fetch.as_str()).unwrap();
+    if !hold_stopped && is_external_state_change(requested_status, connector_status, final_status) {
+        let total = EXTERNAL_STATE_CHANGES.fetch_add(1, Ordering::Relaxed) + 1;
+        warn!(
+            id = connector_id,
+            reported = ?connector_status,
+            actual = ?final_status,
+            total,
+            "Connector state changed outside of the composer"
+        );
+    }
+    if let Some(status) = status_to_report {
+        api.patch_status(connector.id.clone(), status).await;
         info!(id = connector_id, "Patch status");
     }
     // In case of platform upgrade, we need to align all deployed connectors
     let requested_connector_hash = connector.contract_hash.clone();
+    // A container without any hash information is considered outdated
     let current_container_hash = container.extract_opencti_hash();
-    if !requested_connector_hash.eq(current_container_hash) {
+    let outdated = current_container_hash != Some(&requested_connector_hash);
+    if secrets_unusable {
+        // A refresh would replace the deployed secrets with empty values
+        if outdated {
+            warn!(
+                id = connector_id,
+                "Refresh skipped, sensitive values can't be decrypted"
+            );
+        }
+    } else if outdated {
         // Versions are not aligned
         info!(
             id = connector_id,
             hash = requested_connector_hash,
             "Refreshing"
         );
+        // Containers deployed before the contract keys label report no removed key
+        let previous_keys = container.extract_contract_keys().unwrap_or_default();
+        let env_diff =
+            diff_container_envs(&container.envs, &previous_keys, &connector.container_envs());
+        if !env_diff.is_empty() {
+            info!(
+                id = connector_id,
+                added = ?env_diff.added,
+                removed = ?env_diff.removed,
+                changed = ?env_diff.changed,
+                "Connector configuration changes"
+            );
+        }
         orchestrator.refresh(connector).await;
+    } else {
+        // Up to date but edited by hand on the orchestrator side
+        let drift = orchestrator.spec_drift(connector).await;
+        if !drift.is_empty() {
+            warn!(
+                id = connector_id,
+                fields = ?drift,
+                "Deployment modified outside of the composer, restoring its spec"
+            );
+            orchestrator.refresh(connector).await;
+        }
+    }
+    // Stop completed or superseded by a start request
+    if final_status == ConnectorStatus::Stopped || requested_status == RequestedStatus::Starting {
+        state.pending_stops.clear(&connector_id);
     }
     // Align existing and requested status
-    let requested_status = RequestedStatus::from_str(requested_status_fetch.as_str()).unwrap();
-    match (requested_status, container_status) {
+    let now = Instant::now();
+    match (requested_status, final_status) {
         (RequestedStatus::Stopping, ConnectorStatus::Started) => {
-            info!(id = connector_id, "Stopping");
+            match state.pending_stops.in_progress(&connector_id, now) {
+                // Still running until the container honors the stop, don't issue it again
+                Some(elapsed) => {
+                    info!(
+                        id = connector_id,
+                        elapsed = elapsed.as_secs(),
+                        "Stop in progress"
+                    );
+                }
+                None => {
+                    info!(id = connector_id, "Stopping");
+                    orchestrator.stop(&container, connector).await;
+                    state.pending_stops.record_stop(&connector_id, now);
+                }
+            }
+        }
+        (RequestedStatus::Stopping, ConnectorStatus::Paused) => {
+            info!(id = connector_id, "Stopping paused connector");
             orchestrator.stop(&container, connector).await;
         }
+        (RequestedStatus::Pausing, ConnectorStatus::Started) => {
+            info!(id = connector_id, "Pausing");
+            orchestrator.pause(&container, connector).await;
+        }
+        (RequestedStatus::Starting, ConnectorStatus::Paused) => {
+            info!(id = connector_id, "Resuming");
+            orchestrator.resume(&container, connector).await;
+        }
+        (RequestedStatus::Starting, ConnectorStatus::Stopped) if hold_stopped => {
+            info!(id = connector_id, "Restart held by reboot loop action");
+        }
         (RequestedStatus::Starting, ConnectorStatus::Stopped) => {
             info!(id = connector_id, "Starting");
             orchestrator.start(&container, connector).await;
         }
+        // Anomalous container left to the operator, starting or stopping it would likely fail
+        (_, ConnectorStatus::Unknown) => {
+            warn!(
+                id = connector_id,
+                state = container.state,
+                "Container in an unknown state, nothing executed"
+            );
+        }
+        // Steady state, fires every cycle for every aligned connector
         _ => {
-            info!(id = connector_id, "Nothing to execute");
+            debug!(id = connector_id, "Nothing to execute");
         }
     }
     // Get latest logs and update opencti every 5 minutes
-    let now = Instant::now();
-    if now.duration_since(tick.clone()) >= api.post_logs_schedule() {
-        let connector_logs = orchestrator.logs(&container, connector).await;
+    if state
+        .log_timers
+        .poll(&connector_id, api.post_logs_schedule(), Instant::now())
+    {
+        let logs_fetch_timeout = Duration::from_secs(settings().manager.logs_fetch_timeout);
+        // A stopped container has nothing new to report, unless its last logs are collected
+        let connector_logs = if container_status == ConnectorStatus::Started || logs_on_remove {
+            fetch_connector_logs(orchestrator, &container, connector, logs_fetch_timeout).await
+        } else {
+            None
+        };
         match connector_logs {
             Some(logs) => {
-                info!(id = connector_id, "Reporting logs");
-                api.patch_logs(connector_id, logs).await;
+                let overrides = crate::config::overrides::current();
+                let logs = prepare_logs(
+                    logs,
+                    overrides.log_pattern(&connector_id),
+                    settings().manager.logs_min_level,
+                    &connector_id,
+                );
+                let log_sink = &settings().manager.log_sink;
+                // The fetched tail overlaps the previous one, the local sink only gets the new lines
+                if *log_sink != LogSink::Opencti {
+                    let new_logs = state.forwarded_logs.unseen(&connector_id, &logs);
+                    forward_logs(log_sink, api.platform(), &connector_id, &new_logs).await;
+                }
+                // A failed post leaves the last log timestamp behind, as a silent connector would
+                let forwarded = if log_sink.reports_to_platform() {
+                    info!(id = connector_id, "Reporting logs");
+                    api.patch_logs(connector_id.clone(), logs).await.is_some()
+                } else {
+                    true
+                };
+                if forwarded {
+                    record_logs_forwarded(
+                        api.platform(),
+                        &connector_id,
+                        chrono::Utc::now().timestamp(),
+                    );
+                }
             }
             None => {
                 // No logs
             }
         }
-        *tick = now;
+    }
+}
+
+// Connector state carried by an orchestration from one cycle to the next
+#[derive(Debug)]
+pub struct OrchestrationState {
+    pub log_timers: LogTimers,
+    pub forwarded_logs: ForwardedLogs,
+    pub health_tick: Instant,
+    pub restart_backoff: RestartBackoff,
+    pub pending_stops: PendingStops,
+    pub transitions: ConnectorTransitions,
+    pub cooldown: RedeployCooldown,
+}
+
+impl OrchestrationState {
+    pub fn new(manager: &Manager, now: Instant) -> Self {
+        Self {
+            log_timers: LogTimers::new(now, manager.spread_logs_schedule),
+            forwarded_logs: ForwardedLogs::default(),
+            health_tick: now,
+            restart_backoff: RestartBackoff::default(),
+            pending_stops: PendingStops::new(Duration::from_secs(manager.stop_grace_period)),
+            transitions: ConnectorTransitions::new(manager.log_transitions),
+            cooldown: RedeployCooldown::new(Duration::from_secs(manager.redeploy_cooldown)),
+        }
+    }
+}
+
+impl Default for OrchestrationState {
+    fn default() -> Self {
+        Self {
+            log_timers: LogTimers::default(),
+            forwarded_logs: ForwardedLogs::default(),
+            health_tick: Instant::now(),
+            restart_backoff: RestartBackoff::default(),
+            pending_stops: PendingStops::default(),
+            transitions: ConnectorTransitions::default(),
+            cooldown: RedeployCooldown::default(),
+        }
     }
 }
 
 pub async fn orchestrate(
-    tick: &mut Instant,
-    health_tick: &mut Instant,
+    state: &mut OrchestrationState,
     orchestrator: &Box<dyn Orchestrator + Send + Sync>,
     api: &Box<dyn ComposerApi + Send + Sync>,
 ) {
+    // Status patch responses are decrypted too, the tally only covers the connectors listed below
+    api.take_decrypt_tally();
     // Get the current definition from OpenCTI
-    let connectors_response = api.connectors().await;
-    if connectors_response.is_some() {
+    let listing_response = api.listing().await;
+    if listing_response.is_some() {
         // First round trip to instantiate and control if needed
-        let connectors = connectors_response.unwrap();
+        let ConnectorListing {
+            connectors,
+            skipped,
+        } = listing_response.unwrap();
+        status::record_connectors(api.platform(), connectors.len());
+        let reboot_loop_action = settings().manager.reboot_loop_action;
+        let verify_deploy = settings().manager.verify_deploy;
+        let unusable_secrets = secrets_unusable(
+            api.platform(),
+            api.take_decrypt_tally(),
+            settings().manager.fail_on_decrypt_errors,
+        );
+        let logs_on_remove = settings().manager.logs_on_remove;
+        let require_owner_label = settings().manager.require_owner_label;
+        let per_connector_delay = Duration::from_millis(settings().manager.per_connector_delay);
+        let colliding = colliding_connectors(&connectors);
         // Iter on each definition and check alignment between the status and the container
-        for connector in &connectors {
+        for (index, connector) in connectors.iter().enumerate() {
+            if colliding.contains(&connector.id) {
+                continue;
+            }
+            wait_between_connectors(index, per_connector_delay).await;
+            // An overlapping cycle is still operating on this connector, leave it to that cycle
+            let Some(_lock) = ConnectorLock::try_acquire(&connector.id) else {
+                warn!(
+                    id = connector.id,
+                    "Connector locked by another cycle, skipping"
+                );
+                continue;
+            };
             // Get current containers in the orchestrator
             let container_get = orchestrator.get(connector).await;
             match container_get {
                 Some(container) => {
-                    orchestrate_existing(tick, health_tick, orchestrator, api, connector, container).await
+                    orchestrate_existing(
+                        state,
+                        orchestrator,
+                        api,
+                        connector,
+                        container,
+                        reboot_loop_action,
+                        logs_on_remove,
+                        unusable_secrets,
+                    )
+                    .await
+                }
+                None => {
+                    // Removed recently after leaving the listing, wait before deploying it again
+                    if let Some(remaining) = state.cooldown.remaining(&connector.id, Instant::now())
+                    {
+                        info!(
+                            id = connector.id,
+                            remaining_secs = remaining.as_secs(),
+                            "Deployment deferred, connector in redeploy cooldown"
+                        );
+                        continue;
+                    }
+                    orchestrate_missing(
+                        orchestrator,
+                        api,
+                        connector,
+                        verify_deploy,
+                        unusable_secrets,
+                    )
+                    .await
                 }
-                None => orchestrate_missing(orchestrator, api, connector).await,
             }
         }
         // Iter on each existing container to clean the containers
@@ -161,33 +759,99 @@ pub async fn orchestrate(
             .iter()
             .map(|n| (n.id.clone(), n.clone()))
             .collect();
+        state
+            .transitions
+            .retain(|connector_id| connectors_by_id.contains_key(connector_id));
+        state
+            .log_timers
+            .retain(|connector_id| connectors_by_id.contains_key(connector_id));
+        state
+            .forwarded_logs
+            .retain(|connector_id| connectors_by_id.contains_key(connector_id));
+        state
+            .pending_stops
+            .retain(|connector_id| connectors_by_id.contains_key(connector_id));
+        state.cooldown.prune(Instant::now());
+        status::retain_log_timestamps(api.platform(), |connector_id| {
+            connectors_by_id.contains_key(connector_id)
+                || skipped.iter().any(|id| id == connector_id)
+        });
         let platform = api.platform();
-        let existing_containers = orchestrator.list().await;
-        for container in existing_containers {
-            let container_platform = container
-                .labels
-                .get("opencti-platform")
-                .map(|value| value.as_str());
-            // Only skip containers explicitly belonging to another platform
-            if container_platform.is_some() && container_platform != Some(platform) {
+        // Only consider containers of this platform, never the ones of another orchestration
+        let existing_containers = filter_platform_containers(
+            orchestrator.list().await,
+            platform,
+            settings().manager.exclusive_platforms,
+        );
+        // Still listed by the platform but not mappable this cycle, the container is not an orphan
+        let existing_containers: Vec<OrchestratorContainer> = existing_containers
+            .into_iter()
+            .filter(|container| !skipped.contains(&container.extract_opencti_id()))
+            .collect();
+        // A persistently high value signals churn or a mis-scoped manager id
+        let orphaned = count_orphaned_containers(&existing_containers, &connectors_by_id);
+        status::record_orphaned_containers(platform, orphaned);
+        if orphaned > 0 {
+            info!(
+                platform,
+                xtm_orphaned_containers = orphaned,
+                "Orphaned containers detected"
+            );
+        }
+        cleanup_containers(
+            orchestrator,
+            api,
+            existing_containers,
+            &connectors_by_id,
+            logs_on_remove,
+            require_owner_label,
+            &mut state.cooldown,
+        )
+        .await;
+    }
+}
+
+async fn cleanup_containers(
+    orchestrator: &Box<dyn Orchestrator + Send + Sync>,
+    api: &Box<dyn ComposerApi + Send + Sync>,
+    existing_containers: Vec<OrchestratorContainer>,
+    connectors_by_id: &HashMap<String, ApiConnector>,
+    logs_on_remove: bool,
+    require_owner_label: bool,
+    cooldown: &mut RedeployCooldown,
+) {
+    for container in existing_containers {
+        let connector_id = container.extract_opencti_id();
+        let connector = connectors_by_id.get(&connector_id);
+        // Connector no longer exists, or still exists but the deployment name may be stale
+        // after a connector instance name change while the connector ID remains the same.
+        // Remove the old deployment so the next orchestration cycle deploys with the correct name.
+        let stale = connector.is_none_or(|connector| container.name != connector.container_name());
+        if stale {
+            if require_owner_label && !container.is_composer_owned() {
+                warn!(
+                    id = connector_id,
+                    name = container.name,
+                    "Container not created by a composer, removal skipped"
+                );
                 continue;
             }
-            let connector_id = container.extract_opencti_id();
-            match connectors_by_id.get(&connector_id) {
-                None => {
-                    // Connector no longer exists — remove the orphaned container
-                    orchestrator.remove(&container).await;
-                }
-                Some(connector) => {
-                    // Connector still exists but the deployment name may be stale
-                    // after a connector instance name change while the connector ID
-                    // remains the same. Remove the old deployment so the next
-                    // orchestration cycle deploys with the correct name.
-                    let expected_name = connector.container_name();
-                    if container.name != expected_name {
-                        orchestrator.remove(&container).await;
-                    }
-                }
+            // An overlapping cycle is still operating on this connector, leave it to that cycle
+            let Some(_lock) = ConnectorLock::try_acquire(&connector_id) else {
+                warn!(
+                    id = connector_id,
+                    name = container.name,
+                    "Connector locked by another cycle, removal skipped"
+                );
+                continue;
+            };
+            if logs_on_remove {
+                preserve_logs(orchestrator, api, &container, connector).await;
+            }
+            orchestrator.remove(&container).await;
+            // A renamed connector is redeployed right away
+            if connector.is_none() {
+                cooldown.record_removal(&connector_id, Instant::now());
             }
         }
     }
@@ -198,6 +862,9 @@ mod tests {
     use super::*;
     use crate::api::ApiContractConfig;
     use crate::config::settings::Daemon;
+    use crate::orchestrator::PLATFORM_LABEL;
+    use crate::orchestrator::plan::{PlanApi, PlanOrchestrator, ReconcilePlan};
+    use chrono::Utc;
     use std::sync::{Arc, Mutex};
 
     fn connector(id: &str) -> ApiConnector {
@@ -217,7 +884,7 @@ mod tests {
         let mut labels = HashMap::new();
         labels.insert("opencti-manager".to_string(), "shared-manager".to_string());
         labels.insert("opencti-connector-id".to_string(), id.to_string());
-        labels.insert("opencti-platform".to_string(), platform.to_string());
+        labels.insert(PLATFORM_LABEL.to_string(), platform.to_string());
 
         let mut envs = HashMap::new();
         envs.insert("OPENCTI_CONFIG_HASH".to_string(), format!("hash-{id}"));
@@ -233,6 +900,146 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn reconcile_plan_lists_intended_actions_without_performing_them() {
+        let mut started = managed_container("plan-B", "opencti");
+        started.state = "exited".to_string();
+        let mut running = managed_container("plan-C", "opencti");
+        running.state = "running".to_string();
+        let mut outdated = managed_container("plan-D", "opencti");
+        outdated
+            .envs
+            .insert("OPENCTI_CONFIG_HASH".to_string(), "hash-old".to_string());
+        let orphan = managed_container("plan-E", "opencti");
+
+        let mut to_start = connector("plan-B");
+        to_start.requested_status = "starting".to_string();
+        let connectors = vec![
+            connector("plan-A"),
+            to_start,
+            connector("plan-C"),
+            connector("plan-D"),
+        ];
+
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let fake = FakeOrchestrator::new(
+            vec![started, running, outdated, orphan],
+            Arc::clone(&removed_ids),
+        );
+        let started_ids = Arc::clone(&fake.started_ids);
+        let stopped_ids = Arc::clone(&fake.stopped_ids);
+        let refreshed_ids = Arc::clone(&fake.refreshed_ids);
+        let plan = Arc::new(Mutex::new(ReconcilePlan::default()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(PlanOrchestrator::new(Box::new(fake), Arc::clone(&plan)));
+        let fake_api = FakeApi::new(connectors);
+        let patched_statuses = Arc::clone(&fake_api.patched_statuses);
+        let patched_logs = Arc::clone(&fake_api.patched_logs);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(PlanApi::new(Box::new(fake_api)));
+
+        orchestrate(&mut OrchestrationState::default(), &orchestrator, &api).await;
+
+        let plan = plan.lock().expect("mutex should not be poisoned").clone();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            plan,
+            ReconcilePlan {
+                deploy: ids(&["plan-A"]),
+                start: ids(&["plan-B"]),
+                stop: ids(&["plan-C"]),
+                refresh: ids(&["plan-D"]),
+                remove: ids(&["plan-E"]),
+                ..Default::default()
+            }
+        );
+        // Nothing reached the backend
+        for performed in [started_ids, stopped_ids, refreshed_ids, removed_ids] {
+            assert!(
+                performed
+                    .lock()
+                    .expect("mutex should not be poisoned")
+                    .is_empty()
+            );
+        }
+        // Nor the platform
+        assert!(
+            patched_statuses
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+        assert!(
+            patched_logs
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn connectors_sharing_a_container_name_are_skipped() {
+        let mut containers = Vec::new();
+        let mut connectors = Vec::new();
+        for (id, name) in [
+            ("collision-A", "MISP Feed"),
+            ("collision-B", "misp feed"),
+            ("collision-C", "connector-collision-C"),
+        ] {
+            let mut connector = connector(id);
+            connector.name = name.to_string();
+            connector.requested_status = "starting".to_string();
+            let mut container = managed_container(id, "opencti");
+            container.name = connector.container_name();
+            containers.push(container);
+            connectors.push(connector);
+        }
+        assert_eq!(
+            colliding_connectors(&connectors),
+            HashSet::from(["collision-A".to_string(), "collision-B".to_string()])
+        );
+
+        let fake = FakeOrchestrator::new(containers, Arc::new(Mutex::new(Vec::new())));
+        let started_ids = Arc::clone(&fake.started_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(connectors));
+        orchestrate(&mut OrchestrationState::default(), &orchestrator, &api).await;
+
+        let started = started_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(started, vec!["collision-C".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn connector_locked_by_an_overlapping_cycle_is_skipped() {
+        let mut containers = Vec::new();
+        let mut connectors = Vec::new();
+        for id in ["overlap-A", "overlap-B"] {
+            containers.push(managed_container(id, "opencti"));
+            let mut connector = connector(id);
+            connector.requested_status = "starting".to_string();
+            connectors.push(connector);
+        }
+        let fake = FakeOrchestrator::new(containers, Arc::new(Mutex::new(Vec::new())));
+        let started_ids = Arc::clone(&fake.started_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(connectors));
+
+        // Another cycle is still starting overlap-A
+        let overlapping_cycle = ConnectorLock::try_acquire("overlap-A").unwrap();
+        orchestrate(&mut OrchestrationState::default(), &orchestrator, &api).await;
+        drop(overlapping_cycle);
+
+        let started = started_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(started, vec!["overlap-B".to_string()]);
+        // Lock released at the end of the cycle
+        assert!(ConnectorLock::try_acquire("overlap-B").is_some());
+    }
+
     fn legacy_container(id: &str) -> OrchestratorContainer {
         let mut labels = HashMap::new();
         labels.insert("opencti-manager".to_string(), "shared-manager".to_string());
@@ -254,11 +1061,25 @@ mod tests {
 
     struct FakeApi {
         connectors: Vec<ApiConnector>,
+        skipped: Vec<String>,
+        patched_statuses: Arc<Mutex<Vec<(String, ConnectorStatus)>>>,
+        patched_logs: Arc<Mutex<Vec<String>>>,
+        logs_schedule: Duration,
+        logs_accepted: bool,
+        platform: &'static str,
     }
 
     impl FakeApi {
         fn new(connectors: Vec<ApiConnector>) -> Self {
-            Self { connectors }
+            Self {
+                connectors,
+                skipped: Vec::new(),
+                patched_statuses: Arc::new(Mutex::new(Vec::new())),
+                patched_logs: Arc::new(Mutex::new(Vec::new())),
+                logs_schedule: Duration::from_secs(3600),
+                logs_accepted: false,
+                platform: "opencti",
+            }
         }
     }
 
@@ -269,11 +1090,11 @@ mod tests {
         }
 
         fn platform(&self) -> &'static str {
-            "opencti"
+            self.platform
         }
 
         fn post_logs_schedule(&self) -> Duration {
-            Duration::from_secs(3600)
+            self.logs_schedule
         }
 
         async fn version(&self) -> Option<String> {
@@ -292,14 +1113,29 @@ mod tests {
             Some(self.connectors.clone())
         }
 
-        async fn patch_status(&self, _id: String, _status: ConnectorStatus) -> Option<ApiConnector> {
-            None
+        async fn listing(&self) -> Option<ConnectorListing> {
+            Some(ConnectorListing {
+                connectors: self.connectors.clone(),
+                skipped: self.skipped.clone(),
+            })
         }
 
-        async fn patch_logs(&self, _id: String, _logs: Vec<String>) -> Option<String> {
+        async fn patch_status(&self, id: String, status: ConnectorStatus) -> Option<ApiConnector> {
+            self.patched_statuses
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push((id, status));
             None
         }
 
+        async fn patch_logs(&self, id: String, _logs: Vec<String>) -> Option<String> {
+            self.patched_logs
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(id.clone());
+            self.logs_accepted.then_some(id)
+        }
+
         async fn patch_health(
             &self,
             _id: String,
@@ -314,6 +1150,19 @@ mod tests {
     struct FakeOrchestrator {
         containers: Vec<OrchestratorContainer>,
         removed_ids: Arc<Mutex<Vec<String>>>,
+        refreshed_ids: Arc<Mutex<Vec<String>>>,
+        started_ids: Arc<Mutex<Vec<String>>>,
+        stopped_ids: Arc<Mutex<Vec<String>>>,
+        paused_ids: Arc<Mutex<Vec<String>>>,
+        resumed_ids: Arc<Mutex<Vec<String>>>,
+        // Connector ids of the removal logs fetches, with whether the container was already removed
+        removal_logs_ids: Arc<Mutex<Vec<(String, bool)>>>,
+        logs_ids: Arc<Mutex<Vec<String>>>,
+        deployed_ids: Arc<Mutex<Vec<String>>>,
+        drift: Vec<String>,
+        logs_delay: Option<Duration>,
+        deployed: Option<OrchestratorContainer>,
+        deploy_visible: bool,
     }
 
     impl FakeOrchestrator {
@@ -321,6 +1170,18 @@ mod tests {
             Self {
                 containers,
                 removed_ids,
+                refreshed_ids: Arc::new(Mutex::new(Vec::new())),
+                started_ids: Arc::new(Mutex::new(Vec::new())),
+                stopped_ids: Arc::new(Mutex::new(Vec::new())),
+                paused_ids: Arc::new(Mutex::new(Vec::new())),
+                resumed_ids: Arc::new(Mutex::new(Vec::new())),
+                removal_logs_ids: Arc::new(Mutex::new(Vec::new())),
+                logs_ids: Arc::new(Mutex::new(Vec::new())),
+                deployed_ids: Arc::new(Mutex::new(Vec::new())),
+                drift: Vec::new(),
+                logs_delay: None,
+                deployed: None,
+                deploy_visible: false,
             }
         }
     }
@@ -328,8 +1189,10 @@ mod tests {
     #[async_trait::async_trait]
     impl Orchestrator for FakeOrchestrator {
         async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            let deployed = self.deployed.iter().filter(|_| self.deploy_visible);
             self.containers
                 .iter()
+                .chain(deployed)
                 .find(|container| container.labels.get("opencti-connector-id") == Some(&connector.id))
                 .cloned()
         }
@@ -338,9 +1201,33 @@ mod tests {
             self.containers.clone()
         }
 
-        async fn start(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {}
+        async fn start(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+            self.started_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+        }
+
+        async fn stop(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+            self.stopped_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+        }
+
+        async fn pause(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+            self.paused_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+        }
 
-        async fn stop(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {}
+        async fn resume(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+            self.resumed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+        }
 
         async fn remove(&self, container: &OrchestratorContainer) -> () {
             self.removed_ids
@@ -349,53 +1236,191 @@ mod tests {
                 .push(container.extract_opencti_id());
         }
 
-        async fn refresh(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            self.refreshed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
             None
         }
 
-        async fn deploy(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
-            None
+        async fn spec_drift(&self, _connector: &ApiConnector) -> Vec<String> {
+            self.drift.clone()
+        }
+
+        async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            self.deployed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+            self.deployed.clone()
         }
 
         async fn logs(
             &self,
             _container: &OrchestratorContainer,
-            _connector: &ApiConnector,
+            connector: &ApiConnector,
         ) -> Option<Vec<String>> {
-            None
+            self.logs_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(connector.id.clone());
+            if let Some(delay) = self.logs_delay {
+                tokio::time::sleep(delay).await;
+            }
+            Some(vec!["connector log line".to_string()])
+        }
+
+        async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+            let id = container.extract_opencti_id();
+            let removed = self
+                .removed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .contains(&id);
+            self.removal_logs_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push((id, removed));
+            Some(vec!["last log line".to_string()])
         }
 
         fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
-            if container.state == "running" {
-                ConnectorStatus::Started
-            } else {
-                ConnectorStatus::Stopped
+            match container.state.as_str() {
+                "running" => ConnectorStatus::Started,
+                "paused" => ConnectorStatus::Paused,
+                "dead" => ConnectorStatus::Unknown,
+                _ => ConnectorStatus::Stopped,
             }
         }
     }
 
-    #[tokio::test]
-    async fn cleanup_does_not_delete_other_platform_connectors_in_shared_mode() {
-        let all_containers = vec![
-            managed_container("A", "opencti"),
-            managed_container("B", "opencti"),
-            managed_container("C", "opencti"),
-            managed_container("X", "openaev"),
-            managed_container("Y", "openaev"),
+    fn env(key: &str, value: &str, is_sensitive: bool) -> EnvVariable {
+        EnvVariable {
+            key: key.to_string(),
+            value: value.to_string(),
+            is_sensitive,
+        }
+    }
+
+    #[test]
+    fn env_diff_identifies_added_removed_and_changed_keys() {
+        let current = HashMap::from([
+            ("OPENCTI_CONFIG_HASH".to_string(), "hash-1".to_string()),
+            ("CONNECTOR_LOG_LEVEL".to_string(), "info".to_string()),
+            ("LEGACY_SETTING".to_string(), "true".to_string()),
+        ]);
+        let previous_keys = HashSet::from(["CONNECTOR_LOG_LEVEL", "LEGACY_SETTING"]);
+        let requested = vec![
+            env("OPENCTI_CONFIG_HASH", "hash-2", false),
+            env("CONNECTOR_LOG_LEVEL", "info", false),
+            env("CONNECTOR_SCOPE", "report", false),
         ];
 
-        let removed_ids = Arc::new(Mutex::new(Vec::new()));
-        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
-            Box::new(FakeOrchestrator::new(all_containers, Arc::clone(&removed_ids)));
-        let api: Box<dyn ComposerApi + Send + Sync> =
-            Box::new(FakeApi::new(vec![connector("A"), connector("B"), connector("C")]));
+        let diff = diff_container_envs(&current, &previous_keys, &requested);
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        assert_eq!(diff.added, vec!["CONNECTOR_SCOPE=report".to_string()]);
+        assert_eq!(diff.removed, vec!["LEGACY_SETTING".to_string()]);
+        assert_eq!(
+            diff.changed,
+            vec!["OPENCTI_CONFIG_HASH: hash-1 -> hash-2".to_string()]
+        );
+    }
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+    #[test]
+    fn env_diff_redacts_sensitive_values() {
+        let current = HashMap::from([
+            ("API_KEY".to_string(), "old-secret".to_string()),
+            ("DROPPED_TOKEN".to_string(), "dropped-secret".to_string()),
+        ]);
+        let previous_keys = HashSet::from(["API_KEY", "DROPPED_TOKEN"]);
+        let requested = vec![
+            env("API_KEY", "new-secret", true),
+            env("PASSWORD", "added-secret", true),
+        ];
 
-        let removed = removed_ids
+        let diff = diff_container_envs(&current, &previous_keys, &requested);
+
+        assert_eq!(diff.added, vec![format!("PASSWORD={REDACTED_VALUE}")]);
+        assert_eq!(diff.changed, vec![format!("API_KEY: {REDACTED_VALUE} -> {REDACTED_VALUE}")]);
+        assert_eq!(diff.removed, vec!["DROPPED_TOKEN".to_string()]);
+        let rendered = format!("{diff:?}");
+        for secret in ["old-secret", "new-secret", "added-secret", "dropped-secret"] {
+            assert!(!rendered.contains(secret), "diff leaked {secret}: {rendered}");
+        }
+    }
+
+    #[test]
+    fn env_diff_is_empty_when_aligned() {
+        let current = HashMap::from([("CONNECTOR_SCOPE".to_string(), "report".to_string())]);
+        let requested = vec![env("CONNECTOR_SCOPE", "report", false)];
+        let previous_keys = HashSet::from(["CONNECTOR_SCOPE"]);
+        assert!(diff_container_envs(&current, &previous_keys, &requested).is_empty());
+    }
+
+    #[test]
+    fn env_diff_ignores_envs_outside_of_the_previous_contract() {
+        // Image envs and Kubernetes annotations read back along the container envs
+        let current = HashMap::from([
+            ("CONNECTOR_SCOPE".to_string(), "report".to_string()),
+            ("PATH".to_string(), "/usr/local/bin:/usr/bin".to_string()),
+            (
+                "opencti-previous-image".to_string(),
+                "opencti/connector-misp:6.7.0".to_string(),
+            ),
+            (
+                crate::orchestrator::LAST_MODIFIED_BY_LABEL.to_string(),
+                "composer-eu".to_string(),
+            ),
+        ]);
+        let requested = vec![env("CONNECTOR_SCOPE", "report", false)];
+
+        let previous_keys = HashSet::from(["CONNECTOR_SCOPE"]);
+        assert!(diff_container_envs(&current, &previous_keys, &requested).is_empty());
+        // Deployed before the contract keys were recorded
+        assert!(diff_container_envs(&current, &HashSet::new(), &requested).is_empty());
+    }
+
+    #[test]
+    fn stream_tags_are_put_back_after_parsing_and_filtering() {
+        let logs = vec![
+            "[stdout] 2025-06-01 10:00:00 INFO 12 objects imported".to_string(),
+            "[stderr] 2025-06-01 10:00:01 ERROR queue unreachable".to_string(),
+            "[stderr] Traceback (most recent call last):".to_string(),
+        ];
+        let pattern = r"^(?P<timestamp>\S+ \S+) (?P<level>[A-Z]+) (?P<message>.*)$";
+
+        let prepared = prepare_logs(logs, Some(pattern), Some(LogLevel::Warn), "connector-1");
+        assert_eq!(
+            prepared,
+            vec![
+                r#"[stderr] {"timestamp":"2025-06-01 10:00:01","level":"ERROR","message":"queue unreachable"}"#,
+                "[stderr] Traceback (most recent call last):",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cleanup_does_not_delete_other_platform_connectors_in_shared_mode() {
+        let all_containers = vec![
+            managed_container("A", "opencti"),
+            managed_container("B", "opencti"),
+            managed_container("C", "opencti"),
+            managed_container("X", "openaev"),
+            managed_container("Y", "openaev"),
+        ];
+
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(FakeOrchestrator::new(all_containers, Arc::clone(&removed_ids)));
+        let api: Box<dyn ComposerApi + Send + Sync> =
+            Box::new(FakeApi::new(vec![connector("A"), connector("B"), connector("C")]));
+
+        let mut state = OrchestrationState::default();
+
+        orchestrate(&mut state, &orchestrator, &api).await;
+
+        let removed = removed_ids
             .lock()
             .expect("mutex should not be poisoned")
             .clone();
@@ -405,6 +1430,86 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn state_transitions_are_tracked_across_cycles() {
+        let mut state = OrchestrationState {
+            transitions: ConnectorTransitions::new(true),
+            ..Default::default()
+        };
+        for container_state in ["exited", "exited", "running"] {
+            let mut container = managed_container("transition-A", "opencti");
+            container.state = container_state.to_string();
+            let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(
+                FakeOrchestrator::new(vec![container], Arc::new(Mutex::new(Vec::new()))),
+            );
+            let api: Box<dyn ComposerApi + Send + Sync> =
+                Box::new(FakeApi::new(vec![connector("transition-A")]));
+            orchestrate(&mut state, &orchestrator, &api).await;
+        }
+
+        // Last cycle saw the container running, the same state is no transition
+        assert_eq!(
+            state
+                .transitions
+                .record("transition-A", ConnectorStatus::Started),
+            None
+        );
+        assert_eq!(
+            state
+                .transitions
+                .record("transition-A", ConnectorStatus::Stopped),
+            Some(ConnectorStatus::Started)
+        );
+
+        // A connector gone from the platform is forgotten
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(FakeOrchestrator::new(
+            vec![],
+            Arc::new(Mutex::new(Vec::new())),
+        ));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(vec![]));
+        orchestrate(&mut state, &orchestrator, &api).await;
+        assert_eq!(
+            state
+                .transitions
+                .record("transition-A", ConnectorStatus::Started),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn connector_back_in_listing_waits_for_redeploy_cooldown() {
+        let mut state = OrchestrationState {
+            cooldown: RedeployCooldown::new(Duration::from_secs(300)),
+            ..Default::default()
+        };
+        // Gone from the listing, its container is removed
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(FakeOrchestrator::new(
+            vec![managed_container("cooldown-A", "opencti")],
+            Arc::clone(&removed_ids),
+        ));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(vec![]));
+        orchestrate(&mut state, &orchestrator, &api).await;
+        assert_eq!(
+            *removed_ids.lock().expect("mutex should not be poisoned"),
+            vec!["cooldown-A".to_string()]
+        );
+
+        // Listed again on the next cycle, the deploy is deferred
+        let fake = FakeOrchestrator::new(vec![], Arc::new(Mutex::new(Vec::new())));
+        let deployed_ids = Arc::clone(&fake.deployed_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> =
+            Box::new(FakeApi::new(vec![connector("cooldown-A")]));
+        orchestrate(&mut state, &orchestrator, &api).await;
+        assert!(
+            deployed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+    }
+
     #[tokio::test]
     async fn cleanup_removes_only_orphans_for_current_platform() {
         let all_containers = vec![
@@ -421,10 +1526,9 @@ mod tests {
         let api: Box<dyn ComposerApi + Send + Sync> =
             Box::new(FakeApi::new(vec![connector("A"), connector("B"), connector("C")]));
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        let mut state = OrchestrationState::default();
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+        orchestrate(&mut state, &orchestrator, &api).await;
 
         let removed = removed_ids
             .lock()
@@ -446,10 +1550,9 @@ mod tests {
         let api: Box<dyn ComposerApi + Send + Sync> =
             Box::new(FakeApi::new(vec![connector("A")]));
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        let mut state = OrchestrationState::default();
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+        orchestrate(&mut state, &orchestrator, &api).await;
 
         let removed = removed_ids
             .lock()
@@ -471,10 +1574,9 @@ mod tests {
         let api: Box<dyn ComposerApi + Send + Sync> =
             Box::new(FakeApi::new(vec![connector("A"), connector("B")]));
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        let mut state = OrchestrationState::default();
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+        orchestrate(&mut state, &orchestrator, &api).await;
 
         let removed = removed_ids
             .lock()
@@ -502,10 +1604,9 @@ mod tests {
         let api: Box<dyn ComposerApi + Send + Sync> =
             Box::new(FakeApi::new(vec![connector("A"), connector("B")]));
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        let mut state = OrchestrationState::default();
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+        orchestrate(&mut state, &orchestrator, &api).await;
 
         let removed = removed_ids
             .lock()
@@ -532,10 +1633,9 @@ mod tests {
         let api: Box<dyn ComposerApi + Send + Sync> =
             Box::new(FakeApi::new(vec![connector("A"), connector("B")]));
 
-        let mut tick = Instant::now();
-        let mut health_tick = Instant::now();
+        let mut state = OrchestrationState::default();
 
-        orchestrate(&mut tick, &mut health_tick, &orchestrator, &api).await;
+        orchestrate(&mut state, &orchestrator, &api).await;
 
         let removed = removed_ids
             .lock()
@@ -543,4 +1643,1023 @@ mod tests {
             .clone();
         assert!(removed.is_empty(), "correctly named containers should not be removed: {removed:?}");
     }
+
+    #[tokio::test]
+    async fn orphaned_containers_are_counted_before_removal() {
+        // Own platform, the gauge is shared with the cycles of the other tests
+        let platform = "opencti-orphans";
+        let containers = vec![
+            managed_container("A", platform),
+            managed_container("B", platform),
+            managed_container("C", platform),
+        ];
+        let connectors_by_id = HashMap::from([("A".to_string(), connector("A"))]);
+        assert_eq!(count_orphaned_containers(&containers, &connectors_by_id), 2);
+
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let fake = FakeOrchestrator::new(containers, Arc::clone(&removed_ids));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let mut fake_api = FakeApi::new(vec![connector("A")]);
+        fake_api.platform = platform;
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate(&mut OrchestrationState::default(), &orchestrator, &api).await;
+
+        let report: serde_json::Value = serde_json::from_str(&status::status_json()).unwrap();
+        assert_eq!(report["platforms"][platform]["orphaned_containers"], 2);
+        let mut removed = removed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        removed.sort();
+        assert_eq!(removed, vec!["B".to_string(), "C".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn container_of_skipped_connector_is_not_orphaned() {
+        let containers = vec![
+            managed_container("A", "opencti"),
+            managed_container("B", "opencti"),
+            managed_container("C", "opencti"),
+        ];
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> =
+            Box::new(FakeOrchestrator::new(containers, Arc::clone(&removed_ids)));
+        // B is listed by the platform but misses a required field
+        let mut fake_api = FakeApi::new(vec![connector("A")]);
+        fake_api.skipped = vec!["B".to_string()];
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate(&mut OrchestrationState::default(), &orchestrator, &api).await;
+
+        let removed = removed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(removed, vec!["C".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn drift_detection_uses_hash_label_when_env_not_injected() {
+        use crate::orchestrator::CONFIG_HASH_LABEL;
+
+        // Container deployed without OPENCTI_CONFIG_HASH env, hash kept as label
+        let mut aligned = managed_container("A", "opencti");
+        aligned.envs.clear();
+        aligned
+            .labels
+            .insert(CONFIG_HASH_LABEL.to_string(), "hash-A".to_string());
+        let mut drifted = managed_container("B", "opencti");
+        drifted.envs.clear();
+        drifted
+            .labels
+            .insert(CONFIG_HASH_LABEL.to_string(), "hash-B-previous".to_string());
+
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let fake = FakeOrchestrator::new(vec![aligned, drifted], Arc::clone(&removed_ids));
+        let refreshed_ids = Arc::clone(&fake.refreshed_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> =
+            Box::new(FakeApi::new(vec![connector("A"), connector("B")]));
+
+        let mut state = OrchestrationState::default();
+
+        orchestrate(&mut state, &orchestrator, &api).await;
+
+        let refreshed = refreshed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(refreshed, vec!["B".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn container_without_hash_is_refreshed() {
+        // Same shape as a Docker list() result of a legacy container: no envs, no hash label
+        let mut container = managed_container("A", "opencti");
+        container.envs.clear();
+
+        let fake = FakeOrchestrator::new(vec![container], Arc::new(Mutex::new(Vec::new())));
+        let refreshed_ids = Arc::clone(&fake.refreshed_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(vec![connector("A")]));
+
+        let mut state = OrchestrationState::default();
+
+        orchestrate(&mut state, &orchestrator, &api).await;
+
+        let refreshed = refreshed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(refreshed, vec!["A".to_string()]);
+    }
+
+    fn rebooting_container(
+        id: &str,
+        state: &str,
+        restart_count: u32,
+        uptime_secs: i64,
+    ) -> OrchestratorContainer {
+        let mut container = managed_container(id, "opencti");
+        container.state = state.to_string();
+        container.restart_count = restart_count;
+        container.started_at =
+            Some((Utc::now() - chrono::Duration::seconds(uptime_secs)).to_rfc3339());
+        container
+    }
+
+    fn starting_connector(id: &str, current_status: &str) -> ApiConnector {
+        let mut connector = connector(id);
+        connector.requested_status = "starting".to_string();
+        connector.current_status = Some(current_status.to_string());
+        connector
+    }
+
+    struct ExistingRun {
+        started: Vec<String>,
+        stopped: Vec<String>,
+        paused: Vec<String>,
+        resumed: Vec<String>,
+        patched: Vec<(String, ConnectorStatus)>,
+    }
+
+    async fn run_orchestrate_existing(
+        action: RebootLoopAction,
+        state: &mut OrchestrationState,
+        connector: ApiConnector,
+        container: OrchestratorContainer,
+    ) -> ExistingRun {
+        let fake = FakeOrchestrator::new(vec![container.clone()], Arc::new(Mutex::new(Vec::new())));
+        let started_ids = Arc::clone(&fake.started_ids);
+        let stopped_ids = Arc::clone(&fake.stopped_ids);
+        let paused_ids = Arc::clone(&fake.paused_ids);
+        let resumed_ids = Arc::clone(&fake.resumed_ids);
+        let fake_api = FakeApi::new(vec![connector.clone()]);
+        let patched_statuses = Arc::clone(&fake_api.patched_statuses);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate_existing(
+            state,
+            &orchestrator,
+            &api,
+            &connector,
+            container,
+            action,
+            false,
+            false,
+        )
+        .await;
+
+        ExistingRun {
+            started: started_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .clone(),
+            stopped: stopped_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .clone(),
+            paused: paused_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .clone(),
+            resumed: resumed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .clone(),
+            patched: patched_statuses
+                .lock()
+                .expect("mutex should not be poisoned")
+                .clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn reboot_loop_report_only_keeps_connector_running() {
+        let container = rebooting_container("A", "running", 5, 60);
+        assert!(container.is_in_reboot_loop());
+
+        let run = run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "started"),
+            container,
+        )
+        .await;
+
+        assert!(run.stopped.is_empty());
+        assert!(run.started.is_empty());
+        assert!(run.patched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reboot_loop_stop_stops_connector_and_patches_stopped() {
+        let run = run_orchestrate_existing(
+            RebootLoopAction::Stop,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "started"),
+            rebooting_container("A", "running", 5, 60),
+        )
+        .await;
+
+        assert_eq!(run.stopped, vec!["A".to_string()]);
+        assert!(run.started.is_empty());
+        assert_eq!(
+            run.patched,
+            vec![("A".to_string(), ConnectorStatus::Stopped)]
+        );
+    }
+
+    #[tokio::test]
+    async fn reboot_loop_stop_holds_restart_of_stopped_connector() {
+        let run = run_orchestrate_existing(
+            RebootLoopAction::Stop,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "stopped"),
+            rebooting_container("A", "exited", 5, 60),
+        )
+        .await;
+
+        assert!(run.stopped.is_empty());
+        assert!(
+            run.started.is_empty(),
+            "restart should be held: {:?}",
+            run.started
+        );
+    }
+
+    #[tokio::test]
+    async fn reboot_loop_backoff_stops_crashing_connector_and_holds_restart() {
+        let mut state = OrchestrationState::default();
+
+        let run = run_orchestrate_existing(
+            RebootLoopAction::Backoff,
+            &mut state,
+            starting_connector("A", "started"),
+            rebooting_container("A", "running", 5, 60),
+        )
+        .await;
+
+        assert_eq!(run.stopped, vec!["A".to_string()]);
+        assert!(run.started.is_empty());
+        assert_eq!(
+            run.patched,
+            vec![("A".to_string(), ConnectorStatus::Stopped)]
+        );
+        assert_eq!(state.restart_backoff.attempts("A"), 1);
+
+        // Next cycle, the connector is stopped and the backoff delay is not elapsed
+        let run = run_orchestrate_existing(
+            RebootLoopAction::Backoff,
+            &mut state,
+            starting_connector("A", "stopped"),
+            rebooting_container("A", "exited", 5, 60),
+        )
+        .await;
+
+        assert!(run.stopped.is_empty());
+        assert!(run.started.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reboot_loop_backoff_restarts_once_delay_elapsed() {
+        let mut state = OrchestrationState {
+            restart_backoff: RestartBackoff::new(
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_secs(300),
+            ),
+            ..Default::default()
+        };
+        state.restart_backoff.record_crash("A", 5, Instant::now());
+
+        let run = run_orchestrate_existing(
+            RebootLoopAction::Backoff,
+            &mut state,
+            starting_connector("A", "stopped"),
+            rebooting_container("A", "exited", 5, 60),
+        )
+        .await;
+
+        assert!(run.stopped.is_empty());
+        assert_eq!(run.started, vec!["A".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn restart_backoff_resets_once_connector_stays_up() {
+        let mut state = OrchestrationState::default();
+        state.restart_backoff.record_crash("A", 5, Instant::now());
+
+        // Running for 10 minutes, no longer detected as a reboot loop
+        let container = rebooting_container("A", "running", 5, 600);
+        assert!(!container.is_in_reboot_loop());
+        run_orchestrate_existing(
+            RebootLoopAction::Backoff,
+            &mut state,
+            starting_connector("A", "started"),
+            container,
+        )
+        .await;
+
+        assert_eq!(state.restart_backoff.attempts("A"), 0);
+    }
+
+    #[derive(Clone)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .expect("mutex should not be poisoned")
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Events logged at INFO and above while the future runs on the current test thread
+    async fn info_logs<T>(future: impl Future<Output = T>) -> String {
+        let captured = CapturedLogs(Arc::new(Mutex::new(Vec::new())));
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        future.await;
+        let output = captured.0.lock().expect("mutex should not be poisoned");
+        String::from_utf8_lossy(&output).to_string()
+    }
+
+    #[tokio::test]
+    async fn aligned_connector_logs_nothing_at_info() {
+        let logs = info_logs(run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "started"),
+            rebooting_container("A", "running", 0, 600),
+        ))
+        .await;
+        assert_eq!(logs, "");
+
+        // An action taken is still reported
+        let logs = info_logs(run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "stopped"),
+            rebooting_container("A", "exited", 0, 600),
+        ))
+        .await;
+        assert!(logs.contains("Starting"), "{logs}");
+    }
+
+    fn requested_connector(id: &str, requested_status: &str, current_status: &str) -> ApiConnector {
+        let mut connector = connector(id);
+        connector.requested_status = requested_status.to_string();
+        connector.current_status = Some(current_status.to_string());
+        connector
+    }
+
+    async fn run_status_transition(
+        requested_status: &str,
+        current_status: &str,
+        container_state: &str,
+    ) -> ExistingRun {
+        run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut OrchestrationState::default(),
+            requested_connector("A", requested_status, current_status),
+            rebooting_container("A", container_state, 0, 600),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn pausing_suspends_running_connector() {
+        let run = run_status_transition("pausing", "started", "running").await;
+
+        assert_eq!(run.paused, vec!["A".to_string()]);
+        assert!(run.stopped.is_empty());
+        assert!(run.patched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn paused_connector_is_reported_stopped_and_left_alone() {
+        let run = run_status_transition("pausing", "started", "paused").await;
+
+        assert_eq!(
+            run.patched,
+            vec![("A".to_string(), ConnectorStatus::Stopped)]
+        );
+        assert!(run.paused.is_empty());
+        assert!(run.stopped.is_empty());
+        assert!(run.started.is_empty());
+
+        // Already reported stopped, nothing to patch again
+        let run = run_status_transition("paused", "stopped", "paused").await;
+        assert!(run.patched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_state_is_neither_reported_nor_acted_on() {
+        let run = run_status_transition("starting", "started", "dead").await;
+
+        assert!(run.patched.is_empty());
+        assert!(run.started.is_empty());
+        assert!(run.resumed.is_empty());
+
+        let run = run_status_transition("stopping", "stopped", "dead").await;
+        assert!(run.patched.is_empty());
+        assert!(run.stopped.is_empty());
+    }
+
+    #[tokio::test]
+    async fn starting_resumes_paused_connector() {
+        let run = run_status_transition("starting", "stopped", "paused").await;
+
+        assert_eq!(run.resumed, vec!["A".to_string()]);
+        assert!(run.started.is_empty());
+        assert!(run.patched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stopping_stops_paused_connector() {
+        let run = run_status_transition("stopping", "stopped", "paused").await;
+
+        assert_eq!(run.stopped, vec!["A".to_string()]);
+        assert!(run.resumed.is_empty());
+    }
+
+    async fn run_cleanup(logs_on_remove: bool) -> (Vec<(String, bool)>, Vec<String>, Vec<String>) {
+        let mut renamed = managed_container("C", "opencti");
+        renamed.name = "old-connector-name".to_string();
+        let containers = vec![
+            managed_container("A", "opencti"),
+            managed_container("B", "opencti"),
+            renamed,
+        ];
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let fake = FakeOrchestrator::new(containers.clone(), Arc::clone(&removed_ids));
+        let removal_logs_ids = Arc::clone(&fake.removal_logs_ids);
+        let fake_api = FakeApi::new(Vec::new());
+        let patched_logs = Arc::clone(&fake_api.patched_logs);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+        let connectors_by_id = HashMap::from([
+            ("A".to_string(), connector("A")),
+            ("C".to_string(), connector("C")),
+        ]);
+
+        cleanup_containers(
+            &orchestrator,
+            &api,
+            containers,
+            &connectors_by_id,
+            logs_on_remove,
+            false,
+            &mut RedeployCooldown::default(),
+        )
+        .await;
+
+        let removal_logs_ids = removal_logs_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        let removed = removed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        let patched_logs = patched_logs
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        (removal_logs_ids, removed, patched_logs)
+    }
+
+    #[tokio::test]
+    async fn orphan_locked_by_an_overlapping_cycle_is_not_removed() {
+        let containers = vec![managed_container("locked-orphan", "opencti")];
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(FakeOrchestrator::new(
+            containers.clone(),
+            Arc::clone(&removed_ids),
+        ));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(Vec::new()));
+
+        // Another cycle is still operating on the orphan
+        let overlapping_cycle = ConnectorLock::try_acquire("locked-orphan").unwrap();
+        cleanup_containers(
+            &orchestrator,
+            &api,
+            containers.clone(),
+            &HashMap::new(),
+            false,
+            false,
+            &mut RedeployCooldown::default(),
+        )
+        .await;
+        drop(overlapping_cycle);
+        assert!(
+            removed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+
+        cleanup_containers(
+            &orchestrator,
+            &api,
+            containers,
+            &HashMap::new(),
+            false,
+            false,
+            &mut RedeployCooldown::default(),
+        )
+        .await;
+        let removed = removed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(removed, vec!["locked-orphan"]);
+    }
+
+    #[tokio::test]
+    async fn cleanup_requiring_owner_label_keeps_foreign_containers() {
+        let (owner_label, owner_value) = crate::orchestrator::build_owner_label();
+        let mut owned = managed_container("B", "opencti");
+        owned.labels.insert(owner_label.clone(), owner_value);
+        let mut foreign_value = managed_container("D", "opencti");
+        foreign_value
+            .labels
+            .insert(owner_label, "other-tool".to_string());
+        // Same manager label, but never deployed by a composer
+        let containers = vec![owned, managed_container("C", "opencti"), foreign_value];
+        let removed_ids = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(FakeOrchestrator::new(
+            containers.clone(),
+            Arc::clone(&removed_ids),
+        ));
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(Vec::new()));
+
+        for require_owner_label in [true, false] {
+            cleanup_containers(
+                &orchestrator,
+                &api,
+                containers.clone(),
+                &HashMap::new(),
+                false,
+                require_owner_label,
+                &mut RedeployCooldown::default(),
+            )
+            .await;
+        }
+
+        let removed = removed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        // Only the owned container goes with the guard, every orphan without it
+        assert_eq!(removed, vec!["B", "B", "C", "D"]);
+    }
+
+    // Connector ids refreshed for an up to date container reporting the given drift
+    async fn refreshed_on_drift(drift: &[&str]) -> Vec<String> {
+        refreshed(managed_container("A", "opencti"), drift, false).await
+    }
+
+    async fn refreshed(
+        container: OrchestratorContainer,
+        drift: &[&str],
+        secrets_unusable: bool,
+    ) -> Vec<String> {
+        let mut fake =
+            FakeOrchestrator::new(vec![container.clone()], Arc::new(Mutex::new(Vec::new())));
+        fake.drift = drift.iter().map(|field| field.to_string()).collect();
+        let refreshed_ids = Arc::clone(&fake.refreshed_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(FakeApi::new(vec![connector("A")]));
+
+        orchestrate_existing(
+            &mut OrchestrationState::default(),
+            &orchestrator,
+            &api,
+            &connector("A"),
+            container,
+            RebootLoopAction::ReportOnly,
+            false,
+            secrets_unusable,
+        )
+        .await;
+        refreshed_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn drifted_deployment_is_refreshed_back() {
+        assert!(refreshed_on_drift(&[]).await.is_empty());
+        assert_eq!(refreshed_on_drift(&["image"]).await, vec!["A".to_string()]);
+        assert_eq!(refreshed_on_drift(&["env"]).await, vec!["A".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_refreshed_with_unusable_secrets() {
+        let mut outdated = managed_container("A", "opencti");
+        outdated.envs.clear();
+        assert_eq!(
+            refreshed(outdated.clone(), &[], false).await,
+            vec!["A".to_string()]
+        );
+        assert!(refreshed(outdated, &[], true).await.is_empty());
+        let up_to_date = managed_container("A", "opencti");
+        assert!(refreshed(up_to_date, &["env"], true).await.is_empty());
+    }
+
+    // Connector ids whose logs were fetched, with the logs schedule already elapsed
+    async fn logs_fetched(state: &str, logs_on_remove: bool) -> Vec<String> {
+        let mut container = managed_container("A", "opencti");
+        container.state = state.to_string();
+        let fake = FakeOrchestrator::new(vec![container.clone()], Arc::new(Mutex::new(Vec::new())));
+        let logs_ids = Arc::clone(&fake.logs_ids);
+        let mut fake_api = FakeApi::new(vec![connector("A")]);
+        fake_api.logs_schedule = Duration::ZERO;
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        let mut state = OrchestrationState::default();
+        orchestrate_existing(
+            &mut state,
+            &orchestrator,
+            &api,
+            &connector("A"),
+            container,
+            RebootLoopAction::ReportOnly,
+            logs_on_remove,
+            false,
+        )
+        .await;
+        logs_ids
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone()
+    }
+
+    // Last log timestamp of the connector once its logs are posted to the platform
+    async fn last_log_timestamp_after_post(id: &str, logs_accepted: bool) -> Option<i64> {
+        let mut container = managed_container(id, "opencti");
+        container.state = "running".to_string();
+        let fake = FakeOrchestrator::new(vec![container.clone()], Arc::new(Mutex::new(Vec::new())));
+        let mut fake_api = FakeApi::new(vec![connector(id)]);
+        fake_api.logs_schedule = Duration::ZERO;
+        fake_api.logs_accepted = logs_accepted;
+        // Own platform, the cycles of the other tests prune the timestamps of theirs
+        fake_api.platform = "opencti-log-timestamps";
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate_existing(
+            &mut OrchestrationState::default(),
+            &orchestrator,
+            &api,
+            &connector(id),
+            container,
+            RebootLoopAction::ReportOnly,
+            false,
+            false,
+        )
+        .await;
+        let report: serde_json::Value = serde_json::from_str(&status::status_json()).unwrap();
+        report["platforms"]["opencti-log-timestamps"]["last_log_timestamps"][id].as_i64()
+    }
+
+    #[tokio::test]
+    async fn last_log_timestamp_follows_successful_posts_only() {
+        let before = Utc::now().timestamp();
+        let timestamp = last_log_timestamp_after_post("logs-accepted", true)
+            .await
+            .unwrap();
+        assert!(timestamp >= before, "{timestamp}");
+
+        assert_eq!(
+            last_log_timestamp_after_post("logs-rejected", false).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn logs_are_not_fetched_from_stopped_container() {
+        assert!(logs_fetched("exited", false).await.is_empty());
+        assert_eq!(logs_fetched("running", false).await, vec!["A".to_string()]);
+        // Final logs collection keeps fetching the logs of stopped containers
+        assert_eq!(logs_fetched("exited", true).await, vec!["A".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn logs_are_preserved_before_removal_when_enabled() {
+        let (removal_logs_ids, removed, patched_logs) = run_cleanup(true).await;
+
+        assert_eq!(removed, vec!["B".to_string(), "C".to_string()]);
+        // Fetched before the removal of each container
+        assert_eq!(
+            removal_logs_ids,
+            vec![("B".to_string(), false), ("C".to_string(), false)]
+        );
+        // Only the connector still known by the platform gets its logs reported
+        assert_eq!(patched_logs, vec!["C".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn logs_are_not_collected_on_removal_by_default() {
+        let (removal_logs_ids, removed, patched_logs) = run_cleanup(false).await;
+
+        assert_eq!(removed, vec!["B".to_string(), "C".to_string()]);
+        assert!(removal_logs_ids.is_empty());
+        assert!(patched_logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn per_connector_delay_is_applied_between_iterations() {
+        let delay = Duration::from_millis(40);
+        let started = Instant::now();
+        for index in 0..3 {
+            wait_between_connectors(index, delay).await;
+        }
+        // No wait before the first connector, one delay before each of the others
+        let elapsed = started.elapsed();
+        assert!(elapsed >= delay * 2, "{elapsed:?}");
+
+        let started = Instant::now();
+        for index in 0..3 {
+            wait_between_connectors(index, Duration::ZERO).await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(20));
+    }
+
+    fn stopping_connector(id: &str) -> ApiConnector {
+        let mut connector = connector(id);
+        connector.current_status = Some("started".to_string());
+        connector
+    }
+
+    #[tokio::test]
+    async fn stop_in_progress_is_not_reissued() {
+        let mut state = OrchestrationState {
+            pending_stops: PendingStops::new(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let run = run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut state,
+            stopping_connector("A"),
+            rebooting_container("A", "running", 0, 600),
+        )
+        .await;
+        assert_eq!(run.stopped, vec!["A".to_string()]);
+        assert!(
+            state
+                .pending_stops
+                .in_progress("A", Instant::now())
+                .is_some()
+        );
+
+        // Next cycle, the container is still stopping
+        let run = run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut state,
+            stopping_connector("A"),
+            rebooting_container("A", "running", 0, 600),
+        )
+        .await;
+        assert!(run.stopped.is_empty());
+        assert!(run.patched.is_empty());
+
+        // Container stopped, the pending stop is forgotten
+        let run = run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut state,
+            stopping_connector("A"),
+            managed_container("A", "opencti"),
+        )
+        .await;
+        assert_eq!(
+            run.patched,
+            vec![("A".to_string(), ConnectorStatus::Stopped)]
+        );
+        assert!(state.pending_stops.can_stop("A", Instant::now()));
+    }
+
+    #[tokio::test]
+    async fn stop_is_reissued_once_grace_period_elapsed() {
+        let mut state = OrchestrationState {
+            pending_stops: PendingStops::new(Duration::ZERO),
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            let run = run_orchestrate_existing(
+                RebootLoopAction::ReportOnly,
+                &mut state,
+                stopping_connector("A"),
+                rebooting_container("A", "running", 0, 600),
+            )
+            .await;
+            assert_eq!(run.stopped, vec!["A".to_string()]);
+        }
+    }
+
+    async fn fetch_logs_with_delay(logs_delay: Option<Duration>) -> Option<Vec<String>> {
+        let container = managed_container("A", "opencti");
+        let mut fake =
+            FakeOrchestrator::new(vec![container.clone()], Arc::new(Mutex::new(Vec::new())));
+        fake.logs_delay = logs_delay;
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+
+        fetch_connector_logs(
+            &orchestrator,
+            &container,
+            &connector("A"),
+            Duration::from_millis(50),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn logs_fetch_returns_logs_within_timeout() {
+        let logs = fetch_logs_with_delay(None).await;
+        assert_eq!(logs, Some(vec!["connector log line".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn logs_fetch_is_skipped_on_timeout() {
+        let started = Instant::now();
+        let logs = fetch_logs_with_delay(Some(Duration::from_secs(10))).await;
+        assert_eq!(logs, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn external_state_change_detection() {
+        use ConnectorStatus::{Started, Stopped};
+        use RequestedStatus::{Starting, Stopping};
+
+        assert!(is_external_state_change(Starting, Started, Stopped));
+        assert!(is_external_state_change(Stopping, Stopped, Started));
+        // Regular transitions driven by the composer
+        assert!(!is_external_state_change(Starting, Stopped, Started));
+        assert!(!is_external_state_change(Stopping, Started, Stopped));
+        assert!(!is_external_state_change(Starting, Started, Started));
+    }
+
+    #[tokio::test]
+    async fn externally_stopped_container_is_reported_and_counted() {
+        let before = EXTERNAL_STATE_CHANGES.load(Ordering::Relaxed);
+        // Platform believes the connector runs, the container was stopped by an operator
+        let run = run_orchestrate_existing(
+            RebootLoopAction::ReportOnly,
+            &mut OrchestrationState::default(),
+            starting_connector("A", "started"),
+            managed_container("A", "opencti"),
+        )
+        .await;
+
+        assert_eq!(
+            run.patched,
+            vec![("A".to_string(), ConnectorStatus::Stopped)]
+        );
+        assert!(EXTERNAL_STATE_CHANGES.load(Ordering::Relaxed) > before);
+        // Requested status is still started, the composer brings it back
+        assert_eq!(run.started, vec!["A".to_string()]);
+    }
+
+    async fn deploy_patches(
+        deployed: Option<OrchestratorContainer>,
+        deploy_visible: bool,
+        verify_deploy: bool,
+    ) -> Vec<(String, ConnectorStatus)> {
+        let mut fake = FakeOrchestrator::new(Vec::new(), Arc::new(Mutex::new(Vec::new())));
+        fake.deployed = deployed;
+        fake.deploy_visible = deploy_visible;
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let fake_api = FakeApi::new(vec![connector("A")]);
+        let patched_statuses = Arc::clone(&fake_api.patched_statuses);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate_missing(&orchestrator, &api, &connector("A"), verify_deploy, false).await;
+
+        patched_statuses
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn deploy_is_skipped_when_no_sensitive_value_decrypts() {
+        // Every sensitive value of the cycle failed, the credentials key is wrong
+        let tally = DecryptTally {
+            decrypted: 0,
+            failed: 3,
+        };
+        assert!(!secrets_unusable("opencti", tally, false));
+        assert!(secrets_unusable("opencti", tally, true));
+        // A few failures among decrypted values are not blocking
+        let partial = DecryptTally {
+            decrypted: 2,
+            failed: 1,
+        };
+        assert!(!secrets_unusable("opencti", partial, true));
+
+        let mut fake = FakeOrchestrator::new(Vec::new(), Arc::new(Mutex::new(Vec::new())));
+        fake.deployed = Some(managed_container("A", "opencti"));
+        let deployed_ids = Arc::clone(&fake.deployed_ids);
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let fake_api = FakeApi::new(vec![connector("A")]);
+        let patched_statuses = Arc::clone(&fake_api.patched_statuses);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        let skip = secrets_unusable("opencti", tally, true);
+        orchestrate_missing(&orchestrator, &api, &connector("A"), false, skip).await;
+
+        assert!(
+            deployed_ids
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+        assert!(
+            patched_statuses
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn deploy_without_verification_patches_stopped() {
+        let patched = deploy_patches(Some(managed_container("A", "opencti")), false, false).await;
+        assert_eq!(patched, vec![("A".to_string(), ConnectorStatus::Stopped)]);
+    }
+
+    #[tokio::test]
+    async fn deploy_of_started_connector_patches_running_state() {
+        // Docker returns the created container, then starts it as requested
+        let mut running = managed_container("A", "opencti");
+        running.state = "running".to_string();
+        let mut fake = FakeOrchestrator::new(vec![running], Arc::new(Mutex::new(Vec::new())));
+        fake.deployed = Some(managed_container("A", "opencti"));
+        let orchestrator: Box<dyn Orchestrator + Send + Sync> = Box::new(fake);
+        let mut starting = connector("A");
+        starting.requested_status = "starting".to_string();
+        let fake_api = FakeApi::new(vec![starting.clone()]);
+        let patched_statuses = Arc::clone(&fake_api.patched_statuses);
+        let api: Box<dyn ComposerApi + Send + Sync> = Box::new(fake_api);
+
+        orchestrate_missing(&orchestrator, &api, &starting, false, false).await;
+
+        let patched = patched_statuses
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(patched, vec![("A".to_string(), ConnectorStatus::Started)]);
+    }
+
+    #[tokio::test]
+    async fn verified_deploy_patches_status_of_found_container() {
+        let mut running = managed_container("A", "opencti");
+        running.state = "running".to_string();
+
+        let patched = deploy_patches(Some(running), true, true).await;
+
+        assert_eq!(patched, vec![("A".to_string(), ConnectorStatus::Started)]);
+    }
+
+    #[tokio::test]
+    async fn deploy_in_unknown_state_is_not_reported() {
+        let mut dead = managed_container("A", "opencti");
+        dead.state = "dead".to_string();
+
+        for verify_deploy in [false, true] {
+            let patched = deploy_patches(Some(dead.clone()), true, verify_deploy).await;
+            assert!(patched.is_empty(), "unexpected patches: {patched:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn verified_deploy_does_not_patch_missing_container() {
+        let patched = deploy_patches(Some(managed_container("A", "opencti")), false, true).await;
+        assert!(patched.is_empty(), "unexpected patches: {patched:?}");
+    }
+
+    #[tokio::test]
+    async fn canceled_deploy_is_not_verified() {
+        let patched = deploy_patches(None, true, true).await;
+        assert!(patched.is_empty(), "unexpected patches: {patched:?}");
+    }
 }
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Connectors removed because they left the platform listing, kept for the cooldown
+// period so a connector flapping in and out of the listing is not redeployed each cycle
+#[derive(Debug, Default)]
+pub struct RedeployCooldown {
+    period: Duration,
+    removals: HashMap<String, Instant>,
+}
+
+impl RedeployCooldown {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            removals: HashMap::new(),
+        }
+    }
+
+    pub fn record_removal(&mut self, connector_id: &str, now: Instant) {
+        if !self.period.is_zero() {
+            self.removals.insert(connector_id.to_string(), now);
+        }
+    }
+
+    // Time left before the connector can be deployed again, None once the cooldown is over
+    pub fn remaining(&mut self, connector_id: &str, now: Instant) -> Option<Duration> {
+        let removed_at = *self.removals.get(connector_id)?;
+        let remaining = self
+            .period
+            .saturating_sub(now.saturating_duration_since(removed_at));
+        if remaining.is_zero() {
+            self.removals.remove(connector_id);
+            return None;
+        }
+        Some(remaining)
+    }
+
+    // Forget the elapsed cooldowns of connectors never listed again
+    pub fn prune(&mut self, now: Instant) {
+        let period = self.period;
+        self.removals
+            .retain(|_, removed_at| now.saturating_duration_since(*removed_at) < period);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redeploy_is_deferred_during_cooldown() {
+        let mut cooldown = RedeployCooldown::new(Duration::from_secs(300));
+        let now = Instant::now();
+        assert_eq!(cooldown.remaining("A", now), None);
+
+        cooldown.record_removal("A", now);
+        assert_eq!(
+            cooldown.remaining("A", now + Duration::from_secs(100)),
+            Some(Duration::from_secs(200))
+        );
+        // Other connectors are not affected
+        assert_eq!(cooldown.remaining("B", now), None);
+    }
+
+    #[test]
+    fn cooldown_is_forgotten_once_elapsed() {
+        let mut cooldown = RedeployCooldown::new(Duration::from_secs(300));
+        let now = Instant::now();
+        cooldown.record_removal("A", now);
+
+        assert_eq!(
+            cooldown.remaining("A", now + Duration::from_secs(300)),
+            None
+        );
+        assert!(cooldown.removals.is_empty());
+    }
+
+    #[test]
+    fn elapsed_cooldowns_are_pruned() {
+        let mut cooldown = RedeployCooldown::new(Duration::from_secs(300));
+        let now = Instant::now();
+        cooldown.record_removal("A", now);
+        cooldown.record_removal("B", now + Duration::from_secs(200));
+
+        cooldown.prune(now + Duration::from_secs(300));
+        assert_eq!(cooldown.removals.len(), 1);
+        assert!(cooldown.removals.contains_key("B"));
+    }
+
+    #[test]
+    fn no_cooldown_by_default() {
+        let mut cooldown = RedeployCooldown::default();
+        let now = Instant::now();
+        cooldown.record_removal("A", now);
+        assert_eq!(cooldown.remaining("A", now), None);
+    }
+}
@@ -1,15 +1,21 @@
 use crate::api::{ApiConnector, ConnectorStatus};
 use crate::orchestrator::docker::DockerOrchestrator;
-use crate::orchestrator::image::Image;
+use crate::orchestrator::image::{
+    Image, current_registry_config, pull_error_status, pull_permit, report_pull_failure,
+};
 use crate::api::PROXY_CA_CERT_MOUNT_PATH;
-use crate::orchestrator::ensure_proxy_ca_file;
+use crate::orchestrator::{
+    STDERR_TAG, STDOUT_TAG, build_contract_keys_label, build_owner_label, container_hostname,
+    ensure_proxy_ca_file, unrecognized_state_status,
+};
 use crate::orchestrator::{Orchestrator, OrchestratorContainer};
 use async_trait::async_trait;
 use bollard::Docker;
+use bollard::container::LogOutput;
 
-use bollard::models::{ContainerCreateBody, HostConfig};
+use bollard::models::{ContainerCreateBody, DeviceRequest, HostConfig};
 use bollard::query_parameters::{
-    CreateContainerOptions, CreateImageOptions, InspectContainerOptions, ListContainersOptions,
+    CreateContainerOptions, InspectContainerOptions, ListContainersOptions,
     LogsOptions, RemoveContainerOptions, StartContainerOptions, StopContainerOptions,
 };
 use futures::TryStreamExt;
@@ -36,6 +42,145 @@ impl DockerOrchestrator {
     pub fn normalize_name(name: Option<String>) -> String {
         name.unwrap().strip_prefix("/").unwrap().into()
     }
+
+    // Host or container network / UTS modes reuse the hostname of another namespace
+    fn shares_uts_namespace(docker_opts: &crate::config::settings::Docker) -> bool {
+        let shared = |mode: &Option<String>| {
+            mode.as_deref()
+                .is_some_and(|mode| mode == "host" || mode.starts_with("container:"))
+        };
+        shared(&docker_opts.network_mode) || shared(&docker_opts.uts_mode)
+    }
+
+    // Docker rejects a hostname when the UTS namespace is shared
+    fn hostname(
+        docker_opts: Option<&crate::config::settings::Docker>,
+        connector: &ApiConnector,
+    ) -> Option<String> {
+        match docker_opts {
+            Some(docker_opts) if Self::shares_uts_namespace(docker_opts) => None,
+            Some(docker_opts) => Some(container_hostname(connector, docker_opts.hostname.as_ref())),
+            None => Some(container_hostname(connector, None)),
+        }
+    }
+
+    // Same defaults as `docker run --gpus all`, every GPU of the host with the gpu capability
+    pub fn device_requests(
+        docker_opts: &crate::config::settings::Docker,
+        connector_id: &str,
+    ) -> Option<Vec<DeviceRequest>> {
+        let gpu = docker_opts.connector_gpus.as_ref()?.get(connector_id)?;
+        let count = match (&gpu.device_ids, gpu.count) {
+            (Some(_), _) => None,
+            (None, count) => Some(count.unwrap_or(-1)),
+        };
+        let capabilities = gpu
+            .capabilities
+            .clone()
+            .unwrap_or_else(|| vec!["gpu".to_string()]);
+        Some(vec![DeviceRequest {
+            driver: gpu.driver.clone(),
+            count,
+            device_ids: gpu.device_ids.clone(),
+            capabilities: Some(vec![capabilities]),
+            ..Default::default()
+        }])
+    }
+
+    // Tag the line with its stream when split, TTY (console) output has no stream to tell
+    pub fn log_line(log: LogOutput, split_streams: bool) -> String {
+        match log {
+            LogOutput::StdOut { message } if split_streams => {
+                format!("{STDOUT_TAG}{}", String::from_utf8_lossy(&message))
+            }
+            LogOutput::StdErr { message } if split_streams => {
+                format!("{STDERR_TAG}{}", String::from_utf8_lossy(&message))
+            }
+            log => log.to_string(),
+        }
+    }
+
+    // Docker engine container states, shared with Portainer
+    pub fn state_status(state: &str, distinct_unknown: bool) -> ConnectorStatus {
+        match state {
+            "running" => ConnectorStatus::Started,
+            "paused" => ConnectorStatus::Paused,
+            "created" | "exited" | "restarting" => ConnectorStatus::Stopped,
+            _ => unrecognized_state_status(distinct_unknown),
+        }
+    }
+
+    // Removed out of band between the get and the operation
+    fn is_not_found(err: &bollard::errors::Error) -> bool {
+        matches!(
+            err,
+            bollard::errors::Error::DockerResponseServerError {
+                status_code: 404,
+                ..
+            }
+        )
+    }
+
+    // Outcome of a start / stop / pause / unpause, a container already in the requested
+    // state (304) or already removed is not an error
+    fn report_action(
+        container_name: &str,
+        action: &str,
+        result: Result<(), bollard::errors::Error>,
+    ) {
+        match result {
+            Ok(())
+            | Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 304, ..
+            }) => {}
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name = container_name, action, "Container already removed");
+            }
+            Err(err) => {
+                error!(
+                    name = container_name,
+                    action,
+                    error = err.to_string(),
+                    "Container action failed"
+                );
+            }
+        }
+    }
+
+    // Last 100 lines of a container, by name or id
+    async fn container_logs(&self, container: &str) -> Option<Vec<String>> {
+        let opts = Some(LogsOptions {
+            follow: false,
+            stdout: true,
+            stderr: true,
+            tail: "100".to_string(),
+            ..Default::default()
+        });
+        let split_streams = crate::settings().manager.split_log_streams;
+        let logs = self.docker.logs(container, opts);
+        let mut logs_content = Vec::new();
+        let logs_result = logs
+            .try_for_each(|log| {
+                logs_content.push(DockerOrchestrator::log_line(log, split_streams));
+                future::ok(())
+            })
+            .await;
+        match logs_result {
+            Ok(()) => Some(logs_content),
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name = container, "Container already removed, no logs");
+                None
+            }
+            Err(err) => {
+                error!(
+                    name = container,
+                    error = err.to_string(),
+                    "Error fetching logs"
+                );
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -72,6 +217,14 @@ impl Orchestrator for DockerOrchestrator {
         }
     }
 
+    async fn ping(&self) -> Result<(), String> {
+        self.docker
+            .ping()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
     async fn list(&self) -> Vec<OrchestratorContainer> {
         let settings = crate::settings();
         let manager_label = format!("opencti-manager={}", settings.manager.id.clone());
@@ -113,29 +266,57 @@ impl Orchestrator for DockerOrchestrator {
     async fn start(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
         connector.display_env_variables();
         let container_name = connector.container_name();
-        let _ = self
+        let result = self
             .docker
             .start_container(container_name.as_str(), None::<StartContainerOptions>)
             .await;
+        Self::report_action(&container_name, "start", result);
     }
 
     async fn stop(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
         let container_name = connector.container_name();
-        let _ = self
+        let result = self
             .docker
             .stop_container(container_name.as_str(), None::<StopContainerOptions>)
             .await;
+        Self::report_action(&container_name, "stop", result);
+    }
+
+    async fn pause(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        let container_name = connector.container_name();
+        let result = self.docker.pause_container(container_name.as_str()).await;
+        Self::report_action(&container_name, "pause", result);
+    }
+
+    async fn resume(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        let container_name = connector.container_name();
+        let result = self.docker.unpause_container(container_name.as_str()).await;
+        Self::report_action(&container_name, "unpause", result);
     }
 
     async fn remove(&self, container: &OrchestratorContainer) -> () {
         let container_name = container.name.as_str();
+        let graceful_timeout = crate::settings().manager.graceful_remove_timeout();
+        if let Some(timeout) = graceful_timeout {
+            // Let the connector shutdown cleanly before removing it
+            let _ = self
+                .docker
+                .stop_container(
+                    container_name,
+                    Some(StopContainerOptions {
+                        t: Some(timeout as i32),
+                        signal: None,
+                    }),
+                )
+                .await;
+        }
         let remove_response = self
             .docker
             .remove_container(
                 container_name,
                 Some(RemoveContainerOptions {
                     v: true,
-                    force: true,
+                    force: graceful_timeout.is_none(),
                     link: false,
                 }),
             )
@@ -144,6 +325,9 @@ impl Orchestrator for DockerOrchestrator {
             Ok(_) => {
                 info!(name = container_name, "Removed container");
             }
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name = container_name, "Container already removed");
+            }
             Err(err) => {
                 error!(
                     name = container_name,
@@ -165,19 +349,16 @@ impl Orchestrator for DockerOrchestrator {
     }
 
     async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
-        let settings = crate::settings();
-        let registry_config = settings.opencti.daemon.registry.clone();
+        let registry_config = current_registry_config();
         let resolver = Image::new(registry_config);
         let auth = resolver.get_credentials();
-        let image = resolver.build_name(connector.image.clone());
+        let image = resolver.build_name(connector.image());
 
+        let pull_permit = pull_permit(&image).await;
         let deploy_response = self
             .docker
             .create_image(
-                Some(CreateImageOptions {
-                    from_image: Some(image.clone()),
-                    ..Default::default()
-                }),
+                Some(resolver.create_image_options(&image)),
                 None,
                 auth,
             )
@@ -191,6 +372,7 @@ impl Orchestrator for DockerOrchestrator {
                 future::ok(())
             })
             .await;
+        drop(pull_permit);
 
         match deploy_response {
             Ok(_) => {
@@ -200,7 +382,15 @@ impl Orchestrator for DockerOrchestrator {
                     .into_iter()
                     .map(|config| format!("{}={}", config.key, config.value))
                     .collect::<Vec<String>>();
-                let labels = self.labels(connector);
+                let mut labels = self.labels(connector);
+                labels.extend(self.requested_status_label(connector));
+                labels.extend(self.last_modified_by_label());
+                labels.extend(self.config_hash_label(connector));
+                labels.extend([
+                    self.contract_hash_label(connector),
+                    build_owner_label(),
+                    build_contract_keys_label(connector),
+                ]);
 
                 // Build host config with Docker options
                 let mut host_config = HostConfig::default();
@@ -280,6 +470,7 @@ impl Orchestrator for DockerOrchestrator {
                             host_config.ulimits = Some(ulimits_vec);
                         }
                     }
+                    host_config.device_requests = Self::device_requests(docker_opts, &connector.id);
                 }
 
                 if let Some(proxy_ca_host_path) = ensure_proxy_ca_file(connector) {
@@ -293,6 +484,7 @@ impl Orchestrator for DockerOrchestrator {
 
                 let config = ContainerCreateBody {
                     image: Some(image),
+                    hostname: Self::hostname(docker_options, connector),
                     env: Some(container_env_variables),
                     labels: Some(labels),
                     host_config: Some(host_config),
@@ -327,11 +519,7 @@ impl Orchestrator for DockerOrchestrator {
                 created
             }
             Err(e) => {
-                error!(
-                    image = image,
-                    error = e.to_string(),
-                    "Error fetching container image"
-                );
+                report_pull_failure(&image, pull_error_status(&e), &e.to_string());
                 None
             }
         }
@@ -342,28 +530,218 @@ impl Orchestrator for DockerOrchestrator {
         _container: &OrchestratorContainer,
         connector: &ApiConnector,
     ) -> Option<Vec<String>> {
-        let opts = Some(LogsOptions {
-            follow: false,
-            stdout: true,
-            stderr: true,
-            tail: "100".to_string(),
-            ..Default::default()
-        });
-        let logs = self.docker.logs(connector.container_name().as_str(), opts);
-        let mut logs_content = Vec::new();
-        logs.try_for_each(|log| {
-            logs_content.push(log.to_string());
-            future::ok(())
-        })
-        .await
-        .unwrap();
-        Some(logs_content)
+        self.container_logs(connector.container_name().as_str())
+            .await
+    }
+
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        self.container_logs(container.id.as_str()).await
     }
 
     fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
-        match container.state.as_str() {
-            "running" => ConnectorStatus::Started,
-            _ => ConnectorStatus::Stopped,
+        let distinct_unknown = crate::settings().manager.distinct_unknown_status;
+        Self::state_status(&container.state, distinct_unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn docker_options(value: serde_json::Value) -> crate::config::settings::Docker {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn hostname_allowed_with_private_namespaces() {
+        assert!(!DockerOrchestrator::shares_uts_namespace(&docker_options(
+            serde_json::json!({})
+        )));
+        assert!(!DockerOrchestrator::shares_uts_namespace(&docker_options(
+            serde_json::json!({ "network_mode": "bridge", "hostname": "connectors" })
+        )));
+    }
+
+    #[test]
+    fn hostname_skipped_with_shared_namespaces() {
+        for options in [
+            serde_json::json!({ "network_mode": "host" }),
+            serde_json::json!({ "network_mode": "container:opencti" }),
+            serde_json::json!({ "uts_mode": "host" }),
+        ] {
+            assert!(
+                DockerOrchestrator::shares_uts_namespace(&docker_options(options.clone())),
+                "{options}"
+            );
         }
     }
+
+    #[test]
+    fn container_hostname_prefers_configured_value_over_container_name() {
+        let connector = ApiConnector::for_test("docker-hostname");
+        assert_eq!(
+            DockerOrchestrator::hostname(None, &connector),
+            Some(connector.container_name())
+        );
+        let options = docker_options(serde_json::json!({ "network_mode": "bridge" }));
+        assert_eq!(
+            DockerOrchestrator::hostname(Some(&options), &connector),
+            Some(connector.container_name())
+        );
+        let options = docker_options(serde_json::json!({ "hostname": "connectors" }));
+        assert_eq!(
+            DockerOrchestrator::hostname(Some(&options), &connector),
+            Some("connectors".to_string())
+        );
+    }
+
+    #[test]
+    fn container_hostname_is_not_set_with_shared_namespaces() {
+        let options = docker_options(serde_json::json!({
+            "network_mode": "host",
+            "hostname": "connectors"
+        }));
+        let connector = ApiConnector::for_test("docker-hostname");
+        assert_eq!(
+            DockerOrchestrator::hostname(Some(&options), &connector),
+            None
+        );
+    }
+
+    #[test]
+    fn gpu_device_request_is_set_for_configured_connector() {
+        let options = docker_options(serde_json::json!({
+            "connector_gpus": {
+                "all-gpus": {},
+                "pinned-gpus": { "device_ids": ["0", "2"], "driver": "nvidia" }
+            }
+        }));
+
+        let requests = DockerOrchestrator::device_requests(&options, "all-gpus").unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].count, Some(-1));
+        assert_eq!(requests[0].device_ids, None);
+        assert_eq!(
+            requests[0].capabilities,
+            Some(vec![vec!["gpu".to_string()]])
+        );
+
+        let requests = DockerOrchestrator::device_requests(&options, "pinned-gpus").unwrap();
+        assert_eq!(requests[0].count, None);
+        assert_eq!(requests[0].driver.as_deref(), Some("nvidia"));
+        assert_eq!(
+            requests[0].device_ids,
+            Some(vec!["0".to_string(), "2".to_string()])
+        );
+
+        assert_eq!(
+            DockerOrchestrator::device_requests(&options, "cpu-only"),
+            None
+        );
+    }
+
+    #[test]
+    fn removed_container_is_not_an_error() {
+        let server_error = |status_code| bollard::errors::Error::DockerResponseServerError {
+            status_code,
+            message: "No such container: connector-1".to_string(),
+        };
+        assert!(DockerOrchestrator::is_not_found(&server_error(404)));
+        assert!(!DockerOrchestrator::is_not_found(&server_error(500)));
+    }
+
+    // Docker daemon answering every request with a 404, along with the number of requests received
+    async fn daemon_not_found() -> (DockerOrchestrator, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let received = requests.clone();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let body = r#"{"message":"No such container: misp"}"#;
+        let answer = format!(
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                received.fetch_add(1, Ordering::Relaxed);
+                let mut buffer = vec![0u8; 8192];
+                let _ = stream.read(&mut buffer).await;
+                let _ = stream.write_all(answer.as_bytes()).await;
+            }
+        });
+        let docker = Docker::connect_with_http(&address, 5, bollard::API_DEFAULT_VERSION).unwrap();
+        (DockerOrchestrator { docker }, requests)
+    }
+
+    #[tokio::test]
+    async fn stop_and_logs_of_removed_container_are_skipped() {
+        let (orchestrator, requests) = daemon_not_found().await;
+        let connector = ApiConnector::for_test("docker-removed");
+        let container = OrchestratorContainer {
+            id: "container-1".to_string(),
+            name: connector.container_name(),
+            state: "running".to_string(),
+            labels: HashMap::new(),
+            envs: HashMap::new(),
+            restart_count: 0,
+            started_at: None,
+        };
+
+        orchestrator.stop(&container, &connector).await;
+        assert!(orchestrator.logs(&container, &connector).await.is_none());
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn log_lines_carry_stream_tag_when_split() {
+        let stdout = || LogOutput::StdOut {
+            message: "INFO 12 objects imported\n".into(),
+        };
+        let stderr = || LogOutput::StdErr {
+            message: "ERROR queue unreachable\n".into(),
+        };
+
+        assert_eq!(
+            DockerOrchestrator::log_line(stdout(), true),
+            "[stdout] INFO 12 objects imported\n"
+        );
+        assert_eq!(
+            DockerOrchestrator::log_line(stderr(), true),
+            "[stderr] ERROR queue unreachable\n"
+        );
+        assert_eq!(
+            DockerOrchestrator::log_line(stderr(), false),
+            "ERROR queue unreachable\n"
+        );
+        // A TTY container has a single console stream
+        let console = LogOutput::Console {
+            message: "started\n".into(),
+        };
+        assert_eq!(DockerOrchestrator::log_line(console, true), "started\n");
+    }
+
+    #[test]
+    fn unrecognized_states_are_unknown_only_when_enabled() {
+        assert_eq!(
+            DockerOrchestrator::state_status("exited", true),
+            ConnectorStatus::Stopped
+        );
+        assert_eq!(
+            DockerOrchestrator::state_status("dead", true),
+            ConnectorStatus::Unknown
+        );
+        assert_eq!(
+            DockerOrchestrator::state_status("removing", true),
+            ConnectorStatus::Unknown
+        );
+        assert_eq!(
+            DockerOrchestrator::state_status("dead", false),
+            ConnectorStatus::Stopped
+        );
+    }
 }
@@ -0,0 +1,152 @@
+use crate::api::ApiConnector;
+use crate::config::settings::Manager;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+use tracing::{error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookStage {
+    PreDeploy,
+    PostDeploy,
+}
+
+impl HookStage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookStage::PreDeploy => "pre_deploy",
+            HookStage::PostDeploy => "post_deploy",
+        }
+    }
+
+    fn command<'a>(&self, manager: &'a Manager) -> Option<&'a str> {
+        match self {
+            HookStage::PreDeploy => manager.pre_deploy_hook.as_deref(),
+            HookStage::PostDeploy => manager.post_deploy_hook.as_deref(),
+        }
+        .filter(|command| !command.trim().is_empty())
+    }
+}
+
+// Connector metadata exposed to the hook command
+fn hook_envs(stage: HookStage, connector: &ApiConnector) -> Vec<(&'static str, String)> {
+    vec![
+        ("XTM_HOOK_STAGE", stage.as_str().to_string()),
+        ("XTM_CONNECTOR_ID", connector.id.clone()),
+        ("XTM_CONNECTOR_NAME", connector.name.clone()),
+        ("XTM_CONNECTOR_PLATFORM", connector.platform.clone()),
+        ("XTM_CONNECTOR_IMAGE", connector.image.clone()),
+        ("XTM_CONNECTOR_CONTAINER_NAME", connector.container_name()),
+        (
+            "XTM_CONNECTOR_REQUESTED_STATUS",
+            connector.requested_status.clone(),
+        ),
+    ]
+}
+
+// Run the shell command, killed when the timeout is exceeded
+pub async fn run_hook(
+    stage: HookStage,
+    command: &str,
+    connector: &ApiConnector,
+    hook_timeout: Duration,
+) -> Result<(), String> {
+    let mut process = Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        .envs(hook_envs(stage, connector))
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = match timeout(hook_timeout, process.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => return Err(err.to_string()),
+        Err(_) => return Err(format!("timed out after {}s", hook_timeout.as_secs_f32())),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stdout.trim().is_empty() || !stderr.trim().is_empty() {
+        info!(
+            id = connector.id,
+            hook = stage.as_str(),
+            stdout = stdout.trim_end(),
+            stderr = stderr.trim_end(),
+            "Deploy hook output"
+        );
+    }
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", output.status))
+    }
+}
+
+// Returns false when the deploy must not go on, only a required pre deploy hook can block it
+pub async fn run_deploy_hook(
+    manager: &Manager,
+    stage: HookStage,
+    connector: &ApiConnector,
+) -> bool {
+    // The read-only plan mode never runs external commands
+    if manager.reconcile_plan {
+        return true;
+    }
+    let Some(command) = stage.command(manager) else {
+        return true;
+    };
+    let hook_timeout = Duration::from_secs(manager.deploy_hook_timeout);
+    match run_hook(stage, command, connector, hook_timeout).await {
+        Ok(()) => true,
+        Err(err) => {
+            error!(
+                id = connector.id,
+                hook = stage.as_str(),
+                error = err,
+                "Deploy hook failed"
+            );
+            !(manager.deploy_hook_required && stage == HookStage::PreDeploy)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn connector() -> ApiConnector {
+        ApiConnector {
+            name: "MISP Feed".into(),
+            image: "opencti/connector-misp:6.8.0".into(),
+            ..ApiConnector::for_test("hook-connector")
+        }
+    }
+
+    async fn run(command: &str, hook_timeout: Duration) -> Result<(), String> {
+        run_hook(HookStage::PreDeploy, command, &connector(), hook_timeout).await
+    }
+
+    #[tokio::test]
+    async fn hook_receives_connector_metadata() {
+        let command = r#"[ "$XTM_HOOK_STAGE" = "pre_deploy" ] \
+            && [ "$XTM_CONNECTOR_ID" = "hook-connector" ] \
+            && [ "$XTM_CONNECTOR_PLATFORM" = "opencti" ] \
+            && [ "$XTM_CONNECTOR_IMAGE" = "opencti/connector-misp:6.8.0" ]"#;
+        assert_eq!(run(command, Duration::from_secs(5)).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn failing_hook_reports_exit_status() {
+        let result = run("echo failure >&2; exit 3", Duration::from_secs(5)).await;
+        assert!(result.unwrap_err().contains('3'));
+    }
+
+    #[tokio::test]
+    async fn hook_is_killed_on_timeout() {
+        let start = Instant::now();
+        let result = run("sleep 10", Duration::from_millis(200)).await;
+        assert!(result.unwrap_err().starts_with("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,10 +1,172 @@
-use crate::config::settings::Registry;
+use crate::config::settings::{Registry, Settings};
 use base64::Engine;
 use base64::engine::general_purpose;
 use bollard::auth::DockerCredentials;
+use bollard::query_parameters::CreateImageOptions;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use slug::slugify;
 use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::{debug, error, info, warn};
+
+// Last registry configuration read from the configuration sources, with its load time
+static REGISTRY_STATE: Mutex<Option<(Instant, Option<Registry>)>> = Mutex::new(None);
+
+/// Registry configuration to use for the next deployment.
+///
+/// When `credentials_refresh_interval` is set, the configuration sources (file/env)
+/// are re-read at most once per interval so rotated credentials are picked up
+/// without restarting the composer.
+pub fn current_registry_config() -> Option<Registry> {
+    let startup_config = crate::settings().opencti.daemon.registry.clone();
+    let Some(refresh_interval) = startup_config
+        .as_ref()
+        .and_then(|registry| registry.credentials_refresh_interval)
+    else {
+        return startup_config;
+    };
+
+    let mut state = REGISTRY_STATE.lock().unwrap();
+    if let Some((loaded_at, registry)) = state.as_ref() {
+        if loaded_at.elapsed() < Duration::from_secs(refresh_interval) {
+            return registry.clone();
+        }
+    }
+    let previous = state
+        .as_ref()
+        .map(|(_, registry)| registry.clone())
+        .unwrap_or(startup_config);
+    let reloaded = match Settings::new() {
+        Ok(settings) => settings.opencti.daemon.registry,
+        Err(err) => {
+            warn!(
+                error = err.to_string(),
+                "Unable to reload registry configuration, keeping current credentials"
+            );
+            previous.clone()
+        }
+    };
+    if credentials_changed(previous.as_ref(), reloaded.as_ref()) {
+        info!("Registry credentials changed, using the new credentials");
+    }
+    *state = Some((Instant::now(), reloaded.clone()));
+    reloaded
+}
+
+// Bounds the image pulls running at the same time, unbounded without a maximum
+#[derive(Debug)]
+pub struct PullLimiter {
+    permits: Option<Semaphore>,
+}
+
+impl PullLimiter {
+    pub fn new(max_concurrent_pulls: Option<usize>) -> Self {
+        Self {
+            permits: max_concurrent_pulls.map(|max| Semaphore::new(max.max(1))),
+        }
+    }
+
+    // Held for the whole pull, released on drop
+    pub async fn acquire(&self, image: &str) -> Option<SemaphorePermit<'_>> {
+        let permits = self.permits.as_ref()?;
+        if let Ok(permit) = permits.try_acquire() {
+            return Some(permit);
+        }
+        debug!(image, "Waiting for a free image pull slot");
+        permits.acquire().await.ok()
+    }
+}
+
+// registry.max_concurrent_pulls of the platforms (the smallest when both set it),
+// then the deprecated manager.max_concurrent_pulls
+fn max_concurrent_pulls(settings: &Settings) -> Option<usize> {
+    let registry_limit = [&settings.opencti.daemon, &settings.openaev.daemon]
+        .into_iter()
+        .filter_map(|daemon| daemon.registry.as_ref()?.max_concurrent_pulls)
+        .min();
+    if registry_limit.is_none() && settings.manager.max_concurrent_pulls.is_some() {
+        warn!("manager.max_concurrent_pulls is deprecated, use registry.max_concurrent_pulls");
+    }
+    registry_limit.or(settings.manager.max_concurrent_pulls)
+}
+
+// Shared by every orchestrator of both platforms, sized from the startup configuration
+static PULL_LIMITER: OnceLock<PullLimiter> = OnceLock::new();
+
+pub async fn pull_permit(image: &str) -> Option<SemaphorePermit<'static>> {
+    let limiter =
+        PULL_LIMITER.get_or_init(|| PullLimiter::new(max_concurrent_pulls(crate::settings())));
+    limiter.acquire(image).await
+}
+
+pub fn credentials_changed(previous: Option<&Registry>, current: Option<&Registry>) -> bool {
+    let fingerprint = |registry: Option<&Registry>| {
+        registry.and_then(|config| Image::new(Some(config.clone())).credentials_fingerprint())
+    };
+    fingerprint(previous) != fingerprint(current)
+}
+
+// Counter xtm_image_pull_auth_failures_total: pulls rejected by the registry authentication
+static IMAGE_PULL_AUTH_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+const PULL_AUTH_ERRORS: [&str; 6] = [
+    "unauthorized",
+    "authentication required",
+    "access denied",
+    "denied: ",
+    "no basic auth credentials",
+    "incorrect username or password",
+];
+
+/// Tell if a pull failure comes from the registry authentication (credentials problem)
+/// rather than from the network or a missing image.
+pub fn is_pull_auth_failure(status_code: Option<u16>, message: &str) -> bool {
+    if matches!(status_code, Some(401) | Some(403)) {
+        return true;
+    }
+    let message = message.to_lowercase();
+    PULL_AUTH_ERRORS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+pub fn pull_error_status(err: &bollard::errors::Error) -> Option<u16> {
+    match err {
+        bollard::errors::Error::DockerResponseServerError { status_code, .. } => Some(*status_code),
+        _ => None,
+    }
+}
+
+/// First error reported in a Docker pull progress stream (JSON lines)
+pub fn pull_stream_error(body: &str) -> Option<String> {
+    body.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|progress| progress.get("error")?.as_str().map(str::to_string))
+}
+
+pub fn report_pull_failure(image: &str, status_code: Option<u16>, message: &str) {
+    if is_pull_auth_failure(status_code, message) {
+        let total = IMAGE_PULL_AUTH_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+        error!(
+            image,
+            status_code,
+            error = message,
+            xtm_image_pull_auth_failures_total = total,
+            "Image pull rejected by the registry authentication, check the registry credentials"
+        );
+    } else {
+        error!(
+            image,
+            status_code,
+            error = message,
+            "Error fetching container image"
+        );
+    }
+}
 
 pub struct Image {
     config: Registry,
@@ -31,6 +193,9 @@ impl Image {
                 username: None,
                 password: None,
                 email: None,
+                platform: None,
+                credentials_refresh_interval: None,
+                max_concurrent_pulls: None,
             }),
         }
     }
@@ -61,9 +226,50 @@ impl Image {
         }
         Some(self.build_credentials(&self.config))
     }
+
+    // Digest of the credentials, used to detect rotations without keeping secrets around
+    pub fn credentials_fingerprint(&self) -> Option<String> {
+        let username = self.config.username.as_ref()?;
+        let password = self.config.password.as_ref()?;
+        let mut hasher = Sha256::new();
+        for part in [
+            self.config.server.as_deref().unwrap_or_default(),
+            username.as_str(),
+            password.as_str(),
+            self.config.email.as_deref().unwrap_or_default(),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        Some(format!("{:x}", hasher.finalize()))
+    }
+
+    pub fn get_platform(&self) -> Option<String> {
+        self.config.platform.clone()
+    }
+
+    pub fn create_image_options(&self, image: &str) -> CreateImageOptions {
+        CreateImageOptions {
+            from_image: Some(image.to_string()),
+            platform: self.get_platform().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
     // endregion
 
     // region Kubernetes
+    pub fn get_kubernetes_node_selector(&self) -> Option<BTreeMap<String, String>> {
+        // platform is expressed as os/arch (e.g. linux/amd64), mapped to well-known node labels
+        let platform = self.get_platform()?;
+        let mut parts = platform.split('/');
+        let os = parts.next().filter(|os| !os.is_empty())?;
+        let mut selector = BTreeMap::from([("kubernetes.io/os".to_string(), os.to_string())]);
+        if let Some(arch) = parts.next().filter(|arch| !arch.is_empty()) {
+            selector.insert("kubernetes.io/arch".to_string(), arch.to_string());
+        }
+        Some(selector)
+    }
+
     pub fn get_kubernetes_secret_name(&self) -> Option<String> {
         // secret name must be slug to be compatible with kubernetes naming convention (RFC 1123)
         self.config.server.clone().map(|server| slugify(&server))
@@ -95,3 +301,204 @@ impl Image {
     }
     // endregion
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+
+    fn registry(platform: Option<&str>) -> Registry {
+        Registry {
+            server: None,
+            username: None,
+            password: None,
+            email: None,
+            platform: platform.map(|p| p.to_string()),
+            credentials_refresh_interval: None,
+            max_concurrent_pulls: None,
+        }
+    }
+
+    fn credentials(username: &str, password: &str) -> Registry {
+        Registry {
+            server: Some("registry.example.com".to_string()),
+            username: Some(username.to_string()),
+            password: Some(password.to_string()),
+            ..registry(None)
+        }
+    }
+
+    #[test]
+    fn pull_auth_failures_are_classified_from_status_code() {
+        assert!(is_pull_auth_failure(Some(401), "Unauthorized"));
+        assert!(is_pull_auth_failure(Some(403), ""));
+        assert!(!is_pull_auth_failure(Some(404), "manifest unknown"));
+        assert!(!is_pull_auth_failure(Some(500), "internal server error"));
+    }
+
+    #[test]
+    fn pull_auth_failures_are_classified_from_message() {
+        let auth_errors = [
+            "Head \"https://registry.example.com/v2/connector/manifests/6.8.0\": unauthorized: authentication required",
+            "pull access denied for connector-misp, repository does not exist or may require 'docker login'",
+            "denied: requested access to the resource is denied",
+            "Get https://registry.example.com/v2/: no basic auth credentials",
+        ];
+        for message in auth_errors {
+            assert!(is_pull_auth_failure(None, message), "{message}");
+        }
+        let other_errors = [
+            "manifest for opencti/connector-misp:9.9.9 not found: manifest unknown",
+            "dial tcp: lookup registry.example.com: no such host",
+            "net/http: request canceled while waiting for connection",
+        ];
+        for message in other_errors {
+            assert!(!is_pull_auth_failure(None, message), "{message}");
+        }
+    }
+
+    #[test]
+    fn pull_stream_error_extracts_first_error() {
+        let body = concat!(
+            "{\"status\":\"Pulling from connector\",\"id\":\"6.8.0\"}\n",
+            "{\"errorDetail\":{\"message\":\"unauthorized\"},\"error\":\"unauthorized: authentication required\"}\n"
+        );
+        assert_eq!(
+            pull_stream_error(body),
+            Some("unauthorized: authentication required".to_string())
+        );
+        assert_eq!(pull_stream_error("{\"status\":\"Downloaded\"}\n"), None);
+    }
+
+    #[test]
+    fn pull_auth_failures_are_counted() {
+        let before = IMAGE_PULL_AUTH_FAILURES.load(Ordering::Relaxed);
+        report_pull_failure(
+            "registry.example.com/connector:6.8.0",
+            Some(401),
+            "unauthorized",
+        );
+        assert!(IMAGE_PULL_AUTH_FAILURES.load(Ordering::Relaxed) > before);
+    }
+
+    #[test]
+    fn create_image_options_carries_configured_platform() {
+        let resolver = Image::new(Some(registry(Some("linux/arm64"))));
+        let options = resolver.create_image_options("opencti/connector-misp:6.8.0");
+        assert_eq!(options.from_image, Some("opencti/connector-misp:6.8.0".to_string()));
+        assert_eq!(options.platform, "linux/arm64");
+    }
+
+    #[test]
+    fn create_image_options_without_platform_keeps_daemon_default() {
+        let resolver = Image::new(None);
+        let options = resolver.create_image_options("opencti/connector-misp:6.8.0");
+        assert!(options.platform.is_empty());
+    }
+
+    #[test]
+    fn kubernetes_node_selector_maps_os_and_arch() {
+        let resolver = Image::new(Some(registry(Some("linux/amd64"))));
+        let selector = resolver.get_kubernetes_node_selector().unwrap();
+        assert_eq!(selector.get("kubernetes.io/os"), Some(&"linux".to_string()));
+        assert_eq!(selector.get("kubernetes.io/arch"), Some(&"amd64".to_string()));
+        assert!(Image::new(None).get_kubernetes_node_selector().is_none());
+    }
+
+    #[test]
+    fn credentials_change_detected_on_rotation() {
+        let before = credentials("composer", "secret-1");
+        let rotated = credentials("composer", "secret-2");
+        assert!(credentials_changed(Some(&before), Some(&rotated)));
+        assert!(credentials_changed(None, Some(&rotated)));
+        assert!(credentials_changed(Some(&before), None));
+    }
+
+    #[test]
+    fn credentials_unchanged_when_identical() {
+        let before = credentials("composer", "secret-1");
+        let reloaded = credentials("composer", "secret-1");
+        assert!(!credentials_changed(Some(&before), Some(&reloaded)));
+        // Settings without credentials never trigger a rotation
+        assert!(!credentials_changed(Some(&registry(Some("linux/amd64"))), None));
+    }
+
+    #[tokio::test]
+    async fn pulls_beyond_the_limit_wait_for_a_free_slot() {
+        let limiter = Arc::new(PullLimiter::new(Some(2)));
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Semaphore::new(0));
+        let pulls: Vec<_> = (0..5)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                let active = Arc::clone(&active);
+                let peak = Arc::clone(&peak);
+                let release = Arc::clone(&release);
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire("opencti/connector-misp:6.8.0").await;
+                    let running = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(running, Ordering::SeqCst);
+                    // Pull blocked until the test lets it complete
+                    release.acquire().await.unwrap().forget();
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(active.load(Ordering::SeqCst), 2);
+        release.add_permits(5);
+        for pull in pulls {
+            pull.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn pulls_are_unbounded_without_limit() {
+        let limiter = PullLimiter::new(None);
+        assert!(
+            limiter
+                .acquire("opencti/connector-misp:6.8.0")
+                .await
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn pull_limit_is_read_from_the_registry_before_the_manager() {
+        let mut settings = crate::settings().clone();
+        settings.manager.max_concurrent_pulls = Some(4);
+        settings.opencti.daemon.registry = None;
+        settings.openaev.daemon.registry = None;
+        assert_eq!(max_concurrent_pulls(&settings), Some(4));
+
+        settings.opencti.daemon.registry = Some(Registry {
+            max_concurrent_pulls: Some(3),
+            ..registry(None)
+        });
+        assert_eq!(max_concurrent_pulls(&settings), Some(3));
+
+        settings.openaev.daemon.registry = Some(Registry {
+            max_concurrent_pulls: Some(2),
+            ..registry(None)
+        });
+        assert_eq!(max_concurrent_pulls(&settings), Some(2));
+
+        settings.manager.max_concurrent_pulls = None;
+        settings.opencti.daemon.registry = None;
+        settings.openaev.daemon.registry = None;
+        assert_eq!(max_concurrent_pulls(&settings), None);
+    }
+
+    #[test]
+    fn credentials_fingerprint_does_not_expose_password() {
+        let fingerprint = Image::new(Some(credentials("composer", "secret-1")))
+            .credentials_fingerprint()
+            .unwrap();
+        assert!(!fingerprint.contains("secret-1"));
+        assert_eq!(fingerprint.len(), 64);
+    }
+}
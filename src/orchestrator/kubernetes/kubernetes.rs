@@ -1,48 +1,258 @@
 use crate::api::{ApiConnector, ConnectorStatus};
 use crate::api::PROXY_CA_CERT_MOUNT_PATH;
-use crate::config::settings::Kubernetes;
-use crate::orchestrator::image::Image;
-use crate::orchestrator::kubernetes::KubeOrchestrator;
-use crate::orchestrator::{Orchestrator, OrchestratorContainer};
+use crate::config::settings::{Kubernetes, Registry, WorkloadType};
+use crate::orchestrator::image::{Image, current_registry_config};
+use crate::orchestrator::kubernetes::{KubeOrchestrator, PodAccessGuard};
+use crate::orchestrator::{
+    Orchestrator, OrchestratorContainer, build_contract_keys_label, build_owner_label,
+    container_hostname, is_valid_label_key, is_valid_label_value, sanitize_label_value,
+    unrecognized_state_status,
+};
 use async_trait::async_trait;
 use k8s_openapi::DeepMerge;
 use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec};
+use k8s_openapi::api::batch::v1::{Job, JobSpec};
 use k8s_openapi::api::core::v1::{
-    Container, ContainerStatus, EnvVar, LocalObjectReference, Pod, PodSpec, PodTemplateSpec,
-    ResourceRequirements, Secret, SecretVolumeSource, Volume, VolumeMount,
+    Affinity, Container, ContainerStatus, EnvVar, LocalObjectReference, Pod, PodSpec,
+    PodTemplateSpec, ResourceRequirements, Secret, SecretVolumeSource, Volume, VolumeMount,
 };
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
 use kube::api::{DeleteParams, LogParams, Patch, PatchParams};
+use kube::config::{KubeConfigOptions, Kubeconfig};
 use kube::{
     Client,
     api::{Api, ListParams, PostParams, ResourceExt},
 };
 use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
 use tracing::{debug, error, info, warn};
 
+const HTTP_FORBIDDEN: u16 = 403;
+const HTTP_NOT_FOUND: u16 = 404;
+const PREVIOUS_IMAGE_ANNOTATION: &str = "opencti-previous-image";
+const PREVIOUS_HASH_ANNOTATION: &str = "opencti-previous-hash";
+// Kind of workload a connector was deployed as, its configured kind may have changed since
+const WORKLOAD_KIND_LABEL: &str = "opencti-workload-kind";
+const DEPLOYMENT_KIND: &str = "deployment";
+const JOB_KIND: &str = "job";
+
+impl PodAccessGuard {
+    pub fn is_forbidden(&self) -> bool {
+        self.forbidden.load(Ordering::Relaxed)
+    }
+
+    // Returns true when the error is a RBAC denial, warning only the first time
+    pub fn handle_error(&self, status_code: Option<u16>, verb: &str, resource: &str) -> bool {
+        if status_code != Some(HTTP_FORBIDDEN) {
+            return false;
+        }
+        if !self.forbidden.swap(true, Ordering::Relaxed) {
+            warn!(
+                verb = verb,
+                resource = resource,
+                "Missing Kubernetes RBAC permission '{} {}' for the composer service account, connector logs and pod health collection are disabled until restart",
+                verb,
+                resource
+            );
+        }
+        true
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ClientConfigSource<'a> {
+    InCluster,
+    Context(&'a str),
+    Inferred,
+}
+
+// Priority: forced in cluster config > kubeconfig context > inferred (kubeconfig then in cluster)
+fn client_config_source(config: &Kubernetes) -> ClientConfigSource<'_> {
+    match (config.in_cluster, config.context.as_deref()) {
+        (true, Some(context)) => {
+            warn!(
+                context,
+                "Kubernetes in_cluster is set, ignoring the kubeconfig context"
+            );
+            ClientConfigSource::InCluster
+        }
+        (true, None) => ClientConfigSource::InCluster,
+        (false, Some(context)) => ClientConfigSource::Context(context),
+        (false, None) => ClientConfigSource::Inferred,
+    }
+}
+
+async fn context_config(kubeconfig: Kubeconfig, context: &str) -> Result<kube::Config, String> {
+    let options = KubeConfigOptions {
+        context: Some(context.to_string()),
+        ..Default::default()
+    };
+    kube::Config::from_custom_kubeconfig(kubeconfig, &options)
+        .await
+        .map_err(|err| format!("unable to load kubeconfig context '{context}': {err}"))
+}
+
+// Client configuration of the orchestrator, also used to read the credentials key secret
+pub async fn client_config(config: &Kubernetes) -> Result<kube::Config, String> {
+    match client_config_source(config) {
+        ClientConfigSource::InCluster => kube::Config::incluster()
+            .map_err(|err| format!("unable to load in cluster config: {err}")),
+        ClientConfigSource::Context(context) => {
+            let kubeconfig =
+                Kubeconfig::read().map_err(|err| format!("unable to read kubeconfig: {err}"))?;
+            context_config(kubeconfig, context).await
+        }
+        ClientConfigSource::Inferred => kube::Config::infer()
+            .await
+            .map_err(|err| format!("unable to infer kubernetes config: {err}")),
+    }
+}
+
 impl KubeOrchestrator {
     pub async fn new(config: Kubernetes) -> Self {
-        let client = Client::try_default().await.unwrap();
+        let client_config = client_config(&config)
+            .await
+            .unwrap_or_else(|e| panic!("Failed to configure the Kubernetes client: {}", e));
+        let client = Client::try_from(client_config).unwrap();
         let pods: Api<Pod> = Api::default_namespaced(client.clone());
         let deployments: Api<Deployment> = Api::default_namespaced(client.clone());
+        let jobs: Api<Job> = Api::default_namespaced(client.clone());
         let secrets: Api<Secret> = Api::default_namespaced(client.clone());
-        Self::register_secret(&secrets).await;
+        let registry_config = current_registry_config();
+        let registry_fingerprint = Image::new(registry_config.clone()).credentials_fingerprint();
+        Self::register_secret(&secrets, registry_config).await;
         Self {
             pods,
             deployments,
+            jobs,
             secrets,
             config,
+            pod_access: PodAccessGuard::default(),
+            registry_fingerprint: Mutex::new(registry_fingerprint),
+        }
+    }
+
+    // Record the new fingerprint and tell if the image pull secret must be recreated
+    fn registry_secret_outdated(current: &Mutex<Option<String>>, fingerprint: Option<String>) -> bool {
+        let mut current = current.lock().unwrap();
+        if *current == fingerprint {
+            return false;
+        }
+        *current = fingerprint;
+        true
+    }
+
+    // Recreate the image pull secret when registry credentials were rotated
+    async fn ensure_registry_secret(&self) {
+        let registry_config = current_registry_config();
+        let fingerprint = Image::new(registry_config.clone()).credentials_fingerprint();
+        if Self::registry_secret_outdated(&self.registry_fingerprint, fingerprint) {
+            info!("Registry credentials changed, recreating Kubernetes registry secret");
+            Self::register_secret(&self.secrets, registry_config).await;
+        }
+    }
+
+    fn api_status_code(err: &kube::Error) -> Option<u16> {
+        match err {
+            kube::Error::Api(ae) => Some(ae.code),
+            _ => None,
+        }
+    }
+
+    // Deleted out of band between the get and the operation
+    fn is_not_found(err: &kube::Error) -> bool {
+        Self::api_status_code(err) == Some(HTTP_NOT_FOUND)
+    }
+
+    // Global resources, with the connector GPUs (e.g. nvidia.com/gpu) added to the limits
+    fn get_image_resources(&self, connector_id: &str) -> Option<ResourceRequirements> {
+        let gpus = self
+            .config
+            .connector_gpus
+            .as_ref()
+            .and_then(|gpus| gpus.get(connector_id));
+        let Some(gpus) = gpus else {
+            return self.config.image_resources.clone();
+        };
+        let mut resources = self.config.image_resources.clone().unwrap_or_default();
+        let limits = resources.limits.get_or_insert_with(BTreeMap::new);
+        for (resource, quantity) in gpus {
+            limits.insert(resource.clone(), Quantity(quantity.clone()));
+        }
+        Some(resources)
+    }
+
+    fn workload_type(&self, connector_id: &str) -> WorkloadType {
+        self.config
+            .connector_workload_types
+            .as_ref()
+            .and_then(|types| types.get(connector_id).copied())
+            .unwrap_or_default()
+    }
+
+    // Objects listed before the workload kind label are deployments, see from_job for the jobs
+    fn container_workload_type(container: &OrchestratorContainer) -> WorkloadType {
+        match container
+            .labels
+            .get(WORKLOAD_KIND_LABEL)
+            .map(String::as_str)
+        {
+            Some(JOB_KIND) => WorkloadType::Job,
+            _ => WorkloadType::Deployment,
         }
     }
 
-    fn get_image_resources(&self) -> Option<ResourceRequirements> {
-        self.config.image_resources.clone()
+    // A completed or failed job is never resumed, only a new configuration runs it again
+    fn is_finished_job(container: &OrchestratorContainer) -> bool {
+        Self::container_workload_type(container) == WorkloadType::Job
+            && matches!(container.state.as_str(), "completed" | "failed")
+    }
+
+    // Without any job configured, the cluster role may not grant them
+    fn has_jobs(&self) -> bool {
+        self.config
+            .connector_workload_types
+            .as_ref()
+            .is_some_and(|types| types.values().any(|kind| *kind == WorkloadType::Job))
+    }
+
+    // Connector specific grace period first, then the global one
+    fn get_termination_grace_period(&self, connector_id: &str) -> Option<i64> {
+        self.config
+            .connector_termination_grace_periods
+            .as_ref()
+            .and_then(|periods| periods.get(connector_id).copied())
+            .or(self.config.termination_grace_period)
+    }
+
+    // Connector specific service account first, then the global one
+    fn get_service_account_name(&self, connector_id: &str) -> Option<String> {
+        self.config
+            .connector_service_account_names
+            .as_ref()
+            .and_then(|names| names.get(connector_id))
+            .or(self.config.service_account_name.as_ref())
+            .cloned()
+    }
+
+    // Connector specific pull secret first, then the registry secret managed by the composer
+    fn get_image_pull_secret(&self, connector_id: &str, resolver: &Image) -> Option<String> {
+        self.config
+            .connector_image_pull_secrets
+            .as_ref()
+            .and_then(|secrets| secrets.get(connector_id))
+            .cloned()
+            .or_else(|| {
+                resolver
+                    .get_credentials()
+                    .and_then(|_| resolver.get_kubernetes_secret_name())
+            })
     }
 
     // Validate and return image pull policy
-    async fn register_secret(secrets: &Api<Secret>) {
-        let settings = crate::settings();
-        let registry_config = settings.opencti.daemon.registry.clone();
+    async fn register_secret(secrets: &Api<Secret>, registry_config: Option<Registry>) {
         let resolver = Image::new(registry_config);
         let registry_secret = resolver.get_kubernetes_registry_secret();
         if registry_secret.is_some() {
@@ -76,10 +286,28 @@ impl KubeOrchestrator {
         }
     }
 
-    fn get_image_pull_policy(&self) -> String {
+    // Connector specific pull policy first (air-gapped connectors pre-loaded on the nodes), then the global one
+    fn get_image_pull_policy(&self, connector_id: &str) -> String {
         const VALID_POLICIES: [&str; 3] = ["Always", "IfNotPresent", "Never"];
         const DEFAULT_POLICY: &str = "IfNotPresent";
 
+        let connector_policy = self
+            .config
+            .connector_image_pull_policies
+            .as_ref()
+            .and_then(|policies| policies.get(connector_id));
+        match connector_policy {
+            Some(policy) if VALID_POLICIES.contains(&policy.as_str()) => return policy.clone(),
+            Some(invalid_policy) => {
+                warn!(
+                    id = connector_id,
+                    "Invalid connector image pull policy '{}'. Valid values: {:?}. Using the global policy",
+                    invalid_policy,
+                    VALID_POLICIES
+                );
+            }
+            None => {}
+        }
         match &self.config.image_pull_policy {
             Some(policy) if VALID_POLICIES.contains(&policy.as_str()) => policy.clone(),
             Some(invalid_policy) => {
@@ -153,74 +381,244 @@ impl KubeOrchestrator {
         labels.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
     }
 
-    async fn set_deployment_scale(&self, connector: &ApiConnector, scale: i32) {
-        let deployment_patch = Deployment {
+    // Replicas only, with the manager performing the scale when recorded
+    pub fn build_scale_patch(scale: i32, last_modified_by: Option<(String, String)>) -> Deployment {
+        Deployment {
+            metadata: ObjectMeta {
+                annotations: last_modified_by.map(|annotation| BTreeMap::from([annotation])),
+                ..Default::default()
+            },
             spec: Some(DeploymentSpec {
                 replicas: Some(scale),
                 ..Default::default()
             }),
             ..Default::default()
-        };
+        }
+    }
+
+    async fn set_deployment_scale(&self, connector: &ApiConnector, scale: i32) {
+        let deployment_patch = Self::build_scale_patch(scale, self.last_modified_by_label());
         let patch = Patch::Merge(&deployment_patch);
         let name = connector.container_name();
-        self.deployments
+        let scale_result = self
+            .deployments
             .patch(name.as_str(), &PatchParams::default(), &patch)
-            .await
-            .unwrap();
+            .await;
+        match scale_result {
+            Ok(_) => {}
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name, "Deployment already removed");
+            }
+            Err(err) => {
+                error!(
+                    name,
+                    scale,
+                    error = err.to_string(),
+                    "Fail scaling the deployment"
+                );
+            }
+        }
+    }
+
+    async fn set_job_suspended(&self, connector: &ApiConnector, suspended: bool) {
+        let mut patch_value = serde_json::json!({ "spec": { "suspend": suspended } });
+        if let Some((key, manager_id)) = self.last_modified_by_label() {
+            patch_value["metadata"] = serde_json::json!({ "annotations": { key: manager_id } });
+        }
+        let patch = Patch::Merge(&patch_value);
+        let name = connector.container_name();
+        let suspend_result = self
+            .jobs
+            .patch(name.as_str(), &PatchParams::default(), &patch)
+            .await;
+        match suspend_result {
+            Ok(_) => {}
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name, "Job already removed");
+            }
+            Err(err) => {
+                error!(
+                    name,
+                    suspended,
+                    error = err.to_string(),
+                    "Fail suspending the job"
+                );
+            }
+        }
+    }
+
+    fn template_envs(template: &PodTemplateSpec) -> HashMap<String, String> {
+        template
+            .spec
+            .as_ref()
+            .and_then(|pod_spec| pod_spec.containers.first())
+            .and_then(|container| container.env.as_ref())
+            .map(|env| {
+                env.iter()
+                    .map(|var| (var.name.clone(), var.value.clone().unwrap_or_default()))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     pub fn from_deployment(deployment: Deployment) -> OrchestratorContainer {
         let dep = deployment.clone();
+        let mut envs = dep
+            .spec
+            .as_ref()
+            .map(|spec| Self::template_envs(&spec.template))
+            .unwrap_or_default();
+        // Annotations on top (config hash is kept on top level)
+        envs.extend(KubeOrchestrator::convert_to_map(deployment.annotations()));
         let expected_replicas = dep.spec.unwrap().replicas.unwrap_or(0);
-        let compute_state: &str = if expected_replicas == 0 {
+        // Deleted but still finalizing, neither started nor stopped
+        let compute_state: &str = if deployment.metadata.deletion_timestamp.is_some() {
+            "removing"
+        } else if expected_replicas == 0 {
             "terminated"
         } else {
             "running"
         };
-        let annotations_as_env = KubeOrchestrator::convert_to_map(deployment.annotations());
         OrchestratorContainer {
             id: deployment.uid().unwrap(),
             name: dep.metadata.name.unwrap(),
             state: compute_state.to_string(),
-            envs: annotations_as_env,
+            envs,
             labels: KubeOrchestrator::convert_to_map(&deployment.labels()),
             restart_count: 0, // Will be updated from pod status
             started_at: None, // Will be updated from pod status
         }
     }
 
-    async fn get_deployment_pod(&self, connector_id: String) -> Option<Pod> {
-        let lp = &ListParams::default().labels(&format!("opencti-connector-id={}", connector_id));
+    // A finished job is reported like an exited container, a suspended one like a deployment scaled to zero
+    pub fn job_state(job: &Job) -> &'static str {
+        if job.metadata.deletion_timestamp.is_some() {
+            return "removing";
+        }
+        let suspended = job.spec.as_ref().and_then(|spec| spec.suspend) == Some(true);
+        let conditions = job
+            .status
+            .as_ref()
+            .and_then(|status| status.conditions.as_ref());
+        let reached = |condition_type: &str| {
+            conditions.is_some_and(|conditions| {
+                conditions.iter().any(|condition| {
+                    condition.type_ == condition_type && condition.status == "True"
+                })
+            })
+        };
+        if reached("Complete") {
+            "completed"
+        } else if reached("Failed") {
+            "failed"
+        } else if suspended {
+            "suspended"
+        } else {
+            "running"
+        }
+    }
+
+    pub fn from_job(job: Job) -> OrchestratorContainer {
+        let mut envs = job
+            .spec
+            .as_ref()
+            .map(|spec| Self::template_envs(&spec.template))
+            .unwrap_or_default();
+        envs.extend(KubeOrchestrator::convert_to_map(job.annotations()));
+        let mut labels = KubeOrchestrator::convert_to_map(job.labels());
+        // Jobs created before the workload kind label
+        labels
+            .entry(WORKLOAD_KIND_LABEL.to_string())
+            .or_insert_with(|| JOB_KIND.to_string());
+        OrchestratorContainer {
+            id: job.uid().unwrap(),
+            name: job.name_any(),
+            state: Self::job_state(&job).to_string(),
+            envs,
+            labels,
+            restart_count: 0, // Will be updated from pod status
+            started_at: None, // Will be updated from pod status
+        }
+    }
+
+    async fn connector_pods(&self, connector_id: &str) -> Vec<Pod> {
+        if self.pod_access.is_forbidden() {
+            return Vec::new();
+        }
+        let lp = &ListParams::default().labels(&format!(
+            "opencti-connector-id={}",
+            sanitize_label_value(connector_id)
+        ));
         let deployment_pods_response = self.pods.list(lp).await;
         match deployment_pods_response {
-            Ok(pods) => {
-                let pod_list = pods.items;
-                match !pod_list.is_empty() {
-                    true => pod_list.into_iter().next(),
-                    false => None,
-                }
-            }
+            Ok(pods) => pods.items,
             Err(err) => {
-                error!(error = err.to_string(), "Fail to get deployment pod");
-                None
+                let status_code = Self::api_status_code(&err);
+                if !self.pod_access.handle_error(status_code, "list", "pods") {
+                    error!(error = err.to_string(), "Fail to get deployment pod");
+                }
+                Vec::new()
             }
         }
     }
 
+    async fn get_deployment_pod(&self, connector_id: String) -> Option<Pod> {
+        self.connector_pods(&connector_id).await.into_iter().next()
+    }
+
+    // Pod of the named deployment (through its replica set) or job, a renamed connector has
+    // the pods of both its old and new workloads under the same connector id label
+    pub fn is_workload_pod(pod: &Pod, workload_name: &str) -> bool {
+        pod.owner_references()
+            .iter()
+            .any(|owner| match owner.kind.as_str() {
+                "Job" => owner.name == workload_name,
+                "ReplicaSet" => owner
+                    .name
+                    .strip_prefix(workload_name)
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .is_some_and(|hash| !hash.is_empty() && !hash.contains('-')),
+                _ => false,
+            })
+    }
+
+    // An invalid label makes the API reject the whole deployment, normalize it instead
+    pub fn sanitize_labels(
+        labels: HashMap<String, String>,
+        connector_id: &str,
+    ) -> BTreeMap<String, String> {
+        labels
+            .into_iter()
+            .filter_map(|(key, value)| {
+                if !is_valid_label_key(&key) {
+                    warn!(id = connector_id, key, "Invalid label key, label skipped");
+                    return None;
+                }
+                if is_valid_label_value(&value) {
+                    return Some((key, value));
+                }
+                let sanitized = sanitize_label_value(&value);
+                warn!(
+                    id = connector_id,
+                    key, value, sanitized, "Invalid label value, label normalized"
+                );
+                Some((key, sanitized))
+            })
+            .collect()
+    }
+
     pub fn build_configuration(
         &self,
         connector: &ApiConnector,
         labels: HashMap<String, String>,
         proxy_ca_secret_name: Option<String>,
     ) -> Deployment {
-        let deployment_labels: BTreeMap<String, String> = labels.into_iter().collect();
+        let deployment_labels = Self::sanitize_labels(labels, &connector.id);
         let pod_env = self.container_envs(connector);
         let is_starting = &connector.requested_status == "starting";
-        let settings = crate::settings();
-        let registry_config = settings.opencti.daemon.registry.clone();
+        let registry_config = current_registry_config();
         let resolver = Image::new(registry_config);
-        let auth = resolver.get_credentials();
-        let image = resolver.build_name(connector.image.clone());
+        let image = resolver.build_name(connector.image());
         let selector = LabelSelector {
             match_labels: Some(deployment_labels.clone()),
             ..Default::default()
@@ -229,8 +627,8 @@ impl KubeOrchestrator {
             name: connector.container_name(),
             image: Some(image.clone()),
             env: Some(pod_env),
-            image_pull_policy: Some(self.get_image_pull_policy()),
-            resources: self.get_image_resources(),
+            image_pull_policy: Some(self.get_image_pull_policy(&connector.id)),
+            resources: self.get_image_resources(&connector.id),
             ..Default::default()
         };
         let mut volumes: Option<Vec<Volume>> = None;
@@ -252,31 +650,78 @@ impl KubeOrchestrator {
             }]);
         }
 
+        // Specific case to let the hash config on top level
+        let mut annotations = BTreeMap::from([(
+            "OPENCTI_CONFIG_HASH".into(),
+            connector.contract_hash.clone(),
+        )]);
+        // A pod template change always triggers a rollout, even when the env values merge identically
+        let pod_annotations = self.config.rollout_on_hash_change.then(|| {
+            BTreeMap::from([(
+                "OPENCTI_CONFIG_HASH".to_string(),
+                connector.contract_hash.clone(),
+            )])
+        });
+        // Requested status is mutable, keep it as annotation to stay out of the selector
+        annotations.extend(self.requested_status_label(connector));
+        annotations.extend(self.last_modified_by_label());
+        // Comma separated keys are no valid label value
+        annotations.extend([build_contract_keys_label(connector)]);
+        // Hash changes over time, only set on the deployment (never in the selector or the pods)
+        let mut metadata_labels = deployment_labels.clone();
+        let (hash_label, hash_value) = self.contract_hash_label(connector);
+        if is_valid_label_value(&hash_value) {
+            metadata_labels.insert(hash_label, hash_value);
+        } else {
+            warn!(
+                id = connector.id,
+                "Contract hash is not a valid label value, hash label skipped"
+            );
+        }
+        metadata_labels.extend([
+            build_owner_label(),
+            (WORKLOAD_KIND_LABEL.to_string(), DEPLOYMENT_KIND.to_string()),
+        ]);
+
+        let affinity = self
+            .config
+            .node_affinity
+            .clone()
+            .map(|node_affinity| Affinity {
+                node_affinity: Some(node_affinity),
+                ..Default::default()
+            });
+
         let target_deployment = Deployment {
             metadata: ObjectMeta {
                 name: Some(connector.container_name()),
-                labels: Some(deployment_labels.clone()),
-                // Specific case to let the hash config on top level
-                annotations: Some(BTreeMap::from([(
-                    "OPENCTI_CONFIG_HASH".into(),
-                    connector.contract_hash.clone(),
-                )])),
+                labels: Some(metadata_labels),
+                annotations: Some(annotations),
                 ..Default::default()
             },
             spec: Some(DeploymentSpec {
                 replicas: Some(if is_starting { 1 } else { 0 }),
+                revision_history_limit: Some(self.config.revision_history_limit),
                 selector,
                 template: PodTemplateSpec {
                     metadata: Some(ObjectMeta {
                         labels: Some(deployment_labels.clone()),
+                        annotations: pod_annotations,
                         ..Default::default()
                     }),
                     spec: Some(PodSpec {
-                        image_pull_secrets: auth.map(|_| {
-                            vec![LocalObjectReference {
-                                name: resolver.get_kubernetes_secret_name().unwrap(),
-                            }]
-                        }),
+                        image_pull_secrets: self
+                            .get_image_pull_secret(&connector.id, &resolver)
+                            .map(|name| vec![LocalObjectReference { name }]),
+                        node_selector: resolver.get_kubernetes_node_selector(),
+                        affinity,
+                        termination_grace_period_seconds: self
+                            .get_termination_grace_period(&connector.id),
+                        service_account_name: self.get_service_account_name(&connector.id),
+                        hostname: Some(container_hostname(
+                            connector,
+                            self.config.hostname.as_ref(),
+                        )),
                         containers: vec![container],
                         volumes,
                         ..Default::default()
@@ -303,6 +748,96 @@ impl KubeOrchestrator {
         base_deployment
     }
 
+    pub fn state_status(state: &str, distinct_unknown: bool) -> ConnectorStatus {
+        match state {
+            "running" => ConnectorStatus::Started,
+            "waiting" => ConnectorStatus::Started,
+            "exited" => ConnectorStatus::Stopped,
+            "terminated" => ConnectorStatus::Stopped,
+            // Job workloads
+            "suspended" | "completed" | "failed" => ConnectorStatus::Stopped,
+            _ => unrecognized_state_status(distinct_unknown),
+        }
+    }
+
+    // Same pod as the deployment, run once and never restarted after its completion
+    pub fn build_job(
+        &self,
+        connector: &ApiConnector,
+        labels: HashMap<String, String>,
+        proxy_ca_secret_name: Option<String>,
+    ) -> Job {
+        let mut deployment = self.build_configuration(connector, labels, proxy_ca_secret_name);
+        deployment
+            .labels_mut()
+            .insert(WORKLOAD_KIND_LABEL.to_string(), JOB_KIND.to_string());
+        let spec = deployment.spec.unwrap_or_default();
+        let mut template = spec.template;
+        if let Some(pod_spec) = template.spec.as_mut() {
+            pod_spec.restart_policy = Some("Never".to_string());
+        }
+        Job {
+            metadata: deployment.metadata,
+            spec: Some(JobSpec {
+                // The selector is generated by Kubernetes, a stopped connector waits suspended
+                suspend: Some(spec.replicas == Some(0)),
+                template,
+                ..Default::default()
+            }),
+            status: None,
+        }
+    }
+
+    // Deployed as the other kind before a workload type change, replaced by the configured kind
+    async fn remove_other_workload(&self, connector: &ApiConnector, workload_type: WorkloadType) {
+        let name = connector.container_name();
+        let delete_result = match workload_type {
+            WorkloadType::Job => self
+                .deployments
+                .delete(&name, &DeleteParams::default())
+                .await
+                .map(|_| ()),
+            WorkloadType::Deployment => self
+                .jobs
+                .delete(&name, &DeleteParams::background())
+                .await
+                .map(|_| ()),
+        };
+        match delete_result {
+            Ok(()) => info!(
+                id = connector.id,
+                name, "Workload type changed, previous workload removed"
+            ),
+            // Nothing to replace, or jobs not allowed when none is configured
+            Err(err)
+                if Self::is_not_found(&err)
+                    || Self::api_status_code(&err) == Some(HTTP_FORBIDDEN) => {}
+            Err(err) => warn!(
+                id = connector.id,
+                name,
+                error = err.to_string(),
+                "Fail removing the previous workload"
+            ),
+        }
+    }
+
+    async fn create_job(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        let labels = self.labels(connector);
+        let proxy_ca_secret_name = self.upsert_proxy_ca_secret(connector).await;
+        let job = self.build_job(connector, labels, proxy_ca_secret_name);
+        match self.jobs.create(&PostParams::default(), &job).await {
+            Ok(job) => Some(KubeOrchestrator::from_job(job)),
+            Err(e) => {
+                error!(
+                    id = connector.id,
+                    error = e.to_string(),
+                    "Kubernetes job creation error"
+                );
+                None
+            }
+        }
+    }
+
     pub fn build_refresh_patch(deployment: &Deployment) -> serde_json::Value {
         // spec.selector is immutable after creation — strip it from the merge
         // patch so Kubernetes leaves the existing selector untouched.
@@ -313,6 +848,77 @@ impl KubeOrchestrator {
         patch_value
     }
 
+    // Image and hash about to be replaced by the refresh, nothing when the deployed hash is already the requested one
+    pub fn previous_revision_annotations(
+        current: &Deployment,
+        connector: &ApiConnector,
+    ) -> BTreeMap<String, String> {
+        let previous_hash = current.annotations().get("OPENCTI_CONFIG_HASH");
+        if previous_hash == Some(&connector.contract_hash) {
+            return BTreeMap::new();
+        }
+        let previous_image = current
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.spec.as_ref())
+            .and_then(|pod_spec| pod_spec.containers.first())
+            .and_then(|container| container.image.clone());
+        [
+            (PREVIOUS_IMAGE_ANNOTATION, previous_image),
+            (PREVIOUS_HASH_ANNOTATION, previous_hash.cloned()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+        .collect()
+    }
+
+    // Image and env of the connector container, the fields reconciled by enforce_spec
+    fn connector_container_spec(
+        deployment: &Deployment,
+    ) -> (Option<String>, BTreeMap<String, Option<String>>) {
+        let container = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.spec.as_ref())
+            .and_then(|pod_spec| pod_spec.containers.first());
+        let image = container.and_then(|container| container.image.clone());
+        // Values coming from a secret or config map reference are never generated
+        let env = container
+            .and_then(|container| container.env.as_ref())
+            .map(|env| {
+                env.iter()
+                    .map(|var| (var.name.clone(), var.value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        (image, env)
+    }
+
+    // Fields of the live deployment diverging from the generated one
+    pub fn diverging_fields(live: &Deployment, expected: &Deployment) -> Vec<String> {
+        let (live_image, live_env) = Self::connector_container_spec(live);
+        let (expected_image, expected_env) = Self::connector_container_spec(expected);
+        let mut fields = Vec::new();
+        if live_image != expected_image {
+            fields.push("image".to_string());
+        }
+        if live_env != expected_env {
+            fields.push("env".to_string());
+        }
+        fields
+    }
+
+    // With the Never pull policy a missing image only shows up as a waiting pod, explain how to fix it
+    pub fn image_never_pull_error(container_status: &ContainerStatus) -> Option<String> {
+        let waiting = container_status.state.as_ref()?.waiting.as_ref()?;
+        (waiting.reason.as_deref() == Some("ErrImageNeverPull")).then(|| {
+            format!(
+                "Image {} is not present on the node and the pull policy is Never, pre-load it on the nodes or use another image_pull_policy",
+                container_status.image
+            )
+        })
+    }
+
     // Enrich container with pod information
     fn enrich_container_from_pod(&self, container: &mut OrchestratorContainer, pod: Pod) {
         let container_status = pod
@@ -322,6 +928,9 @@ impl KubeOrchestrator {
 
         if let Some(status) = container_status {
             container.restart_count = status.restart_count as u32;
+            if let Some(message) = Self::image_never_pull_error(&status) {
+                error!(id = container.extract_opencti_id(), message);
+            }
 
             if let Some(started_at) = self.extract_started_at(&status) {
                 container.started_at = Some(started_at);
@@ -338,11 +947,51 @@ impl KubeOrchestrator {
             .and_then(|running| running.started_at.as_ref())
             .map(|timestamp| timestamp.0.to_string())
     }
+
+    async fn pod_logs(&self, pod: Option<Pod>) -> Option<Vec<String>> {
+        match pod {
+            Some(pod) => {
+                let lp = LogParams::default();
+                let node_name = pod.metadata.name.unwrap();
+                let text_logs_response = self.pods.logs(node_name.as_str(), &lp).await;
+                match text_logs_response {
+                    Ok(text_logs) => Some(text_logs.lines().map(|line| line.to_string()).collect()),
+                    Err(err) if Self::is_not_found(&err) => {
+                        debug!(pod = node_name, "Pod already removed, no logs");
+                        None
+                    }
+                    Err(err) => {
+                        let status_code = Self::api_status_code(&err);
+                        if self.pod_access.handle_error(status_code, "get", "pods/log") {
+                            return None;
+                        }
+                        error!(error = err.to_string(), "Error fetching logs");
+                        Some(vec![err.to_string()])
+                    }
+                }
+            }
+            None => None,
+        }
+    }
 }
 
 #[async_trait]
 impl Orchestrator for KubeOrchestrator {
     async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        if self.workload_type(&connector.id) == WorkloadType::Job {
+            let job = match self.jobs.get(connector.container_name().as_str()).await {
+                Ok(job) => job,
+                Err(err) => {
+                    debug!(error = err.to_string(), "Cant find job");
+                    return None;
+                }
+            };
+            let mut container = KubeOrchestrator::from_job(job);
+            if let Some(pod) = self.get_deployment_pod(connector.id.clone()).await {
+                self.enrich_container_from_pod(&mut container, pod);
+            }
+            return Some(container);
+        }
         let deployment = match self
             .deployments
             .get(connector.container_name().as_str())
@@ -365,27 +1014,92 @@ impl Orchestrator for KubeOrchestrator {
         Some(container)
     }
 
+    async fn ping(&self) -> Result<(), String> {
+        self.deployments
+            .list_metadata(&ListParams::default().limit(1))
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
     async fn list(&self) -> Vec<OrchestratorContainer> {
         let settings = crate::settings();
-        let lp = &ListParams::default()
-            .labels(&format!("opencti-manager={}", settings.manager.id.clone()));
+        // Deployments carry the normalized manager id, see sanitize_labels
+        let lp = &ListParams::default().labels(&format!(
+            "opencti-manager={}",
+            sanitize_label_value(&settings.manager.id)
+        ));
         let get_deployments = self.deployments.list(lp).await.unwrap();
-        get_deployments
+        let mut containers: Vec<OrchestratorContainer> = get_deployments
             .into_iter()
             .map(|deployment| KubeOrchestrator::from_deployment(deployment))
-            .collect()
+            .collect();
+        // Jobs of connectors configured back as deployments are listed as well
+        match self.jobs.list(lp).await {
+            Ok(jobs) => containers.extend(jobs.into_iter().map(KubeOrchestrator::from_job)),
+            Err(err) if !self.has_jobs() && Self::api_status_code(&err) == Some(HTTP_FORBIDDEN) => {
+                debug!(
+                    error = err.to_string(),
+                    "Jobs not listable, no connector job configured"
+                )
+            }
+            Err(err) => error!(error = err.to_string(), "Fail listing the connector jobs"),
+        }
+        containers
     }
 
-    async fn start(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+    async fn start(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        if Self::is_finished_job(container) {
+            debug!(
+                id = connector.id,
+                state = container.state,
+                "Job already finished, nothing to start"
+            );
+            return;
+        }
         connector.display_env_variables();
-        self.set_deployment_scale(connector, 1).await;
+        match Self::container_workload_type(container) {
+            WorkloadType::Deployment => self.set_deployment_scale(connector, 1).await,
+            WorkloadType::Job => self.set_job_suspended(connector, false).await,
+        }
     }
 
-    async fn stop(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
-        self.set_deployment_scale(connector, 0).await;
+    async fn stop(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        match Self::container_workload_type(container) {
+            WorkloadType::Deployment => self.set_deployment_scale(connector, 0).await,
+            WorkloadType::Job => self.set_job_suspended(connector, true).await,
+        }
     }
 
     async fn remove(&self, container: &OrchestratorContainer) -> () {
+        if Self::container_workload_type(container) == WorkloadType::Job {
+            // Background propagation so the job pods are removed with it
+            let delete_response = self
+                .jobs
+                .delete(&container.name, &DeleteParams::background())
+                .await;
+            match delete_response {
+                Ok(_) => info!(
+                    name = container.name,
+                    id = container.extract_opencti_id(),
+                    "Job successfully deleted"
+                ),
+                Err(err) if Self::is_not_found(&err) => {
+                    debug!(name = container.name, "Job already removed")
+                }
+                Err(err) => error!(
+                    name = container.name,
+                    error = err.to_string(),
+                    "Fail removing the job"
+                ),
+            }
+            let proxy_secret_name = Self::proxy_ca_secret_name(&container.name);
+            let _ = self
+                .secrets
+                .delete(proxy_secret_name.as_str(), &DeleteParams::default())
+                .await;
+            return;
+        }
         let dp = &DeleteParams::default();
         let delete_response = self.deployments.delete(&container.name, dp).await;
         match delete_response {
@@ -394,6 +1108,9 @@ impl Orchestrator for KubeOrchestrator {
                 id = container.extract_opencti_id(),
                 "Deployment successfully deleted"
             ),
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name = container.name, "Deployment already removed")
+            }
             Err(err) => error!(
                 name = container.name,
                 error = err.to_string(),
@@ -409,18 +1126,56 @@ impl Orchestrator for KubeOrchestrator {
     }
 
     async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.ensure_registry_secret().await;
+        if self.workload_type(&connector.id) == WorkloadType::Job {
+            // The job pod template is immutable, run it again with the new configuration
+            let name = connector.container_name();
+            match self.jobs.delete(&name, &DeleteParams::background()).await {
+                Ok(_) => {}
+                Err(err) if Self::is_not_found(&err) => {
+                    debug!(name, "Job already removed, creating it")
+                }
+                Err(err) => {
+                    error!(name, error = err.to_string(), "Fail removing the job");
+                    return None;
+                }
+            }
+            return self.create_job(connector).await;
+        }
         let labels = self.labels(connector);
         let proxy_ca_secret_name = self.upsert_proxy_ca_secret(connector).await;
-        let deployment_patch = self.build_configuration(connector, labels, proxy_ca_secret_name);
+        let mut deployment_patch =
+            self.build_configuration(connector, labels, proxy_ca_secret_name);
+        let name = connector.container_name();
+        if self.config.record_previous_revision {
+            match self.deployments.get_opt(name.as_str()).await {
+                Ok(Some(current)) => {
+                    let previous = Self::previous_revision_annotations(&current, connector);
+                    deployment_patch.annotations_mut().extend(previous);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(
+                        id = connector.id,
+                        error = e.to_string(),
+                        "Unable to read the deployed revision, previous image not recorded"
+                    );
+                }
+            }
+        }
         let patch_value = Self::build_refresh_patch(&deployment_patch);
         let patch = Patch::Merge(&patch_value);
-        let name = connector.container_name();
         let deployment_result = self
             .deployments
             .patch(name.as_str(), &PatchParams::default(), &patch)
             .await;
         match deployment_result {
             Ok(deployment) => Some(KubeOrchestrator::from_deployment(deployment)),
+            // Deployed again by the next cycle
+            Err(err) if Self::is_not_found(&err) => {
+                debug!(name, "Deployment already removed, nothing to refresh");
+                None
+            }
             Err(kube::Error::Api(ae)) => {
                 error!(error = ae.to_string(), "Kubernetes update api error");
                 None
@@ -432,7 +1187,36 @@ impl Orchestrator for KubeOrchestrator {
         }
     }
 
+    async fn spec_drift(&self, connector: &ApiConnector) -> Vec<String> {
+        // Jobs are never updated in place, a new configuration runs them again
+        if !self.config.enforce_spec || self.workload_type(&connector.id) == WorkloadType::Job {
+            return Vec::new();
+        }
+        let name = connector.container_name();
+        match self.deployments.get_opt(name.as_str()).await {
+            Ok(Some(live)) => {
+                let expected = self.build_configuration(connector, self.labels(connector), None);
+                Self::diverging_fields(&live, &expected)
+            }
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                warn!(
+                    id = connector.id,
+                    error = e.to_string(),
+                    "Unable to read the deployment, spec drift not checked"
+                );
+                Vec::new()
+            }
+        }
+    }
+
     async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.ensure_registry_secret().await;
+        let workload_type = self.workload_type(&connector.id);
+        self.remove_other_workload(connector, workload_type).await;
+        if workload_type == WorkloadType::Job {
+            return self.create_job(connector).await;
+        }
         let labels = self.labels(connector);
         let proxy_ca_secret_name = self.upsert_proxy_ca_secret(connector).await;
         let deployment_creation =
@@ -459,33 +1243,811 @@ impl Orchestrator for KubeOrchestrator {
         _container: &OrchestratorContainer,
         connector: &ApiConnector,
     ) -> Option<Vec<String>> {
-        let deployment_pod = self.get_deployment_pod(connector.id.clone()).await;
-        match deployment_pod {
-            Some(pod) => {
-                let lp = LogParams::default();
-                let node_name = pod.metadata.name.unwrap();
-                let text_logs_response = self.pods.logs(node_name.as_str(), &lp).await;
-                match text_logs_response {
-                    Ok(text_logs) => Some(text_logs.lines().map(|line| line.to_string()).collect()),
-                    Err(err) => {
-                        error!(error = err.to_string(), "Error fetching logs");
-                        Some(vec![err.to_string()])
+        let pod = self.get_deployment_pod(connector.id.clone()).await;
+        self.pod_logs(pod).await
+    }
+
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        let pod = self
+            .connector_pods(&container.extract_opencti_id())
+            .await
+            .into_iter()
+            .find(|pod| Self::is_workload_pod(pod, &container.name));
+        self.pod_logs(pod).await
+    }
+
+    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
+        let distinct_unknown = crate::settings().manager.distinct_unknown_status;
+        Self::state_status(&container.state, distinct_unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn unrecognized_states_are_unknown_only_when_enabled() {
+        for state in ["running", "waiting"] {
+            assert_eq!(
+                KubeOrchestrator::state_status(state, true),
+                ConnectorStatus::Started
+            );
+        }
+        assert_eq!(
+            KubeOrchestrator::state_status("failed", true),
+            ConnectorStatus::Stopped
+        );
+        assert_eq!(
+            KubeOrchestrator::state_status("unknown", true),
+            ConnectorStatus::Unknown
+        );
+        assert_eq!(
+            KubeOrchestrator::state_status("unknown", false),
+            ConnectorStatus::Stopped
+        );
+    }
+
+    #[test]
+    fn workload_being_deleted_is_in_an_unknown_state() {
+        let deletion_timestamp =
+            Some(serde_json::from_value(serde_json::json!("2026-10-16T08:00:00Z")).unwrap());
+        let mut deployment = build_deployment(serde_json::json!({}), "connector-deleted");
+        deployment.metadata.uid = Some("connector-deleted-uid".to_string());
+        deployment.metadata.deletion_timestamp = deletion_timestamp.clone();
+        let container = KubeOrchestrator::from_deployment(deployment);
+        assert_eq!(container.state, "removing");
+        assert_eq!(
+            KubeOrchestrator::state_status(&container.state, true),
+            ConnectorStatus::Unknown
+        );
+
+        let mut job = job_with(false, &[]);
+        job.metadata.deletion_timestamp = deletion_timestamp;
+        assert_eq!(KubeOrchestrator::job_state(&job), "removing");
+    }
+
+    #[test]
+    fn pod_access_forbidden_disables_collection_once() {
+        let guard = PodAccessGuard::default();
+        assert!(!guard.is_forbidden());
+
+        assert!(guard.handle_error(Some(403), "list", "pods"));
+        assert!(guard.is_forbidden());
+        // Subsequent denials are still detected but the guard stays disabled
+        assert!(guard.handle_error(Some(403), "get", "pods/log"));
+        assert!(guard.is_forbidden());
+    }
+
+    #[test]
+    fn registry_secret_recreated_only_on_credentials_change() {
+        let current = Mutex::new(Some("fingerprint-1".to_string()));
+        assert!(!KubeOrchestrator::registry_secret_outdated(
+            &current,
+            Some("fingerprint-1".to_string())
+        ));
+        assert!(KubeOrchestrator::registry_secret_outdated(
+            &current,
+            Some("fingerprint-2".to_string())
+        ));
+        // The new fingerprint is recorded, no second recreation
+        assert!(!KubeOrchestrator::registry_secret_outdated(
+            &current,
+            Some("fingerprint-2".to_string())
+        ));
+        assert!(KubeOrchestrator::registry_secret_outdated(&current, None));
+    }
+
+    #[test]
+    fn pod_access_other_errors_keep_collection_enabled() {
+        let guard = PodAccessGuard::default();
+        assert!(!guard.handle_error(Some(404), "list", "pods"));
+        assert!(!guard.handle_error(Some(500), "get", "pods/log"));
+        assert!(!guard.handle_error(None, "list", "pods"));
+        assert!(!guard.is_forbidden());
+    }
+
+    // Orchestrator on an unreachable API server, enough to build the deployments
+    fn offline_orchestrator(config: Kubernetes) -> KubeOrchestrator {
+        orchestrator_on("http://127.0.0.1:1", config)
+    }
+
+    fn orchestrator_on(api_server: &str, config: Kubernetes) -> KubeOrchestrator {
+        let kube_config = kube::Config::new(api_server.parse().unwrap());
+        let client = Client::try_from(kube_config).unwrap();
+        KubeOrchestrator {
+            pods: Api::namespaced(client.clone(), "default"),
+            deployments: Api::namespaced(client.clone(), "default"),
+            jobs: Api::namespaced(client.clone(), "default"),
+            secrets: Api::namespaced(client, "default"),
+            config,
+            pod_access: PodAccessGuard::default(),
+            registry_fingerprint: Mutex::new(None),
+        }
+    }
+
+    fn build_deployment(config: serde_json::Value, connector_id: &str) -> Deployment {
+        let orchestrator = offline_orchestrator(serde_json::from_value(config).unwrap());
+        let connector = ApiConnector::for_test(connector_id);
+        orchestrator.build_configuration(&connector, orchestrator.labels(&connector), None)
+    }
+
+    #[tokio::test]
+    async fn invalid_labels_are_normalized_instead_of_rejected() {
+        let orchestrator =
+            offline_orchestrator(serde_json::from_value(serde_json::json!({})).unwrap());
+        let connector = ApiConnector::for_test("connector-1");
+        let mut labels = orchestrator.labels(&connector);
+        labels.insert("opencti-manager".to_string(), "Team Manager #1".to_string());
+        labels.insert("opencti-team".to_string(), "t".repeat(70));
+        labels.insert("Invalid Key".to_string(), "value".to_string());
+
+        let deployment = orchestrator.build_configuration(&connector, labels, None);
+
+        let selector = deployment.spec.unwrap().selector.match_labels.unwrap();
+        assert_eq!(selector.get("opencti-manager").unwrap(), "Team-Manager--1");
+        assert_eq!(selector.get("opencti-team").unwrap(), &"t".repeat(63));
+        assert!(!selector.contains_key("Invalid Key"));
+        assert_eq!(selector.get("opencti-connector-id").unwrap(), "connector-1");
+        let metadata_labels = deployment.metadata.labels.unwrap();
+        assert_eq!(
+            metadata_labels.get("opencti-manager").unwrap(),
+            "Team-Manager--1"
+        );
+    }
+
+    #[tokio::test]
+    async fn deployment_spec_carries_revision_history_limit() {
+        let deployment = build_deployment(
+            serde_json::json!({ "revision_history_limit": 5 }),
+            "connector-1",
+        );
+
+        assert_eq!(deployment.spec.unwrap().revision_history_limit, Some(5));
+    }
+
+    fn termination_grace_period(deployment: Deployment) -> Option<i64> {
+        deployment
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.termination_grace_period_seconds)
+    }
+
+    fn pod_template_hash(config: serde_json::Value, contract_hash: &str) -> Option<String> {
+        let orchestrator = offline_orchestrator(serde_json::from_value(config).unwrap());
+        let connector = ApiConnector {
+            contract_hash: contract_hash.to_string(),
+            ..ApiConnector::for_test("connector-1")
+        };
+        orchestrator
+            .build_configuration(&connector, orchestrator.labels(&connector), None)
+            .spec
+            .and_then(|spec| spec.template.metadata)
+            .and_then(|metadata| metadata.annotations)
+            .and_then(|annotations| annotations.get("OPENCTI_CONFIG_HASH").cloned())
+    }
+
+    #[tokio::test]
+    async fn pod_template_annotation_follows_config_hash() {
+        let config = serde_json::json!({});
+        assert_eq!(
+            pod_template_hash(config.clone(), "hash-1"),
+            Some("hash-1".to_string())
+        );
+        assert_eq!(
+            pod_template_hash(config, "hash-2"),
+            Some("hash-2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn pod_template_annotation_disabled() {
+        let config = serde_json::json!({ "rollout_on_hash_change": false });
+        assert_eq!(pod_template_hash(config, "hash-1"), None);
+    }
+
+    fn service_account_name(deployment: Deployment) -> Option<String> {
+        deployment
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.service_account_name)
+    }
+
+    #[tokio::test]
+    async fn pod_spec_carries_service_account_name() {
+        let config = serde_json::json!({
+            "service_account_name": "connectors",
+            "connector_service_account_names": { "secrets-connector": "secrets-reader" }
+        });
+
+        let regular = build_deployment(config.clone(), "connector-1");
+        assert_eq!(
+            service_account_name(regular),
+            Some("connectors".to_string())
+        );
+        let secrets = build_deployment(config, "secrets-connector");
+        assert_eq!(
+            service_account_name(secrets),
+            Some("secrets-reader".to_string())
+        );
+        // Namespace default service account when nothing is configured
+        let unset = build_deployment(serde_json::json!({}), "connector-1");
+        assert_eq!(service_account_name(unset), None);
+    }
+
+    #[tokio::test]
+    async fn pod_spec_uses_connector_image_pull_secret() {
+        let config = serde_json::json!({
+            "connector_image_pull_secrets": { "private-connector": "other-registry-auth" }
+        });
+        let pull_secrets = |deployment: Deployment| {
+            deployment
+                .spec
+                .and_then(|spec| spec.template.spec)
+                .and_then(|pod_spec| pod_spec.image_pull_secrets)
+                .map(|secrets| secrets.into_iter().map(|secret| secret.name).collect())
+        };
+
+        let private = build_deployment(config.clone(), "private-connector");
+        assert_eq!(
+            pull_secrets(private),
+            Some(vec!["other-registry-auth".to_string()])
+        );
+        // Without registry credentials, other connectors get no pull secret
+        let regular = build_deployment(config, "connector-1");
+        assert_eq!(pull_secrets(regular), None::<Vec<String>>);
+    }
+
+    #[tokio::test]
+    async fn pod_spec_carries_node_affinity() {
+        let config = serde_json::json!({
+            "node_affinity": {
+                "preferredDuringSchedulingIgnoredDuringExecution": [{
+                    "weight": 100,
+                    "preference": {
+                        "matchExpressions": [{
+                            "key": "topology.kubernetes.io/zone",
+                            "operator": "In",
+                            "values": ["eu-west-1a"]
+                        }]
                     }
-                }
+                }]
             }
-            None => None,
-        }
+        });
+
+        let node_affinity = build_deployment(config, "connector-1")
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.affinity)
+            .and_then(|affinity| affinity.node_affinity)
+            .unwrap();
+        let terms = node_affinity
+            .preferred_during_scheduling_ignored_during_execution
+            .unwrap();
+        assert_eq!(terms.len(), 1);
+        assert_eq!(terms[0].weight, 100);
+        let expression = &terms[0].preference.match_expressions.as_ref().unwrap()[0];
+        assert_eq!(expression.key, "topology.kubernetes.io/zone");
+        assert_eq!(expression.values, Some(vec!["eu-west-1a".to_string()]));
+
+        let pod_spec = build_deployment(serde_json::json!({}), "connector-1")
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .unwrap();
+        assert!(pod_spec.affinity.is_none());
     }
 
-    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
-        match container.state.as_str() {
-            "running" => ConnectorStatus::Started,
-            "waiting" => ConnectorStatus::Started,
-            "exited" => ConnectorStatus::Stopped,
-            "terminated" => ConnectorStatus::Stopped,
-            _ => ConnectorStatus::Stopped,
+    fn image_pull_policy(config: serde_json::Value, connector_id: &str) -> Option<String> {
+        build_deployment(config, connector_id)
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.containers.into_iter().next())
+            .and_then(|container| container.image_pull_policy)
+    }
+
+    #[tokio::test]
+    async fn image_pull_policy_follows_connector_override() {
+        let config = serde_json::json!({
+            "image_pull_policy": "Never",
+            "connector_image_pull_policies": {
+                "online-connector": "Always",
+                "invalid-connector": "Sometimes"
+            }
+        });
+
+        assert_eq!(
+            image_pull_policy(config.clone(), "connector-1"),
+            Some("Never".to_string())
+        );
+        assert_eq!(
+            image_pull_policy(config.clone(), "online-connector"),
+            Some("Always".to_string())
+        );
+        // An invalid override falls back to the global policy, not the default one
+        assert_eq!(
+            image_pull_policy(config, "invalid-connector"),
+            Some("Never".to_string())
+        );
+    }
+
+    #[test]
+    fn never_pull_policy_missing_image_is_reported_distinctly() {
+        let status = |reason: &str| -> ContainerStatus {
+            serde_json::from_value(serde_json::json!({
+                "name": "connector",
+                "image": "opencti/connector-misp:6.8.0",
+                "imageID": "",
+                "ready": false,
+                "restartCount": 0,
+                "state": { "waiting": { "reason": reason } }
+            }))
+            .unwrap()
+        };
+
+        let message =
+            KubeOrchestrator::image_never_pull_error(&status("ErrImageNeverPull")).unwrap();
+        assert!(message.contains("opencti/connector-misp:6.8.0"));
+        assert!(message.contains("pre-load"));
+        assert_eq!(
+            KubeOrchestrator::image_never_pull_error(&status("ImagePullBackOff")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_captures_the_previous_image_and_hash() {
+        let orchestrator =
+            offline_orchestrator(serde_json::from_value(serde_json::json!({})).unwrap());
+        let mut deployed = ApiConnector::for_test("connector-1");
+        deployed.image = "opencti/connector-test:6.7.0".to_string();
+        deployed.contract_hash = "hash-6.7.0".to_string();
+        let current =
+            orchestrator.build_configuration(&deployed, orchestrator.labels(&deployed), None);
+
+        let mut refreshed = ApiConnector::for_test("connector-1");
+        refreshed.image = "opencti/connector-test:6.8.0".to_string();
+        refreshed.contract_hash = "hash-6.8.0".to_string();
+        let previous = KubeOrchestrator::previous_revision_annotations(&current, &refreshed);
+
+        let previous_image = previous.get("opencti-previous-image").unwrap();
+        assert!(
+            previous_image.ends_with("opencti/connector-test:6.7.0"),
+            "{previous_image}"
+        );
+        assert_eq!(previous.get("opencti-previous-hash").unwrap(), "hash-6.7.0");
+
+        // Refreshing to the deployed hash keeps the recorded previous revision
+        assert!(KubeOrchestrator::previous_revision_annotations(&current, &deployed).is_empty());
+    }
+
+    #[tokio::test]
+    async fn manual_image_and_env_changes_are_detected_as_drift() {
+        let expected = build_deployment(serde_json::json!({}), "connector-1");
+        assert!(KubeOrchestrator::diverging_fields(&expected, &expected).is_empty());
+
+        // kubectl set image
+        let mut live = expected.clone();
+        let pod_spec = live.spec.as_mut().unwrap().template.spec.as_mut().unwrap();
+        pod_spec.containers[0].image = Some("opencti/connector-test:patched".to_string());
+        assert_eq!(
+            KubeOrchestrator::diverging_fields(&live, &expected),
+            vec!["image".to_string()]
+        );
+
+        // kubectl set env
+        let mut live = expected.clone();
+        let pod_spec = live.spec.as_mut().unwrap().template.spec.as_mut().unwrap();
+        pod_spec.containers[0]
+            .env
+            .get_or_insert_with(Vec::new)
+            .push(EnvVar {
+                name: "CONNECTOR_LOG_LEVEL".to_string(),
+                value: Some("debug".to_string()),
+                value_from: None,
+            });
+        assert_eq!(
+            KubeOrchestrator::diverging_fields(&live, &expected),
+            vec!["env".to_string()]
+        );
+
+        // Scaling is driven by the requested status, not a drift
+        let mut live = expected.clone();
+        live.spec.as_mut().unwrap().replicas = Some(0);
+        assert!(KubeOrchestrator::diverging_fields(&live, &expected).is_empty());
+    }
+
+    fn kubernetes_config(config: serde_json::Value) -> Kubernetes {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn client_config_source_selection() {
+        let inferred = kubernetes_config(serde_json::json!({}));
+        assert_eq!(
+            client_config_source(&inferred),
+            ClientConfigSource::Inferred
+        );
+
+        let context = kubernetes_config(serde_json::json!({ "context": "production" }));
+        assert_eq!(
+            client_config_source(&context),
+            ClientConfigSource::Context("production")
+        );
+
+        // Forcing the in cluster config wins over the context
+        let in_cluster =
+            kubernetes_config(serde_json::json!({ "in_cluster": true, "context": "production" }));
+        assert_eq!(
+            client_config_source(&in_cluster),
+            ClientConfigSource::InCluster
+        );
+    }
+
+    #[tokio::test]
+    async fn context_selects_the_kubeconfig_cluster() {
+        let kubeconfig = Kubeconfig::from_yaml(
+            r#"
+apiVersion: v1
+kind: Config
+current-context: staging
+clusters:
+  - name: staging
+    cluster:
+      server: https://staging.example.com:6443
+  - name: production
+    cluster:
+      server: https://production.example.com:6443
+contexts:
+  - name: staging
+    context:
+      cluster: staging
+      user: composer
+      namespace: connectors
+  - name: production
+    context:
+      cluster: production
+      user: composer
+      namespace: connectors
+users:
+  - name: composer
+    user:
+      token: composer-token
+"#,
+        )
+        .unwrap();
+
+        let config = context_config(kubeconfig.clone(), "production")
+            .await
+            .unwrap();
+        assert_eq!(
+            config.cluster_url.to_string(),
+            "https://production.example.com:6443/"
+        );
+        assert_eq!(config.default_namespace, "connectors");
+
+        let error = context_config(kubeconfig, "unknown").await.unwrap_err();
+        assert!(error.contains("unknown"), "{error}");
+    }
+
+    fn pod_hostname(deployment: Deployment) -> Option<String> {
+        deployment
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.hostname)
+    }
+
+    #[tokio::test]
+    async fn pod_spec_carries_hostname() {
+        let default = build_deployment(serde_json::json!({}), "connector-1");
+        assert_eq!(pod_hostname(default), Some("connector-1".to_string()));
+        let configured = build_deployment(
+            serde_json::json!({ "hostname": "connectors" }),
+            "connector-1",
+        );
+        assert_eq!(pod_hostname(configured), Some("connectors".to_string()));
+    }
+
+    #[tokio::test]
+    async fn pod_spec_carries_termination_grace_period() {
+        let config = serde_json::json!({
+            "termination_grace_period": 60,
+            "connector_termination_grace_periods": { "slow-connector": 300 }
+        });
+
+        let regular = build_deployment(config.clone(), "connector-1");
+        assert_eq!(termination_grace_period(regular), Some(60));
+        let slow = build_deployment(config, "slow-connector");
+        assert_eq!(termination_grace_period(slow), Some(300));
+        // Kubernetes default applies when nothing is configured
+        let unset = build_deployment(serde_json::json!({}), "connector-1");
+        assert_eq!(termination_grace_period(unset), None);
+    }
+
+    fn container_limits(deployment: Deployment) -> Option<BTreeMap<String, Quantity>> {
+        deployment
+            .spec
+            .and_then(|spec| spec.template.spec)
+            .and_then(|pod_spec| pod_spec.containers.into_iter().next())
+            .and_then(|container| container.resources)
+            .and_then(|resources| resources.limits)
+    }
+
+    #[tokio::test]
+    async fn gpu_connector_carries_gpu_resource_limit() {
+        let config = serde_json::json!({
+            "image_resources": { "limits": { "memory": "1Gi" } },
+            "connector_gpus": { "gpu-connector": { "nvidia.com/gpu": "1" } }
+        });
+
+        let limits = container_limits(build_deployment(config.clone(), "gpu-connector")).unwrap();
+        assert_eq!(
+            limits.get("nvidia.com/gpu"),
+            Some(&Quantity("1".to_string()))
+        );
+        assert_eq!(limits.get("memory"), Some(&Quantity("1Gi".to_string())));
+
+        let limits = container_limits(build_deployment(config, "connector-1")).unwrap();
+        assert_eq!(limits.get("nvidia.com/gpu"), None);
+        // GPUs are enough to set the limits
+        let gpu_only = serde_json::json!({
+            "connector_gpus": { "gpu-connector": { "amd.com/gpu": "2" } }
+        });
+        let limits = container_limits(build_deployment(gpu_only, "gpu-connector")).unwrap();
+        assert_eq!(limits.get("amd.com/gpu"), Some(&Quantity("2".to_string())));
+    }
+
+    const DEPLOYMENT_NOT_FOUND: &str = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"deployments.apps \"connector-gone\" not found","reason":"NotFound","details":{"name":"connector-gone","group":"apps","kind":"deployments"},"code":404}"#;
+
+    // API server answering every request with a 404 Status
+    async fn api_server_not_found() -> String {
+        counting_api_server_not_found().await.0
+    }
+
+    // Same, along with the number of requests received
+    async fn counting_api_server_not_found() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let received = requests.clone();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_server = format!("http://{}", listener.local_addr().unwrap());
+        let answer = format!(
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            DEPLOYMENT_NOT_FOUND.len(),
+            DEPLOYMENT_NOT_FOUND
+        );
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                received.fetch_add(1, Ordering::Relaxed);
+                let mut buffer = vec![0u8; 8192];
+                let _ = stream.read(&mut buffer).await;
+                let _ = stream.write_all(answer.as_bytes()).await;
+            }
+        });
+        (api_server, requests)
+    }
+
+    #[tokio::test]
+    async fn stop_of_removed_deployment_is_skipped() {
+        let api_server = api_server_not_found().await;
+        let config = serde_json::from_value(serde_json::json!({})).unwrap();
+        let orchestrator = orchestrator_on(&api_server, config);
+        let connector = ApiConnector::for_test("connector-gone");
+        let mut deployment = build_deployment(serde_json::json!({}), "connector-gone");
+        deployment.metadata.uid = Some("connector-gone-uid".to_string());
+        let container = KubeOrchestrator::from_deployment(deployment);
+
+        // Used to panic on the scale patch error
+        orchestrator.stop(&container, &connector).await;
+        orchestrator.remove(&container).await;
+        assert!(orchestrator.refresh(&connector).await.is_none());
+    }
+
+    fn build_job(connector: &ApiConnector) -> Job {
+        let orchestrator =
+            offline_orchestrator(serde_json::from_value(serde_json::json!({})).unwrap());
+        orchestrator.build_job(connector, orchestrator.labels(connector), None)
+    }
+
+    #[tokio::test]
+    async fn job_runs_the_connector_pod_once() {
+        let connector = ApiConnector::for_test("job-connector");
+        let job = build_job(&connector);
+
+        assert_eq!(job.metadata.name.as_deref(), Some("job-connector"));
+        assert_eq!(
+            job.annotations()
+                .get("OPENCTI_CONFIG_HASH")
+                .map(String::as_str),
+            Some("hash")
+        );
+        let spec = job.spec.unwrap();
+        assert_eq!(spec.suspend, Some(false));
+        // Generated by Kubernetes from the job uid
+        assert!(spec.selector.is_none());
+        let pod_spec = spec.template.spec.unwrap();
+        assert_eq!(pod_spec.restart_policy.as_deref(), Some("Never"));
+        assert_eq!(
+            pod_spec.containers[0].image.as_deref(),
+            Some("opencti/connector-test:latest")
+        );
+        let pod_labels = spec.template.metadata.unwrap().labels.unwrap();
+        assert_eq!(
+            pod_labels.get("opencti-connector-id").unwrap(),
+            "job-connector"
+        );
+
+        let mut stopped = ApiConnector::for_test("job-connector");
+        stopped.requested_status = "stopping".to_string();
+        assert_eq!(build_job(&stopped).spec.unwrap().suspend, Some(true));
+    }
+
+    fn job_with(suspend: bool, conditions: &[(&str, &str)]) -> Job {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "job-connector", "uid": "job-uid" },
+            "spec": { "suspend": suspend, "template": {} },
+            "status": {
+                "conditions": conditions
+                    .iter()
+                    .map(|(kind, status)| serde_json::json!({ "type": kind, "status": status }))
+                    .collect::<Vec<_>>()
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn job_completion_is_mapped_to_connector_state() {
+        let orchestrator =
+            offline_orchestrator(serde_json::from_value(serde_json::json!({})).unwrap());
+        let cases = [
+            (job_with(false, &[]), "running", ConnectorStatus::Started),
+            (job_with(true, &[]), "suspended", ConnectorStatus::Stopped),
+            (
+                job_with(false, &[("Complete", "True")]),
+                "completed",
+                ConnectorStatus::Stopped,
+            ),
+            (
+                job_with(false, &[("Failed", "True")]),
+                "failed",
+                ConnectorStatus::Stopped,
+            ),
+            // A condition not reached yet is ignored
+            (
+                job_with(false, &[("Complete", "False")]),
+                "running",
+                ConnectorStatus::Started,
+            ),
+        ];
+        for (job, state, status) in cases {
+            let container = KubeOrchestrator::from_job(job);
+            assert_eq!(container.state, state);
+            assert_eq!(orchestrator.state_converter(&container), status);
         }
     }
+
+    #[tokio::test]
+    async fn workload_kind_is_recorded_on_the_deployed_object() {
+        let connector = ApiConnector::for_test("connector-1");
+        let mut deployment = build_deployment(serde_json::json!({}), "connector-1");
+        assert_eq!(
+            deployment.labels().get(WORKLOAD_KIND_LABEL).unwrap(),
+            DEPLOYMENT_KIND
+        );
+        deployment.metadata.uid = Some("connector-1-uid".to_string());
+        let container = KubeOrchestrator::from_deployment(deployment);
+        assert_eq!(
+            KubeOrchestrator::container_workload_type(&container),
+            WorkloadType::Deployment
+        );
+        let job = build_job(&connector);
+        assert_eq!(job.labels().get(WORKLOAD_KIND_LABEL).unwrap(), JOB_KIND);
+        // Pods keep the deployment selector labels only
+        let pod_labels = job.spec.unwrap().template.metadata.unwrap().labels.unwrap();
+        assert!(!pod_labels.contains_key(WORKLOAD_KIND_LABEL));
+
+        // Jobs created before the label
+        let legacy = KubeOrchestrator::from_job(job_with(false, &[]));
+        assert_eq!(
+            KubeOrchestrator::container_workload_type(&legacy),
+            WorkloadType::Job
+        );
+    }
+
+    #[tokio::test]
+    async fn finished_job_is_not_started_again() {
+        let (api_server, requests) = counting_api_server_not_found().await;
+        // Configured back as a deployment, the job is still driven as a job
+        let config = serde_json::from_value(serde_json::json!({})).unwrap();
+        let orchestrator = orchestrator_on(&api_server, config);
+        let connector = ApiConnector::for_test("job-connector");
+
+        let completed = KubeOrchestrator::from_job(job_with(false, &[("Complete", "True")]));
+        orchestrator.start(&completed, &connector).await;
+        let failed = KubeOrchestrator::from_job(job_with(false, &[("Failed", "True")]));
+        orchestrator.start(&failed, &connector).await;
+        assert_eq!(requests.load(Ordering::Relaxed), 0);
+
+        let suspended = KubeOrchestrator::from_job(job_with(true, &[]));
+        orchestrator.start(&suspended, &connector).await;
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+    }
+
+    fn owned_pod(kind: &str, owner: &str) -> Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": format!("{owner}-pod"),
+                "ownerReferences": [
+                    { "apiVersion": "apps/v1", "kind": kind, "name": owner, "uid": "owner-uid" }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn removal_logs_pod_belongs_to_the_removed_workload() {
+        let old_pod = owned_pod("ReplicaSet", "misp-5d4f8c7b9");
+        assert!(KubeOrchestrator::is_workload_pod(&old_pod, "misp"));
+        // Renamed connector, same connector id label
+        let new_pod = owned_pod("ReplicaSet", "misp-eu-6c9b7f5d8");
+        assert!(!KubeOrchestrator::is_workload_pod(&new_pod, "misp"));
+        assert!(KubeOrchestrator::is_workload_pod(&new_pod, "misp-eu"));
+
+        let job_pod = owned_pod("Job", "misp");
+        assert!(KubeOrchestrator::is_workload_pod(&job_pod, "misp"));
+        assert!(!KubeOrchestrator::is_workload_pod(&job_pod, "misp-eu"));
+    }
+
+    #[tokio::test]
+    async fn workload_type_defaults_to_deployment() {
+        let config = serde_json::json!({ "connector_workload_types": { "job-connector": "job" } });
+        let orchestrator = offline_orchestrator(serde_json::from_value(config).unwrap());
+        assert_eq!(
+            orchestrator.workload_type("job-connector"),
+            WorkloadType::Job
+        );
+        assert_eq!(
+            orchestrator.workload_type("other"),
+            WorkloadType::Deployment
+        );
+        assert!(orchestrator.has_jobs());
+    }
+
+    #[tokio::test]
+    async fn scale_updates_the_last_modified_by_annotation() {
+        let last_modified_by =
+            |manager_id: &str| crate::orchestrator::build_last_modified_by_label(true, manager_id);
+        let mut deployment = build_deployment(serde_json::json!({}), "connector-1");
+        deployment
+            .annotations_mut()
+            .extend(last_modified_by("composer-eu"));
+
+        // A scale from another composer takes over the annotation, the others are kept
+        deployment.merge_from(KubeOrchestrator::build_scale_patch(
+            0,
+            last_modified_by("composer-us"),
+        ));
+        let annotations = deployment.annotations();
+        assert_eq!(
+            annotations.get(crate::orchestrator::LAST_MODIFIED_BY_LABEL),
+            Some(&"composer-us".to_string())
+        );
+        assert!(annotations.contains_key("OPENCTI_CONFIG_HASH"));
+        assert_eq!(deployment.spec.unwrap().replicas, Some(0));
+
+        let untracked = KubeOrchestrator::build_scale_patch(1, None);
+        assert!(untracked.metadata.annotations.is_none());
+    }
+
+    #[test]
+    fn revision_history_limit_defaults_to_three() {
+        let config: Kubernetes = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(config.revision_history_limit, 3);
+    }
 }
 
 // region async map resolution code sample
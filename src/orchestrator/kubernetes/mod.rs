@@ -1,13 +1,25 @@
 use crate::config::settings::Kubernetes;
 use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::batch::v1::Job;
 use k8s_openapi::api::core::v1::{Pod, Secret};
 use kube::Api;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
 
 pub mod kubernetes;
 
 pub struct KubeOrchestrator {
     pods: Api<Pod>,
     deployments: Api<Deployment>,
+    jobs: Api<Job>,
     secrets: Api<Secret>,
-    config: Kubernetes
+    config: Kubernetes,
+    pod_access: PodAccessGuard,
+    registry_fingerprint: Mutex<Option<String>>,
+}
+
+// Tracks missing pod RBAC so log collection is disabled once instead of failing every cycle
+#[derive(Default)]
+pub struct PodAccessGuard {
+    forbidden: AtomicBool,
 }
@@ -0,0 +1,48 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+// Connectors currently operated on, shared by every cycle so two overlapping
+// cycles never deploy, stop or remove the same connector at the same time
+static LOCKED_CONNECTORS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+// Held while a cycle operates on a connector, released on drop
+#[derive(Debug)]
+pub struct ConnectorLock {
+    connector_id: String,
+}
+
+impl ConnectorLock {
+    // None if the connector is already locked by another cycle
+    pub fn try_acquire(connector_id: &str) -> Option<Self> {
+        let mut locked = LOCKED_CONNECTORS.lock().unwrap();
+        locked.insert(connector_id.to_string()).then(|| Self {
+            connector_id: connector_id.to_string(),
+        })
+    }
+}
+
+impl Drop for ConnectorLock {
+    fn drop(&mut self) {
+        LOCKED_CONNECTORS.lock().unwrap().remove(&self.connector_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connector_cannot_be_locked_twice() {
+        let lock = ConnectorLock::try_acquire("lock-test-1");
+        assert!(lock.is_some());
+        assert!(ConnectorLock::try_acquire("lock-test-1").is_none());
+        // Other connectors are not affected
+        assert!(ConnectorLock::try_acquire("lock-test-2").is_some());
+    }
+
+    #[test]
+    fn lock_is_released_on_drop() {
+        drop(ConnectorLock::try_acquire("lock-test-3"));
+        assert!(ConnectorLock::try_acquire("lock-test-3").is_some());
+    }
+}
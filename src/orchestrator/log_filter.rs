@@ -0,0 +1,134 @@
+use crate::config::settings::LogLevel;
+
+const JSON_LEVEL_KEYS: [&str; 4] = ["level", "levelname", "severity", "log.level"];
+
+fn parse_level(value: &str) -> Option<LogLevel> {
+    match value.to_lowercase().as_str() {
+        "trace" => Some(LogLevel::Trace),
+        "debug" => Some(LogLevel::Debug),
+        "info" => Some(LogLevel::Info),
+        "warn" | "warning" => Some(LogLevel::Warn),
+        "error" | "err" | "critical" | "fatal" | "panic" => Some(LogLevel::Error),
+        _ => None,
+    }
+}
+
+fn json_level(line: &str) -> Option<LogLevel> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    JSON_LEVEL_KEYS
+        .iter()
+        .find_map(|key| value.get(key).and_then(|level| level.as_str()))
+        .and_then(parse_level)
+}
+
+// Heuristic level detection: JSON level fields, logfmt level=xxx or an uppercase level word
+pub fn detect_level(line: &str) -> Option<LogLevel> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('{') {
+        if let Some(level) = json_level(trimmed) {
+            return Some(level);
+        }
+    }
+    let logfmt_level = trimmed
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("level="))
+        .and_then(|value| parse_level(value.trim_matches('"')));
+    if logfmt_level.is_some() {
+        return logfmt_level;
+    }
+    trimmed
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.len() > 2 && word.chars().all(|c| c.is_ascii_uppercase()))
+        .find_map(parse_level)
+}
+
+// Keep the lines at or above the minimum level, lines without a detectable
+// level (stack traces, multi lines messages) follow the previous line
+pub fn filter_logs<T: AsRef<str>>(logs: Vec<T>, min_level: LogLevel) -> Vec<T> {
+    let mut current_level: Option<LogLevel> = None;
+    logs.into_iter()
+        .filter(|line| {
+            if let Some(level) = detect_level(line.as_ref()) {
+                current_level = Some(level);
+            }
+            current_level.is_none_or(|level| level >= min_level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_level_from_common_formats() {
+        let cases = [
+            (
+                "2025-01-01 10:00:00 INFO Connector started",
+                Some(LogLevel::Info),
+            ),
+            ("[WARNING] Rate limited, retrying", Some(LogLevel::Warn)),
+            ("ts=1 level=debug msg=\"polling\"", Some(LogLevel::Debug)),
+            (
+                r#"{"levelname": "ERROR", "message": "boom"}"#,
+                Some(LogLevel::Error),
+            ),
+            (r#"{"level": "warn", "msg": "slow"}"#, Some(LogLevel::Warn)),
+            ("CRITICAL: queue unreachable", Some(LogLevel::Error)),
+            ("an error occurred while processing", None),
+            ("  File \"main.py\", line 12, in run", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(detect_level(line), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn filters_lines_below_minimum_level() {
+        let logs = lines(&[
+            "DEBUG fetching page 1",
+            "INFO 12 objects imported",
+            "WARN rate limited",
+            r#"{"level": "info", "msg": "sleeping"}"#,
+            r#"{"level": "error", "msg": "failed"}"#,
+        ]);
+        assert_eq!(
+            filter_logs(logs, LogLevel::Warn),
+            lines(&[
+                "WARN rate limited",
+                r#"{"level": "error", "msg": "failed"}"#
+            ])
+        );
+    }
+
+    #[test]
+    fn lines_without_level_follow_previous_line() {
+        let logs = lines(&[
+            "starting connector",
+            "INFO processing bundle",
+            "  bundle size: 12",
+            "ERROR Traceback (most recent call last):",
+            "  File \"main.py\", line 12, in run",
+            "ValueError: invalid bundle",
+        ]);
+        assert_eq!(
+            filter_logs(logs, LogLevel::Error),
+            lines(&[
+                "starting connector",
+                "ERROR Traceback (most recent call last):",
+                "  File \"main.py\", line 12, in run",
+                "ValueError: invalid bundle",
+            ])
+        );
+    }
+
+    #[test]
+    fn minimum_trace_level_keeps_everything() {
+        let logs = lines(&["DEBUG a", "INFO b", "no level", "ERROR c"]);
+        assert_eq!(filter_logs(logs.clone(), LogLevel::Trace), logs);
+    }
+}
@@ -0,0 +1,122 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tracing::error;
+
+const TIMESTAMP_GROUP: &str = "timestamp";
+const LEVEL_GROUP: &str = "level";
+const MESSAGE_GROUP: &str = "message";
+
+// Log patterns compiled once, then shared by every connector and schedule using them
+static COMPILED_PATTERNS: Mutex<BTreeMap<String, Regex>> = Mutex::new(BTreeMap::new());
+
+fn compiled_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut compiled = COMPILED_PATTERNS.lock().unwrap();
+    if let Some(regex) = compiled.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)?;
+    compiled.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+#[derive(Serialize)]
+struct ParsedLine<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<&'a str>,
+    message: &'a str,
+}
+
+// Normalized JSON line, None if the line does not match
+fn parse_line(regex: &Regex, line: &str) -> Option<String> {
+    let captures = regex.captures(line.trim_end())?;
+    let group = |name: &str| captures.name(name).map(|value| value.as_str());
+    let parsed = ParsedLine {
+        timestamp: group(TIMESTAMP_GROUP),
+        level: group(LEVEL_GROUP),
+        message: group(MESSAGE_GROUP).unwrap_or(&captures[0]),
+    };
+    serde_json::to_string(&parsed).ok()
+}
+
+// Apply the connector log pattern, lines not matching it are kept raw
+pub fn parse_logs(logs: Vec<String>, pattern: &str, connector_id: &str) -> Vec<String> {
+    let regex = match compiled_pattern(pattern) {
+        Ok(regex) => regex,
+        Err(err) => {
+            error!(
+                id = connector_id,
+                error = err.to_string(),
+                "Invalid connector log pattern, forwarding raw logs"
+            );
+            return logs;
+        }
+    };
+    logs.into_iter()
+        .map(|line| parse_line(&regex, &line).unwrap_or(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERN: &str =
+        r"^(?P<timestamp>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}) (?P<level>[A-Z]+) (?P<message>.*)$";
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn matching_lines_are_normalized() {
+        let parsed = parse_logs(
+            lines(&["2025-01-01 10:00:00 INFO Connector \"misp\" started\n"]),
+            PATTERN,
+            "connector-1",
+        );
+
+        let record: serde_json::Value = serde_json::from_str(&parsed[0]).unwrap();
+        assert_eq!(record["timestamp"], "2025-01-01 10:00:00");
+        assert_eq!(record["level"], "INFO");
+        assert_eq!(record["message"], "Connector \"misp\" started");
+    }
+
+    #[test]
+    fn patterns_are_compiled_once() {
+        let first = compiled_pattern(PATTERN).unwrap();
+        assert!(COMPILED_PATTERNS.lock().unwrap().contains_key(PATTERN));
+        let second = compiled_pattern(PATTERN).unwrap();
+        assert_eq!(first.as_str(), second.as_str());
+        assert!(compiled_pattern("(unclosed").is_err());
+        assert!(!COMPILED_PATTERNS.lock().unwrap().contains_key("(unclosed"));
+    }
+
+    #[test]
+    fn non_matching_lines_are_kept_raw() {
+        let logs = lines(&[
+            "2025-01-01 10:00:00 ERROR Traceback (most recent call last):",
+            "  File \"main.py\", line 12, in run",
+        ]);
+
+        let parsed = parse_logs(logs, PATTERN, "connector-1");
+
+        assert!(parsed[0].starts_with('{'));
+        assert_eq!(parsed[1], "  File \"main.py\", line 12, in run");
+    }
+
+    #[test]
+    fn pattern_without_groups_keeps_the_whole_match_as_message() {
+        let parsed = parse_logs(lines(&["heartbeat"]), "^heartbeat$", "connector-1");
+        assert_eq!(parsed, lines(&[r#"{"message":"heartbeat"}"#]));
+    }
+
+    #[test]
+    fn invalid_pattern_forwards_raw_logs() {
+        let logs = lines(&["2025-01-01 10:00:00 INFO started"]);
+        assert_eq!(parse_logs(logs.clone(), "(?P<level", "connector-1"), logs);
+    }
+}
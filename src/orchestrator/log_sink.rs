@@ -0,0 +1,196 @@
+use crate::config::settings::LogSink;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use tracing::error;
+
+#[derive(Serialize)]
+struct LogRecord<'a> {
+    platform: &'a str,
+    connector_id: &'a str,
+    message: &'a str,
+}
+
+// One JSON object per line, ready to be picked up by a log shipper
+fn write_logs(
+    writer: &mut impl Write,
+    platform: &str,
+    connector_id: &str,
+    logs: &[String],
+) -> io::Result<()> {
+    for line in logs {
+        let record = LogRecord {
+            platform,
+            connector_id,
+            message: line.trim_end(),
+        };
+        serde_json::to_writer(&mut *writer, &record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+fn append_logs(path: &str, platform: &str, connector_id: &str, logs: &[String]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    write_logs(&mut file, platform, connector_id, logs)
+}
+
+// Last batch copied to the local sink per connector, the next fetched tail overlaps it
+#[derive(Debug, Default)]
+pub struct ForwardedLogs {
+    last_batches: HashMap<String, Vec<String>>,
+}
+
+impl ForwardedLogs {
+    // Lines of the batch not copied yet, the batch becomes the reference of the next one
+    pub fn unseen(&mut self, connector_id: &str, logs: &[String]) -> Vec<String> {
+        let previous = self
+            .last_batches
+            .get(connector_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let overlap = (0..=previous.len().min(logs.len()))
+            .rev()
+            .find(|&length| previous[previous.len() - length..] == logs[..length])
+            .unwrap_or(0);
+        let unseen = logs[overlap..].to_vec();
+        self.last_batches
+            .insert(connector_id.to_string(), logs.to_vec());
+        unseen
+    }
+
+    pub fn retain(&mut self, is_managed: impl Fn(&str) -> bool) {
+        self.last_batches
+            .retain(|connector_id, _| is_managed(connector_id));
+    }
+}
+
+// Copy the connector logs to the local sink, reporting to the platform is left to the caller
+pub async fn forward_logs(sink: &LogSink, platform: &str, connector_id: &str, logs: &[String]) {
+    let path = match sink {
+        LogSink::Opencti => return,
+        LogSink::Stdout | LogSink::Both => None,
+        LogSink::File(path) => Some(path.clone()),
+    };
+    let (platform_name, id, lines) = (
+        platform.to_string(),
+        connector_id.to_string(),
+        logs.to_vec(),
+    );
+    // Stdout and file writes block, keep them off the orchestration tasks
+    let result = tokio::task::spawn_blocking(move || match path {
+        Some(path) => append_logs(&path, &platform_name, &id, &lines),
+        None => write_logs(&mut io::stdout().lock(), &platform_name, &id, &lines),
+    })
+    .await
+    .unwrap_or_else(|err| Err(io::Error::other(err)));
+    if let Err(err) = result {
+        error!(
+            id = connector_id,
+            sink = String::from(sink.clone()),
+            error = err.to_string(),
+            "Unable to forward connector logs"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn logs_are_written_as_json_lines() {
+        let mut output = Vec::new();
+        write_logs(
+            &mut output,
+            "opencti",
+            "connector-1",
+            &lines(&["INFO started\n", "ERROR \"quoted\" failure"]),
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["platform"], "opencti");
+        assert_eq!(records[0]["connector_id"], "connector-1");
+        assert_eq!(records[0]["message"], "INFO started");
+        assert_eq!(records[1]["message"], "ERROR \"quoted\" failure");
+    }
+
+    #[test]
+    fn only_new_lines_of_an_overlapping_tail_are_forwarded() {
+        let mut forwarded = ForwardedLogs::default();
+        assert_eq!(
+            forwarded.unseen("A", &lines(&["one", "two", "three"])),
+            lines(&["one", "two", "three"])
+        );
+        assert_eq!(
+            forwarded.unseen("A", &lines(&["two", "three", "four"])),
+            lines(&["four"])
+        );
+        assert!(
+            forwarded
+                .unseen("A", &lines(&["two", "three", "four"]))
+                .is_empty()
+        );
+        // No overlap, everything fetched is new
+        assert_eq!(
+            forwarded.unseen("A", &lines(&["five", "six"])),
+            lines(&["five", "six"])
+        );
+        // Each connector has its own reference batch
+        assert_eq!(forwarded.unseen("B", &lines(&["six"])), lines(&["six"]));
+
+        forwarded.retain(|connector_id| connector_id == "B");
+        assert_eq!(forwarded.unseen("A", &lines(&["six"])), lines(&["six"]));
+    }
+
+    #[tokio::test]
+    async fn file_sink_appends_logs() {
+        let path = std::env::temp_dir().join(format!("xtm-composer-logs-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let sink = LogSink::File(path.to_string_lossy().to_string());
+
+        forward_logs(&sink, "opencti", "connector-1", &lines(&["first"])).await;
+        forward_logs(&sink, "openaev", "connector-2", &lines(&["second"])).await;
+
+        let content = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let records: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["message"], "first");
+        assert_eq!(records[1]["connector_id"], "connector-2");
+        assert_eq!(records[1]["platform"], "openaev");
+    }
+
+    #[test]
+    fn sink_parsing_and_platform_reporting() {
+        let sink = |value: &str| LogSink::try_from(value.to_string());
+        assert_eq!(sink("opencti"), Ok(LogSink::Opencti));
+        assert_eq!(sink("stdout"), Ok(LogSink::Stdout));
+        assert_eq!(
+            sink("file:/var/log/connectors.log"),
+            Ok(LogSink::File("/var/log/connectors.log".to_string()))
+        );
+        assert!(sink("file:").is_err());
+        assert!(sink("syslog").is_err());
+
+        assert!(LogSink::Opencti.reports_to_platform());
+        assert!(LogSink::Both.reports_to_platform());
+        assert!(!LogSink::Stdout.reports_to_platform());
+        assert!(!LogSink::File("logs".to_string()).reports_to_platform());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// FNV-1a, stable across runs so a connector keeps its position in the schedule after a restart
+fn connector_hash(connector_id: &str) -> u64 {
+    connector_id.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Offset of the connector within the schedule window, always below the schedule
+pub fn schedule_offset(connector_id: &str, schedule: Duration) -> Duration {
+    if schedule.is_zero() {
+        return Duration::ZERO;
+    }
+    let nanos = connector_hash(connector_id) as u128 % schedule.as_nanos();
+    Duration::from_nanos(nanos as u64)
+}
+
+// Log post timer of each connector, optionally offset so the posts are spread over the schedule
+#[derive(Debug)]
+pub struct LogTimers {
+    anchor: Instant,
+    spread: bool,
+    posted_slots: HashMap<String, u128>,
+}
+
+impl Default for LogTimers {
+    fn default() -> Self {
+        Self::new(Instant::now(), false)
+    }
+}
+
+impl LogTimers {
+    pub fn new(anchor: Instant, spread: bool) -> Self {
+        Self {
+            anchor,
+            spread,
+            posted_slots: HashMap::new(),
+        }
+    }
+
+    // True once per schedule window of the connector, the first one ending a schedule after the anchor
+    pub fn poll(&mut self, connector_id: &str, schedule: Duration, now: Instant) -> bool {
+        if schedule.is_zero() {
+            return true;
+        }
+        let offset = if self.spread {
+            schedule_offset(connector_id, schedule)
+        } else {
+            Duration::ZERO
+        };
+        let Some(elapsed) = now.checked_duration_since(self.anchor + offset) else {
+            return false;
+        };
+        let slot = elapsed.as_nanos() / schedule.as_nanos();
+        let posted = self.posted_slots.get(connector_id).copied().unwrap_or(0);
+        if slot <= posted {
+            return false;
+        }
+        self.posted_slots.insert(connector_id.to_string(), slot);
+        true
+    }
+
+    // Connectors gone from the platform listing, a returning one starts over and posts on its next poll
+    pub fn retain(&mut self, is_managed: impl Fn(&str) -> bool) {
+        self.posted_slots
+            .retain(|connector_id, _| is_managed(connector_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_differ_per_connector_within_the_schedule() {
+        let schedule = Duration::from_secs(300);
+        let offsets: Vec<Duration> = (0..20)
+            .map(|index| schedule_offset(&format!("connector-{index}"), schedule))
+            .collect();
+
+        assert!(offsets.iter().all(|offset| *offset < schedule));
+        let mut distinct = offsets.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), offsets.len());
+        // Deterministic for a given connector
+        assert_eq!(schedule_offset("connector-0", schedule), offsets[0]);
+        assert_eq!(
+            schedule_offset("connector-0", Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn connectors_post_once_per_schedule_at_their_offset() {
+        let schedule = Duration::from_secs(300);
+        let anchor = Instant::now();
+        let mut timers = LogTimers::new(anchor, true);
+        let offset = schedule_offset("A", schedule);
+
+        assert!(!timers.poll("A", schedule, anchor + offset));
+        assert!(!timers.poll(
+            "A",
+            schedule,
+            anchor + schedule + offset - Duration::from_secs(1)
+        ));
+        assert!(timers.poll("A", schedule, anchor + schedule + offset));
+        // Once per window
+        assert!(!timers.poll(
+            "A",
+            schedule,
+            anchor + schedule + offset + Duration::from_secs(1)
+        ));
+        assert!(timers.poll("A", schedule, anchor + schedule * 2 + offset));
+
+        timers.retain(|connector_id| connector_id != "A");
+        assert!(timers.posted_slots.is_empty());
+    }
+
+    #[test]
+    fn connectors_are_timed_independently() {
+        let schedule = Duration::from_secs(300);
+        let anchor = Instant::now();
+        let mut timers = LogTimers::new(anchor, false);
+        let now = anchor + schedule;
+
+        // A post for one connector no longer delays the others of the cycle
+        assert!(timers.poll("A", schedule, now));
+        assert!(timers.poll("B", schedule, now));
+        assert!(!timers.poll("A", schedule, now));
+    }
+}
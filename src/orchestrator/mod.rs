@@ -2,17 +2,29 @@ use crate::api::{ApiConnector, ConnectorStatus};
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 use tracing::error;
 
+pub mod backoff;
 pub mod composer;
+pub mod cooldown;
 pub mod docker;
+pub mod hooks;
 pub mod image;
 pub mod kubernetes;
+pub mod locks;
+pub mod log_filter;
+pub mod log_parser;
+pub mod log_timers;
+pub mod log_sink;
+pub mod plan;
 pub mod portainer;
+pub mod stopping;
 pub mod swarm;
+pub mod timeout;
+pub mod transitions;
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all(deserialize = "PascalCase"))]
@@ -35,8 +47,43 @@ impl OrchestratorContainer {
         self.labels.get("opencti-connector-id").unwrap().clone()
     }
 
-    pub fn extract_opencti_hash(&self) -> &String {
-        self.envs.get("OPENCTI_CONFIG_HASH").unwrap()
+    // Legacy containers deployed before the platform label are claimed by every platform,
+    // or by none when the platforms are exclusive
+    pub fn belongs_to_platform(&self, platform: &str, exclusive: bool) -> bool {
+        match self.labels.get(PLATFORM_LABEL) {
+            Some(value) => value == platform,
+            None => !exclusive,
+        }
+    }
+
+    // Containers deployed before the owner label, or by hand with a copied manager label, are not
+    // considered owned and are never removed when the owner label is required
+    pub fn is_composer_owned(&self) -> bool {
+        self.labels
+            .get(OWNER_LABEL)
+            .is_some_and(|value| value == OWNER_VALUE)
+    }
+
+    // None for containers listed without envs and deployed before the hash labels
+    pub fn extract_opencti_hash(&self) -> Option<&String> {
+        // Label fallback for connectors deployed without the hash env var or listed without envs
+        self.envs
+            .get("OPENCTI_CONFIG_HASH")
+            .or_else(|| self.labels.get(CONTRACT_HASH_LABEL))
+            .or_else(|| self.labels.get(CONFIG_HASH_LABEL))
+    }
+
+    // Contract keys recorded at deploy time, Kubernetes keeps them as an annotation read along the envs
+    pub fn extract_contract_keys(&self) -> Option<HashSet<&str>> {
+        self.labels
+            .get(CONTRACT_KEYS_LABEL)
+            .or_else(|| self.envs.get(CONTRACT_KEYS_LABEL))
+            .map(|keys| keys.split(',').filter(|key| !key.is_empty()).collect())
+    }
+
+    pub fn uptime(&self) -> Option<std::time::Duration> {
+        let started_at = DateTime::parse_from_rfc3339(self.started_at.as_ref()?).ok()?;
+        (Utc::now() - started_at.with_timezone(&Utc)).to_std().ok()
     }
 
     pub fn is_in_reboot_loop(&self) -> bool {
@@ -52,14 +99,205 @@ impl OrchestratorContainer {
     }
 }
 
+pub const PLATFORM_LABEL: &str = "opencti-platform";
+pub const REQUESTED_STATUS_LABEL: &str = "opencti-requested-status";
+pub const CONFIG_HASH_LABEL: &str = "opencti-config-hash";
+pub const LAST_MODIFIED_BY_LABEL: &str = "opencti-last-modified-by";
+pub const OWNER_LABEL: &str = "opencti-owner";
+pub const OWNER_VALUE: &str = "xtm-composer";
+pub const CONTRACT_HASH_LABEL: &str = "opencti-contract-hash";
+pub const CONTRACT_KEYS_LABEL: &str = "opencti-contract-keys";
+
+pub fn build_requested_status_label(
+    enabled: bool,
+    connector: &ApiConnector,
+) -> Option<(String, String)> {
+    enabled.then(|| {
+        (
+            REQUESTED_STATUS_LABEL.to_string(),
+            connector.requested_status.clone(),
+        )
+    })
+}
+
+pub fn build_last_modified_by_label(enabled: bool, manager_id: &str) -> Option<(String, String)> {
+    enabled.then(|| (LAST_MODIFIED_BY_LABEL.to_string(), manager_id.to_string()))
+}
+
+pub fn build_config_hash_label(connector: &ApiConnector) -> (String, String) {
+    (CONFIG_HASH_LABEL.to_string(), connector.contract_hash.clone())
+}
+
+// Status of a state the orchestrator mapping does not know, stopped unless kept distinct
+pub fn unrecognized_state_status(distinct_unknown: bool) -> ConnectorStatus {
+    if distinct_unknown {
+        ConnectorStatus::Unknown
+    } else {
+        ConnectorStatus::Stopped
+    }
+}
+
+// Set on every deploy, marks the containers created by a composer (never part of a selector)
+pub fn build_owner_label() -> (String, String) {
+    (OWNER_LABEL.to_string(), OWNER_VALUE.to_string())
+}
+
+pub fn build_contract_hash_label(connector: &ApiConnector) -> (String, String) {
+    (
+        CONTRACT_HASH_LABEL.to_string(),
+        connector.contract_hash.clone(),
+    )
+}
+
+// Configuration keys of the deployed contract, the baseline of the removed keys on the next refresh
+pub fn build_contract_keys_label(connector: &ApiConnector) -> (String, String) {
+    let mut keys: Vec<&str> = connector
+        .contract_configuration
+        .iter()
+        .map(|config| config.key.as_str())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    (CONTRACT_KEYS_LABEL.to_string(), keys.join(","))
+}
+
+// Kubernetes label values: at most 63 alphanumeric, '-', '_' or '.' chars, alphanumeric at both ends
+pub fn is_valid_label_value(value: &str) -> bool {
+    value.len() <= 63
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && value
+            .chars()
+            .next()
+            .is_none_or(|c| c.is_ascii_alphanumeric())
+        && value
+            .chars()
+            .last()
+            .is_none_or(|c| c.is_ascii_alphanumeric())
+}
+
+// Closest valid label value: invalid chars replaced by '-', truncated to 63 chars
+pub fn sanitize_label_value(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(63)
+        .collect();
+    value
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+// Kubernetes label keys: an optional DNS subdomain prefix and a non empty name following the value rules
+pub fn is_valid_label_key(key: &str) -> bool {
+    let (prefix, name) = match key.split_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    let is_dns_label = |part: &str| {
+        !part.is_empty()
+            && part.len() <= 63
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !part.starts_with('-')
+            && !part.ends_with('-')
+    };
+    !name.is_empty()
+        && is_valid_label_value(name)
+        && prefix.is_none_or(|prefix| prefix.len() <= 253 && prefix.split('.').all(is_dns_label))
+}
+
 pub fn build_labels(manager_id: &str, connector: &ApiConnector) -> HashMap<String, String> {
     let mut labels: HashMap<String, String> = HashMap::new();
     labels.insert("opencti-manager".into(), manager_id.to_string());
     labels.insert("opencti-connector-id".into(), connector.id.clone());
-    labels.insert("opencti-platform".into(), connector.platform.clone());
+    labels.insert(PLATFORM_LABEL.into(), connector.platform.clone());
     labels
 }
 
+// Per connector override first, then the orchestrator option, then the container name
+fn resolve_hostname(
+    connector: &ApiConnector,
+    container_name: String,
+    overrides: Option<&HashMap<String, String>>,
+    configured: Option<&String>,
+) -> String {
+    if let Some(hostname) = overrides.and_then(|overrides| overrides.get(&connector.id)) {
+        return hostname.clone();
+    }
+    if let Some(hostname) = configured {
+        return hostname.clone();
+    }
+    // Hostnames are limited to a 63 chars DNS label
+    let hostname: String = container_name.chars().take(63).collect();
+    hostname.trim_end_matches('-').to_string()
+}
+
+pub fn container_hostname(connector: &ApiConnector, configured: Option<&String>) -> String {
+    let overrides = crate::config::overrides::current();
+    let hostnames = overrides.connector_hostnames.as_ref();
+    resolve_hostname(connector, connector.container_name(), hostnames, configured)
+}
+
+// Keep the containers managed by the given platform orchestration
+pub fn filter_platform_containers(
+    containers: Vec<OrchestratorContainer>,
+    platform: &str,
+    exclusive: bool,
+) -> Vec<OrchestratorContainer> {
+    containers
+        .into_iter()
+        .filter(|container| container.belongs_to_platform(platform, exclusive))
+        .collect()
+}
+
+// Stream tags of the split Docker and Swarm logs
+pub const STDOUT_TAG: &str = "[stdout] ";
+pub const STDERR_TAG: &str = "[stderr] ";
+
+// Connector log line, its stream tag kept aside while the line is parsed and filtered
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamLine {
+    pub tag: Option<&'static str>,
+    pub line: String,
+}
+
+impl StreamLine {
+    pub fn split(line: String) -> Self {
+        let tag = [STDOUT_TAG, STDERR_TAG]
+            .into_iter()
+            .find(|tag| line.starts_with(tag));
+        match tag {
+            Some(tag) => Self {
+                tag: Some(tag),
+                line: line[tag.len()..].to_string(),
+            },
+            None => Self { tag: None, line },
+        }
+    }
+
+    pub fn joined(self) -> String {
+        match self.tag {
+            Some(tag) => format!("{tag}{}", self.line),
+            None => self.line,
+        }
+    }
+}
+
+impl AsRef<str> for StreamLine {
+    fn as_ref(&self) -> &str {
+        &self.line
+    }
+}
+
 pub fn ensure_proxy_ca_file(connector: &ApiConnector) -> Option<String> {
     let cert_content = connector.proxy_ca_bundle()?;
 
@@ -100,18 +338,58 @@ pub trait Orchestrator {
         build_labels(&crate::settings().manager.id, connector)
     }
 
+    // Audit label kept out of labels() as it changes over time (never part of a selector)
+    fn requested_status_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        build_requested_status_label(crate::settings().manager.label_requested_status, connector)
+    }
+
+    // Manager behind the last operation on the container, for multi composer setups
+    fn last_modified_by_label(&self) -> Option<(String, String)> {
+        let manager = &crate::settings().manager;
+        build_last_modified_by_label(manager.label_last_modified_by, &manager.id)
+    }
+
+    // Hash label is only needed when the env var is not injected (Kubernetes keeps an annotation)
+    fn config_hash_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        (!crate::settings().manager.inject_config_hash).then(|| build_config_hash_label(connector))
+    }
+
+    // Always set, lets operators find outdated containers (docker ps --filter, kubectl get -l)
+    fn contract_hash_label(&self, connector: &ApiConnector) -> (String, String) {
+        build_contract_hash_label(connector)
+    }
+
     async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer>;
 
+    // Backend reachability, checked before each orchestration cycle
+    async fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
     async fn list(&self) -> Vec<OrchestratorContainer>;
 
     async fn start(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> ();
 
     async fn stop(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> ();
 
+    // Non destructive suspend, backends without a native pause stop the connector (scale to 0)
+    async fn pause(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.stop(container, connector).await
+    }
+
+    async fn resume(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.start(container, connector).await
+    }
+
     async fn remove(&self, container: &OrchestratorContainer) -> ();
 
     async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer>;
 
+    // Fields of an up to date deployment changed outside of the composer, to be refreshed back
+    async fn spec_drift(&self, _connector: &ApiConnector) -> Vec<String> {
+        Vec::new()
+    }
+
     async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer>;
 
     async fn logs(
@@ -120,6 +398,11 @@ pub trait Orchestrator {
         connector: &ApiConnector,
     ) -> Option<Vec<String>>;
 
+    // Last logs of a container about to be removed, its connector may be gone from the platform
+    async fn removal_logs(&self, _container: &OrchestratorContainer) -> Option<Vec<String>> {
+        None
+    }
+
     fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus;
 }
 
@@ -130,16 +413,7 @@ mod tests {
 
     #[test]
     fn labels_include_platform_discriminator() {
-        let connector = ApiConnector {
-            id: "connector-1".to_string(),
-            platform: "opencti".to_string(),
-            name: String::new(),
-            image: String::new(),
-            contract_hash: String::new(),
-            current_status: None,
-            requested_status: String::new(),
-            contract_configuration: vec![],
-        };
+        let connector = ApiConnector::for_test("connector-1");
 
         let labels = build_labels("test-manager", &connector);
 
@@ -148,6 +422,316 @@ mod tests {
         assert_eq!(labels.get("opencti-manager"), Some(&"test-manager".to_string()));
     }
 
+    #[test]
+    fn platform_labels_are_set_per_platform() {
+        for platform in ["opencti", "openaev"] {
+            let connector = ApiConnector {
+                platform: platform.to_string(),
+                ..ApiConnector::for_test(&format!("{platform}-connector"))
+            };
+
+            let labels = build_labels("test-manager", &connector);
+
+            assert_eq!(labels.get(PLATFORM_LABEL), Some(&platform.to_string()));
+        }
+    }
+
+    #[test]
+    fn filter_platform_containers_keeps_own_and_legacy_containers() {
+        let platform_container = |platform: Option<&str>, id: &str| {
+            let mut labels = HashMap::from([("opencti-connector-id".to_string(), id.to_string())]);
+            if let Some(platform) = platform {
+                labels.insert(PLATFORM_LABEL.to_string(), platform.to_string());
+            }
+            container_with(HashMap::new(), labels)
+        };
+        let containers = vec![
+            platform_container(Some("opencti"), "cti"),
+            platform_container(Some("openaev"), "aev"),
+            platform_container(None, "legacy"),
+        ];
+
+        let ids = |platform: &str, exclusive: bool| -> Vec<String> {
+            filter_platform_containers(containers.clone(), platform, exclusive)
+                .iter()
+                .map(|container| container.extract_opencti_id())
+                .collect()
+        };
+
+        assert_eq!(
+            ids("opencti", false),
+            vec!["cti".to_string(), "legacy".to_string()]
+        );
+        assert_eq!(
+            ids("openaev", false),
+            vec!["aev".to_string(), "legacy".to_string()]
+        );
+        // Exclusive platforms leave the unlabelled containers alone
+        assert_eq!(ids("opencti", true), vec!["cti".to_string()]);
+        assert_eq!(ids("openaev", true), vec!["aev".to_string()]);
+    }
+
+    fn container_with(
+        envs: HashMap<String, String>,
+        labels: HashMap<String, String>,
+    ) -> OrchestratorContainer {
+        OrchestratorContainer {
+            id: "container-1".to_string(),
+            name: "connector-1".to_string(),
+            state: "running".to_string(),
+            labels,
+            envs,
+            restart_count: 0,
+            started_at: None,
+        }
+    }
+
+    #[test]
+    fn contract_keys_are_recorded_and_read_back() {
+        let mut connector = ApiConnector::for_test("connector-1");
+        for key in ["MISP_URL", "MISP_KEY", "MISP_URL"] {
+            connector
+                .contract_configuration
+                .push(crate::api::ApiContractConfig {
+                    key: key.to_string(),
+                    value: String::new(),
+                    is_sensitive: false,
+                });
+        }
+        let (key, value) = build_contract_keys_label(&connector);
+        assert_eq!(value, "MISP_KEY,MISP_URL");
+
+        let expected = HashSet::from(["MISP_KEY", "MISP_URL"]);
+        // Docker label
+        let labelled = container_with(
+            HashMap::new(),
+            HashMap::from([(key.clone(), value.clone())]),
+        );
+        assert_eq!(labelled.extract_contract_keys(), Some(expected.clone()));
+        // Kubernetes annotation, merged into the envs
+        let annotated = container_with(HashMap::from([(key, value)]), HashMap::new());
+        assert_eq!(annotated.extract_contract_keys(), Some(expected));
+        // Deployed before the label
+        let legacy = container_with(HashMap::new(), HashMap::new());
+        assert_eq!(legacy.extract_contract_keys(), None);
+    }
+
+    #[test]
+    fn extract_opencti_hash_reads_env_var() {
+        let container = container_with(
+            HashMap::from([("OPENCTI_CONFIG_HASH".to_string(), "hash-env".to_string())]),
+            HashMap::from([(CONFIG_HASH_LABEL.to_string(), "hash-label".to_string())]),
+        );
+        assert_eq!(container.extract_opencti_hash().unwrap(), "hash-env");
+    }
+
+    #[test]
+    fn extract_opencti_hash_falls_back_to_label() {
+        let container = container_with(
+            HashMap::new(),
+            HashMap::from([(CONFIG_HASH_LABEL.to_string(), "hash-label".to_string())]),
+        );
+        assert_eq!(container.extract_opencti_hash().unwrap(), "hash-label");
+    }
+
+    #[test]
+    fn extract_opencti_hash_reads_contract_hash_label() {
+        // list() does not populate the envs
+        let container = container_with(
+            HashMap::new(),
+            HashMap::from([
+                (CONTRACT_HASH_LABEL.to_string(), "hash-contract".to_string()),
+                (CONFIG_HASH_LABEL.to_string(), "hash-legacy".to_string()),
+            ]),
+        );
+        assert_eq!(container.extract_opencti_hash().unwrap(), "hash-contract");
+    }
+
+    #[test]
+    fn extract_opencti_hash_is_none_on_list_derived_container() {
+        // Docker list() only keeps the labels, a legacy container has no hash label
+        let container = container_with(
+            HashMap::new(),
+            HashMap::from([(
+                "opencti-connector-id".to_string(),
+                "connector-1".to_string(),
+            )]),
+        );
+        assert_eq!(container.extract_opencti_hash(), None);
+        assert_eq!(container.extract_opencti_id(), "connector-1");
+    }
+
+    #[test]
+    fn contract_hash_label_carries_connector_hash() {
+        let connector = ApiConnector {
+            contract_hash: "a1b2c3".to_string(),
+            ..ApiConnector::for_test("connector-1")
+        };
+        assert_eq!(
+            build_contract_hash_label(&connector),
+            ("opencti-contract-hash".to_string(), "a1b2c3".to_string())
+        );
+    }
+
+    #[test]
+    fn hostname_defaults_to_container_name() {
+        let connector = ApiConnector::for_test("connector-1");
+        assert_eq!(
+            resolve_hostname(&connector, "misp".to_string(), None, None),
+            "misp"
+        );
+        // Long names are cut to a valid DNS label
+        let long_name = format!("{}-{}", "a".repeat(62), "suffix");
+        assert_eq!(
+            resolve_hostname(&connector, long_name, None, None),
+            "a".repeat(62)
+        );
+    }
+
+    #[test]
+    fn hostname_prefers_connector_override_then_configured_value() {
+        let overrides = HashMap::from([("connector-1".to_string(), "misp-primary".to_string())]);
+        let configured = "connectors".to_string();
+
+        let overridden = ApiConnector::for_test("connector-1");
+        assert_eq!(
+            resolve_hostname(
+                &overridden,
+                "misp".to_string(),
+                Some(&overrides),
+                Some(&configured)
+            ),
+            "misp-primary"
+        );
+        let other = ApiConnector::for_test("connector-2");
+        assert_eq!(
+            resolve_hostname(
+                &other,
+                "misp".to_string(),
+                Some(&overrides),
+                Some(&configured)
+            ),
+            "connectors"
+        );
+    }
+
+    #[test]
+    fn label_value_validation_follows_kubernetes_rules() {
+        assert!(is_valid_label_value(""));
+        assert!(is_valid_label_value("0f3a9c"));
+        assert!(is_valid_label_value("hash_v1.2-rc"));
+        assert!(is_valid_label_value(&"a".repeat(63)));
+        assert!(!is_valid_label_value(&"a".repeat(64)));
+        assert!(!is_valid_label_value("-hash"));
+        assert!(!is_valid_label_value("hash."));
+        assert!(!is_valid_label_value("hash/with/slash"));
+    }
+
+    #[test]
+    fn label_value_sanitization_truncates_and_normalizes() {
+        assert_eq!(sanitize_label_value("Team Manager #1"), "Team-Manager--1");
+        assert_eq!(sanitize_label_value("-manager-"), "manager");
+        assert_eq!(sanitize_label_value(&"a".repeat(80)), "a".repeat(63));
+        // Truncation must not leave a trailing separator
+        let value = format!("{}-b", "a".repeat(62));
+        assert_eq!(sanitize_label_value(&value), "a".repeat(62));
+        assert!(is_valid_label_value(&sanitize_label_value("é/ü:*")));
+    }
+
+    #[test]
+    fn label_key_validation_follows_kubernetes_rules() {
+        assert!(is_valid_label_key("opencti-manager"));
+        assert!(is_valid_label_key("example.com/team"));
+        assert!(!is_valid_label_key(""));
+        assert!(!is_valid_label_key("example.com/"));
+        assert!(!is_valid_label_key("Example.com/team"));
+        assert!(!is_valid_label_key("team name"));
+        assert!(!is_valid_label_key(&"a".repeat(64)));
+        assert!(!is_valid_label_key("a/b/c"));
+    }
+
+    #[test]
+    fn requested_status_label_follows_connector_request() {
+        let mut connector = ApiConnector::for_test("connector-1");
+
+        let deployed = build_requested_status_label(true, &connector);
+        assert_eq!(
+            deployed,
+            Some((REQUESTED_STATUS_LABEL.to_string(), "starting".to_string()))
+        );
+
+        // A refresh re-derives the label from the latest request
+        connector.requested_status = "stopping".to_string();
+        let refreshed = build_requested_status_label(true, &connector);
+        assert_eq!(
+            refreshed,
+            Some((REQUESTED_STATUS_LABEL.to_string(), "stopping".to_string()))
+        );
+    }
+
+    #[test]
+    fn requested_status_label_absent_when_disabled() {
+        let connector = ApiConnector::for_test("connector-1");
+
+        assert_eq!(build_requested_status_label(false, &connector), None);
+    }
+
+    #[test]
+    fn only_containers_with_the_owner_label_are_composer_owned() {
+        let (owner_label, owner_value) = build_owner_label();
+        let owned = container_with(
+            HashMap::new(),
+            HashMap::from([(owner_label.clone(), owner_value)]),
+        );
+        assert!(owned.is_composer_owned());
+
+        let foreign = container_with(
+            HashMap::new(),
+            HashMap::from([(owner_label, "other-tool".to_string())]),
+        );
+        assert!(!foreign.is_composer_owned());
+        assert!(!container_with(HashMap::new(), HashMap::new()).is_composer_owned());
+    }
+
+    #[test]
+    fn last_modified_by_label_carries_manager_id() {
+        assert_eq!(
+            build_last_modified_by_label(true, "composer-eu"),
+            Some((
+                LAST_MODIFIED_BY_LABEL.to_string(),
+                "composer-eu".to_string()
+            ))
+        );
+        assert_eq!(build_last_modified_by_label(false, "composer-eu"), None);
+    }
+
+    #[test]
+    fn refresh_patch_keeps_requested_status_annotation() {
+        use k8s_openapi::api::apps::v1::Deployment;
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+        use std::collections::BTreeMap;
+
+        let deployment = Deployment {
+            metadata: ObjectMeta {
+                annotations: Some(BTreeMap::from([(
+                    REQUESTED_STATUS_LABEL.to_string(),
+                    "stopping".to_string(),
+                )])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let patch = KubeOrchestrator::build_refresh_patch(&deployment);
+        assert_eq!(
+            patch
+                .pointer("/metadata/annotations/opencti-requested-status")
+                .and_then(|v| v.as_str()),
+            Some("stopping"),
+            "refresh patch must carry the updated requested status annotation"
+        );
+    }
+
     #[test]
     fn refresh_patch_strips_selector_from_deployment_spec() {
         // refresh() strips spec.selector from the merge patch so that
@@ -0,0 +1,253 @@
+use crate::api::{ApiConnector, ComposerApi, ConnectorListing, ConnectorStatus, DecryptTally};
+use crate::config::settings::Daemon;
+use crate::orchestrator::{Orchestrator, OrchestratorContainer};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+// Connector ids of the actions a cycle would have performed
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct ReconcilePlan {
+    pub deploy: Vec<String>,
+    pub start: Vec<String>,
+    pub stop: Vec<String>,
+    pub pause: Vec<String>,
+    pub resume: Vec<String>,
+    pub refresh: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PlanReport<'a> {
+    platform: &'a str,
+    #[serde(flatten)]
+    plan: &'a ReconcilePlan,
+}
+
+// Orchestrator decorator recording the mutations instead of performing them,
+// reads still reach the backend so the plan reflects the real state
+pub struct PlanOrchestrator {
+    inner: Box<dyn Orchestrator + Send + Sync>,
+    plan: Arc<Mutex<ReconcilePlan>>,
+}
+
+impl PlanOrchestrator {
+    pub fn new(
+        inner: Box<dyn Orchestrator + Send + Sync>,
+        plan: Arc<Mutex<ReconcilePlan>>,
+    ) -> Self {
+        Self { inner, plan }
+    }
+
+    fn record(&self, connector_id: String, actions: fn(&mut ReconcilePlan) -> &mut Vec<String>) {
+        actions(&mut self.plan.lock().unwrap()).push(connector_id);
+    }
+}
+
+// Platform API decorator dropping the status, logs and health reports of the cycle,
+// the listing and the registration still reach the platform
+pub struct PlanApi {
+    inner: Box<dyn ComposerApi + Send + Sync>,
+}
+
+impl PlanApi {
+    pub fn new(inner: Box<dyn ComposerApi + Send + Sync>) -> Self {
+        Self { inner }
+    }
+}
+
+// Log the plan of the cycle as JSON or replace the content of the given file
+pub fn emit_plan(plan: &ReconcilePlan, platform: &str, file: Option<&str>) {
+    let report = serde_json::to_string(&PlanReport { platform, plan }).unwrap();
+    match file {
+        Some(path) => {
+            if let Err(err) = fs::write(path, &report) {
+                error!(
+                    path,
+                    error = err.to_string(),
+                    "Unable to write the reconcile plan"
+                );
+            }
+        }
+        None => info!(platform, plan = report, "Reconcile plan"),
+    }
+}
+
+#[async_trait]
+impl Orchestrator for PlanOrchestrator {
+    fn labels(&self, connector: &ApiConnector) -> HashMap<String, String> {
+        self.inner.labels(connector)
+    }
+
+    fn requested_status_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        self.inner.requested_status_label(connector)
+    }
+
+    fn last_modified_by_label(&self) -> Option<(String, String)> {
+        self.inner.last_modified_by_label()
+    }
+
+    fn config_hash_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        self.inner.config_hash_label(connector)
+    }
+
+    fn contract_hash_label(&self, connector: &ApiConnector) -> (String, String) {
+        self.inner.contract_hash_label(connector)
+    }
+
+    async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.inner.get(connector).await
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        self.inner.ping().await
+    }
+
+    async fn list(&self) -> Vec<OrchestratorContainer> {
+        self.inner.list().await
+    }
+
+    async fn start(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.record(connector.id.clone(), |plan| &mut plan.start);
+    }
+
+    async fn stop(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.record(connector.id.clone(), |plan| &mut plan.stop);
+    }
+
+    async fn pause(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.record(connector.id.clone(), |plan| &mut plan.pause);
+    }
+
+    async fn resume(&self, _container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.record(connector.id.clone(), |plan| &mut plan.resume);
+    }
+
+    async fn remove(&self, container: &OrchestratorContainer) -> () {
+        self.record(container.extract_opencti_id(), |plan| &mut plan.remove);
+    }
+
+    async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.record(connector.id.clone(), |plan| &mut plan.refresh);
+        None
+    }
+
+    async fn spec_drift(&self, connector: &ApiConnector) -> Vec<String> {
+        self.inner.spec_drift(connector).await
+    }
+
+    async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.record(connector.id.clone(), |plan| &mut plan.deploy);
+        None
+    }
+
+    async fn logs(
+        &self,
+        container: &OrchestratorContainer,
+        connector: &ApiConnector,
+    ) -> Option<Vec<String>> {
+        self.inner.logs(container, connector).await
+    }
+
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        self.inner.removal_logs(container).await
+    }
+
+    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
+        self.inner.state_converter(container)
+    }
+}
+
+#[async_trait]
+impl ComposerApi for PlanApi {
+    fn daemon(&self) -> &Daemon {
+        self.inner.daemon()
+    }
+
+    fn platform(&self) -> &'static str {
+        self.inner.platform()
+    }
+
+    fn post_logs_schedule(&self) -> Duration {
+        self.inner.post_logs_schedule()
+    }
+
+    fn set_cycle_id(&self, cycle_id: &str) {
+        self.inner.set_cycle_id(cycle_id)
+    }
+
+    fn take_decrypt_tally(&self) -> DecryptTally {
+        self.inner.take_decrypt_tally()
+    }
+
+    async fn version(&self) -> Option<String> {
+        self.inner.version().await
+    }
+
+    async fn ping_alive(&self) -> Option<String> {
+        self.inner.ping_alive().await
+    }
+
+    async fn register(&self) -> () {
+        self.inner.register().await
+    }
+
+    async fn connectors(&self) -> Option<Vec<ApiConnector>> {
+        self.inner.connectors().await
+    }
+
+    async fn listing(&self) -> Option<ConnectorListing> {
+        self.inner.listing().await
+    }
+
+    async fn patch_status(&self, id: String, status: ConnectorStatus) -> Option<ApiConnector> {
+        debug!(id, status = ?status, "Reconcile plan, status not reported");
+        None
+    }
+
+    async fn patch_logs(&self, id: String, _logs: Vec<String>) -> Option<String> {
+        debug!(id, "Reconcile plan, logs not reported");
+        None
+    }
+
+    async fn patch_health(
+        &self,
+        id: String,
+        _restart_count: u32,
+        _started_at: String,
+        _is_in_reboot_loop: bool,
+    ) -> Option<String> {
+        debug!(id, "Reconcile plan, health not reported");
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_file_is_replaced_with_the_json_report() {
+        let path = std::env::temp_dir().join(format!("xtm-composer-plan-{}", std::process::id()));
+        let plan = ReconcilePlan {
+            deploy: vec!["connector-1".to_string()],
+            remove: vec!["connector-2".to_string()],
+            ..Default::default()
+        };
+
+        emit_plan(&ReconcilePlan::default(), "opencti", path.to_str());
+        emit_plan(&plan, "opencti", path.to_str());
+
+        let content = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let report: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(report["platform"], "opencti");
+        assert_eq!(report["deploy"], serde_json::json!(["connector-1"]));
+        assert_eq!(report["remove"], serde_json::json!(["connector-2"]));
+        assert_eq!(report["stop"], serde_json::json!([]));
+    }
+}
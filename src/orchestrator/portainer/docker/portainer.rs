@@ -2,8 +2,10 @@ use crate::api::{ApiConnector, ConnectorStatus};
 use crate::api::PROXY_CA_CERT_MOUNT_PATH;
 use crate::config::settings::Portainer;
 use crate::orchestrator::docker::DockerOrchestrator;
-use crate::orchestrator::image::Image;
-use crate::orchestrator::ensure_proxy_ca_file;
+use crate::orchestrator::image::{
+    Image, current_registry_config, pull_permit, pull_stream_error, report_pull_failure,
+};
+use crate::orchestrator::{build_contract_keys_label, build_owner_label, ensure_proxy_ca_file};
 use crate::orchestrator::portainer::docker::{
     PortainerApiError, PortainerDeployHostConfig, PortainerDeployPayload, PortainerDeployResponse,
     PortainerDockerOrchestrator, PortainerGetResponse,
@@ -13,16 +15,71 @@ use async_trait::async_trait;
 use base64::Engine;
 use base64::engine::general_purpose;
 use bollard::models::ContainerSummary;
-use header::HeaderValue;
+use header::{HeaderName, HeaderValue};
 use serde_json;
 use reqwest::header::HeaderMap;
-use reqwest::{Client, header};
+use reqwest::header;
+use reqwest::{Client, ClientBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use std::fmt::Error;
 use tracing::{debug, error, info};
 
 const X_API_KEY: &str = "X-API-KEY";
 
+// Options of the Portainer HTTP client, resolved from the configuration before the build
+struct PortainerClientConfig {
+    user_agent: String,
+    default_headers: HeaderMap,
+    unsecured_certificate: bool,
+}
+const BODY_SNIPPET_LENGTH: usize = 200;
+
+// Start of the response body, enough to recognize an HTML error page in the logs
+fn body_snippet(body: &str) -> String {
+    body.trim().chars().take(BODY_SNIPPET_LENGTH).collect()
+}
+
+fn is_json_response(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.contains("json"))
+}
+
+// Docker answers a JSON 404 for an unknown container, an HTML one is a Portainer routing error
+fn is_missing_container(response: &Response) -> bool {
+    response.status() == StatusCode::NOT_FOUND && is_json_response(response)
+}
+
+// A misconfigured endpoint (wrong env_id) answers with an HTML page instead of JSON
+async fn read_json_response<T: DeserializeOwned>(response: Response, operation: &str) -> Option<T> {
+    let status = response.status().as_u16();
+    if !is_json_response(&response) {
+        let body = response.text().await.unwrap_or_default();
+        error!(
+            operation,
+            status,
+            body = body_snippet(&body),
+            "Portainer returned a non JSON response, check the api and env_id configuration"
+        );
+        return None;
+    }
+    match response.json().await {
+        Ok(data) => Some(data),
+        Err(err) => {
+            error!(
+                operation,
+                status,
+                error = err.to_string(),
+                "Portainer response could not be parsed"
+            );
+            None
+        }
+    }
+}
+
 impl PortainerDockerOrchestrator {
     pub fn new(config: Portainer) -> Self {
         let container_uri = format!(
@@ -33,14 +90,7 @@ impl PortainerDockerOrchestrator {
             "{}/api/endpoints/{}/docker/{}/images",
             config.api, config.env_id, config.api_version
         );
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            X_API_KEY,
-            HeaderValue::from_bytes(config.api_key.as_bytes()).unwrap(),
-        );
-        let client = Client::builder()
-            .default_headers(headers)
-            .danger_accept_invalid_certs(true)
+        let client = Self::client_builder(Self::client_config(&config))
             .build()
             .unwrap();
         Self {
@@ -50,6 +100,150 @@ impl PortainerDockerOrchestrator {
             config,
         }
     }
+
+    async fn remove_container(
+        &self,
+        container: &OrchestratorContainer,
+        graceful_timeout: Option<u64>,
+    ) {
+        let container_name = container.name.as_str();
+        if let Some(timeout) = graceful_timeout {
+            // Let the connector shutdown cleanly before removing it
+            let stop_container_uri =
+                format!("{}/{}/stop?t={}", self.container_uri, container.id, timeout);
+            let _ = self.client.post(stop_container_uri).send().await;
+        }
+        let delete_container_uri = format!(
+            "{}/{}?v=0&force={}",
+            self.container_uri,
+            container.id,
+            graceful_timeout.is_none()
+        );
+        let remove_response = self.client.delete(delete_container_uri).send().await;
+        match remove_response {
+            Ok(response) if is_missing_container(&response) => {
+                debug!(name = container_name, "Container already removed");
+            }
+            Ok(_) => {
+                info!(name = container_name, "Removed container");
+            }
+            Err(err) => {
+                error!(
+                    name = container_name,
+                    error = err.to_string(),
+                    "Could not remove container"
+                );
+            }
+        }
+    }
+
+    fn default_headers(config: &Portainer) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            X_API_KEY,
+            HeaderValue::from_bytes(config.api_key.as_bytes()).unwrap(),
+        );
+        // Optional access scope headers (team, namespace) for RBAC scoped API keys
+        for (name, value) in config.extra_headers.iter().flatten() {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                (Ok(header_name), Ok(header_value)) => {
+                    headers.insert(header_name, header_value);
+                }
+                _ => {
+                    error!(header = name, "Invalid Portainer extra header, ignoring it");
+                }
+            }
+        }
+        headers
+    }
+
+    fn client_config(config: &Portainer) -> PortainerClientConfig {
+        PortainerClientConfig {
+            user_agent: crate::settings().manager.user_agent(),
+            default_headers: Self::default_headers(config),
+            unsecured_certificate: config.unsecured_certificate,
+        }
+    }
+
+    fn client_builder(client_config: PortainerClientConfig) -> ClientBuilder {
+        Client::builder()
+            .user_agent(client_config.user_agent)
+            .default_headers(client_config.default_headers)
+            .danger_accept_invalid_certs(client_config.unsecured_certificate)
+    }
+
+    // Start, stop, pause or unpause, a container removed since it was listed is skipped
+    async fn container_action(&self, container: &OrchestratorContainer, action: &str) {
+        let action_uri = format!("{}/{}/{}", self.container_uri, container.id, action);
+        match self.client.post(action_uri).send().await {
+            Ok(response) if is_missing_container(&response) => {
+                debug!(name = container.name, action, "Container already removed");
+            }
+            // Not modified, the container is already in the requested state
+            Ok(response)
+                if response.status().is_success()
+                    || response.status() == StatusCode::NOT_MODIFIED => {}
+            Ok(response) => {
+                error!(
+                    name = container.name,
+                    action,
+                    status = response.status().as_u16(),
+                    "Portainer container action failed"
+                );
+            }
+            Err(err) => {
+                error!(
+                    name = container.name,
+                    action,
+                    error = err.to_string(),
+                    "Portainer container action failed"
+                );
+            }
+        }
+    }
+
+    async fn container_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        let logs_container_uri = format!(
+            "{}/{}/logs?stderr=1&stdout=1&tail=100",
+            self.container_uri, container.id
+        );
+        let logs_response = match self.client.get(logs_container_uri).send().await {
+            Ok(response) if is_missing_container(&response) => {
+                debug!(name = container.name, "Container already removed, no logs");
+                return None;
+            }
+            Ok(response) if !response.status().is_success() => {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                error!(
+                    name = container.name,
+                    status,
+                    body = body_snippet(&body),
+                    "Portainer error fetching logs"
+                );
+                return None;
+            }
+            Ok(response) => response,
+            Err(err) => {
+                error!(error = err.to_string(), "Portainer error fetching logs");
+                return None;
+            }
+        };
+        match logs_response.text().await {
+            Ok(text_logs) => Some(text_logs.lines().map(|line| line.to_string()).collect()),
+            Err(err) => {
+                error!(
+                    name = container.name,
+                    error = err.to_string(),
+                    "Portainer logs could not be read"
+                );
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -57,17 +251,18 @@ impl Orchestrator for PortainerDockerOrchestrator {
     async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
         let get_uri = format!("{}/{}/json", self.container_uri, connector.container_name());
         let response = self.client.get(get_uri).send().await;
-        let response_result: Result<Option<PortainerGetResponse>, _> = match response {
-            Ok(data) => data.json().await,
+        let container_get: Option<PortainerGetResponse> = match response {
+            // Unknown container, the regular case before the first deployment
+            Ok(data) if is_missing_container(&data) => None,
+            Ok(data) => read_json_response(data, "get").await,
             Err(err) => {
                 error!(
                     error = err.to_string(),
                     "Portainer error fetching containers"
                 );
-                Ok(None)
+                None
             }
         };
-        let container_get = response_result.unwrap_or_default();
         if container_get.is_some() {
             let response_data = container_get.unwrap();
             let container_envs: HashMap<String, String> = response_data
@@ -93,6 +288,20 @@ impl Orchestrator for PortainerDockerOrchestrator {
         }
     }
 
+    async fn ping(&self) -> Result<(), String> {
+        let ping_uri = format!(
+            "{}/api/endpoints/{}/docker/{}/_ping",
+            self.config.api, self.config.env_id, self.config.api_version
+        );
+        self.client
+            .get(ping_uri)
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
     async fn list(&self) -> Vec<OrchestratorContainer> {
         let settings = crate::settings();
         let mut label_filters = Vec::new();
@@ -106,7 +315,8 @@ impl Orchestrator for PortainerDockerOrchestrator {
         let response = self.client.get(list_uri.clone()).send().await;
         let response_result: Result<Vec<OrchestratorContainer>, _> = match response {
             Ok(data) => {
-                let response: Vec<ContainerSummary> = data.json().await.unwrap();
+                let response: Vec<ContainerSummary> =
+                    read_json_response(data, "list").await.unwrap_or_default();
                 let containers = response
                     .into_iter()
                     .map(|summary| {
@@ -142,32 +352,24 @@ impl Orchestrator for PortainerDockerOrchestrator {
 
     async fn start(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
         connector.display_env_variables();
-        let start_container_uri = format!("{}/{}/start", self.container_uri, container.id);
-        self.client.post(start_container_uri).send().await.unwrap();
+        self.container_action(container, "start").await;
     }
 
     async fn stop(&self, container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
-        let start_container_uri = format!("{}/{}/stop", self.container_uri, container.id);
-        self.client.post(start_container_uri).send().await.unwrap();
+        self.container_action(container, "stop").await;
+    }
+
+    async fn pause(&self, container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
+        self.container_action(container, "pause").await;
+    }
+
+    async fn resume(&self, container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
+        self.container_action(container, "unpause").await;
     }
 
     async fn remove(&self, container: &OrchestratorContainer) -> () {
-        let container_name = container.name.as_str();
-        let delete_container_uri =
-            format!("{}/{}?v=0&force=true", self.container_uri, container.id);
-        let remove_response = self.client.delete(delete_container_uri).send().await;
-        match remove_response {
-            Ok(_) => {
-                info!(name = container_name, "Removed container");
-            }
-            Err(err) => {
-                error!(
-                    name = container_name,
-                    error = err.to_string(),
-                    "Could not remove container"
-                );
-            }
-        }
+        let graceful_timeout = crate::settings().manager.graceful_remove_timeout();
+        self.remove_container(container, graceful_timeout).await
     }
 
     async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
@@ -181,29 +383,54 @@ impl Orchestrator for PortainerDockerOrchestrator {
     }
 
     async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
-        let settings = crate::settings();
-        let registry_config = settings.opencti.daemon.registry.clone();
+        let registry_config = current_registry_config();
         let resolver = Image::new(registry_config);
         let auth = resolver.get_credentials();
         let auth_header =
             auth.map(|c| general_purpose::STANDARD.encode(serde_json::to_string(&c).unwrap()));
-        let image = resolver.build_name(connector.image.clone());
+        let image = resolver.build_name(connector.image());
         // region First operation, pull the image
         let create_image_uri = format!("{}/create", self.image_uri);
+        let mut image_query = vec![("fromImage", image.clone())];
+        if let Some(platform) = resolver.get_platform() {
+            image_query.push(("platform", platform));
+        }
         let request_builder = auth_header.into_iter().fold(
-            self.client
-                .post(create_image_uri)
-                .query(&[("fromImage", image.clone())]),
+            self.client.post(create_image_uri).query(&image_query),
             |req, val| req.header("X-Registry-Auth", val),
         );
+        let pull_permit = pull_permit(&image).await;
         let mut create_response = request_builder.send().await.unwrap();
-        while let Some(_chunk) = create_response.chunk().await.unwrap() {} // Iter chunk to fetch all
+        let pull_status = create_response.status();
+        let mut pull_body = Vec::new();
+        while let Some(chunk) = create_response.chunk().await.unwrap() {
+            pull_body.extend_from_slice(&chunk); // Iter chunk to fetch all
+        }
+        drop(pull_permit);
+        // Pull errors come either as an error status or inside the progress stream
+        let pull_body = String::from_utf8_lossy(&pull_body);
+        let pull_error = match pull_stream_error(&pull_body) {
+            Some(stream_error) => Some(stream_error),
+            None if !pull_status.is_success() => Some(body_snippet(&pull_body)),
+            None => None,
+        };
+        if let Some(pull_error) = pull_error {
+            report_pull_failure(&image, Some(pull_status.as_u16()), &pull_error);
+        }
         // endregion
         // region Deploy the container after success
         let image_name: String = connector.container_name();
         let deploy_container_uri = format!("{}/create?name={}", self.container_uri, image_name);
 
         let mut image_labels = self.labels(connector);
+        image_labels.extend(self.requested_status_label(connector));
+        image_labels.extend(self.last_modified_by_label());
+        image_labels.extend(self.config_hash_label(connector));
+        image_labels.extend([
+            self.contract_hash_label(connector),
+            build_owner_label(),
+            build_contract_keys_label(connector),
+        ]);
         let portainer_config = self.config.clone();
         if portainer_config.stack.is_some() {
             let stack_label = portainer_config.stack.unwrap();
@@ -234,15 +461,19 @@ impl Orchestrator for PortainerDockerOrchestrator {
         match deploy_response {
             Ok(response) => {
                 if response.status().is_success() {
-                    let deploy_data: PortainerDeployResponse = response.json().await.unwrap();
+                    let deploy_data: PortainerDeployResponse =
+                        read_json_response(response, "deploy").await?;
                     debug!(id = deploy_data.id, "Portainer container deployed");
                     self.get(connector).await
                 } else {
-                    let deploy_error: PortainerApiError = response.json().await.unwrap();
-                    error!(
-                        error = deploy_error.message,
-                        "Error deploying the container"
-                    );
+                    let deploy_error: Option<PortainerApiError> =
+                        read_json_response(response, "deploy").await;
+                    if let Some(deploy_error) = deploy_error {
+                        error!(
+                            error = deploy_error.message,
+                            "Error deploying the container"
+                        );
+                    }
                     None
                 }
             }
@@ -258,19 +489,258 @@ impl Orchestrator for PortainerDockerOrchestrator {
         container: &OrchestratorContainer,
         _connector: &ApiConnector,
     ) -> Option<Vec<String>> {
-        let logs_container_uri = format!(
-            "{}/{}/logs?stderr=1&stdout=1&tail=100",
-            self.container_uri, container.id
-        );
-        let logs_response = self.client.get(logs_container_uri).send().await.unwrap();
-        let text_logs = logs_response.text().await.unwrap();
-        Some(text_logs.lines().map(|line| line.to_string()).collect())
+        self.container_logs(container).await
+    }
+
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        self.container_logs(container).await
     }
 
     fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
-        match container.state.as_str() {
-            "running" => ConnectorStatus::Started,
-            _ => ConnectorStatus::Stopped,
+        let distinct_unknown = crate::settings().manager.distinct_unknown_status;
+        DockerOrchestrator::state_status(&container.state, distinct_unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn portainer(unsecured_certificate: bool) -> Portainer {
+        Portainer {
+            api: "https://portainer.local:9443".to_string(),
+            api_key: "api-key".to_string(),
+            env_id: "3".to_string(),
+            env_type: "docker".to_string(),
+            api_version: "v1.44".to_string(),
+            stack: None,
+            network_mode: None,
+            unsecured_certificate,
+            extra_headers: None,
+        }
+    }
+
+    #[test]
+    fn client_verifies_certificates_by_default() {
+        let client_config = PortainerDockerOrchestrator::client_config(&portainer(false));
+        assert!(!client_config.unsecured_certificate);
+        assert_eq!(
+            client_config.user_agent,
+            crate::settings().manager.user_agent()
+        );
+        assert_eq!(
+            client_config.default_headers.get(X_API_KEY).unwrap(),
+            "api-key"
+        );
+        PortainerDockerOrchestrator::client_builder(client_config)
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn client_accepts_invalid_certificates_when_unsecured() {
+        let client_config = PortainerDockerOrchestrator::client_config(&portainer(true));
+        assert!(client_config.unsecured_certificate);
+        PortainerDockerOrchestrator::client_builder(client_config)
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn default_headers_include_configured_extra_headers() {
+        let config = Portainer {
+            extra_headers: Some(HashMap::from([(
+                "X-Portainer-Team".to_string(),
+                "connectors".to_string(),
+            )])),
+            ..portainer(false)
+        };
+
+        let headers = PortainerDockerOrchestrator::default_headers(&config);
+
+        assert_eq!(headers.get(X_API_KEY).unwrap(), "api-key");
+        assert_eq!(headers.get("x-portainer-team").unwrap(), "connectors");
+    }
+
+    #[test]
+    fn default_headers_skip_invalid_extra_headers() {
+        let config = Portainer {
+            extra_headers: Some(HashMap::from([(
+                "Invalid Header".to_string(),
+                "value".to_string(),
+            )])),
+            ..portainer(false)
+        };
+
+        let headers = PortainerDockerOrchestrator::default_headers(&config);
+
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key(X_API_KEY));
+    }
+
+    const CONTAINER_PATH: &str = "/api/endpoints/3/docker/v1.44/containers/container-1";
+
+    const HTML_NOT_FOUND: &[u8] = b"HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nContent-Length: 49\r\nConnection: close\r\n\r\n<html><body><h1>Page not found</h1></body></html>";
+
+    // Fake Portainer API answering 204 and recording the received requests (line and headers)
+    async fn fake_portainer() -> (String, Arc<Mutex<Vec<String>>>) {
+        fake_portainer_answering(
+            b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await
+    }
+
+    async fn fake_portainer_answering(answer: &'static [u8]) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = vec![0u8; 4096];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                if let Some((head, _body)) = request.split_once("\r\n\r\n") {
+                    recorded
+                        .lock()
+                        .expect("mutex should not be poisoned")
+                        .push(head.to_string());
+                }
+                let _ = stream.write_all(answer).await;
+            }
+        });
+        (api, requests)
+    }
+
+    fn connector() -> ApiConnector {
+        ApiConnector::for_test("connector-1")
+    }
+
+    fn container() -> OrchestratorContainer {
+        OrchestratorContainer {
+            id: "container-1".to_string(),
+            name: "connector-1".to_string(),
+            state: "running".to_string(),
+            labels: HashMap::new(),
+            envs: HashMap::new(),
+            restart_count: 0,
+            started_at: None,
         }
     }
+
+    async fn removal_requests(graceful_timeout: Option<u64>) -> Vec<String> {
+        let (api, requests) = fake_portainer().await;
+        let orchestrator = PortainerDockerOrchestrator::new(Portainer {
+            api,
+            ..portainer(false)
+        });
+        let container = container();
+
+        orchestrator
+            .remove_container(&container, graceful_timeout)
+            .await;
+
+        requests
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn graceful_remove_stops_before_removing() {
+        let requests = removal_requests(Some(15)).await;
+
+        assert_eq!(requests.len(), 2, "unexpected requests: {requests:?}");
+        assert!(requests[0].starts_with(&format!("POST {CONTAINER_PATH}/stop?t=15 ")));
+        assert!(requests[1].starts_with(&format!("DELETE {CONTAINER_PATH}?v=0&force=false ")));
+    }
+
+    #[tokio::test]
+    async fn default_remove_forces_removal_without_stop() {
+        let requests = removal_requests(None).await;
+
+        assert_eq!(requests.len(), 1, "unexpected requests: {requests:?}");
+        assert!(requests[0].starts_with(&format!("DELETE {CONTAINER_PATH}?v=0&force=true ")));
+    }
+
+    #[tokio::test]
+    async fn requests_carry_composer_user_agent() {
+        let requests = removal_requests(None).await;
+
+        let expected = format!("user-agent: {}", crate::settings().manager.user_agent());
+        assert!(
+            requests[0]
+                .to_lowercase()
+                .contains(&expected.to_lowercase()),
+            "unexpected request: {}",
+            requests[0]
+        );
+    }
+
+    const JSON_NOT_FOUND: &[u8] = b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 44\r\nConnection: close\r\n\r\n{\"message\":\"No such container: container-1\"}";
+
+    #[tokio::test]
+    async fn stop_of_removed_container_is_skipped() {
+        let (api, requests) = fake_portainer_answering(JSON_NOT_FOUND).await;
+        let orchestrator = PortainerDockerOrchestrator::new(Portainer {
+            api,
+            ..portainer(false)
+        });
+        let container = container();
+
+        orchestrator.stop(&container, &connector()).await;
+        assert!(orchestrator.logs(&container, &connector()).await.is_none());
+
+        let requests = requests
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        assert_eq!(requests.len(), 2, "unexpected requests: {requests:?}");
+        assert!(requests[0].starts_with(&format!("POST {CONTAINER_PATH}/stop ")));
+    }
+
+    #[test]
+    fn body_snippet_is_trimmed_and_bounded() {
+        assert_eq!(body_snippet("  <html>error</html>\n"), "<html>error</html>");
+        assert_eq!(body_snippet(&"x".repeat(500)).len(), BODY_SNIPPET_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn html_error_page_is_handled_gracefully() {
+        let (api, requests) = fake_portainer_answering(HTML_NOT_FOUND).await;
+        let orchestrator = PortainerDockerOrchestrator::new(Portainer {
+            api,
+            ..portainer(false)
+        });
+        let connector = connector();
+
+        assert!(orchestrator.get(&connector).await.is_none());
+        assert!(orchestrator.list().await.is_empty());
+        assert!(orchestrator.deploy(&connector).await.is_none());
+
+        let requests = requests
+            .lock()
+            .expect("mutex should not be poisoned")
+            .clone();
+        // get, list, image pull and container creation all reached the fake server
+        assert_eq!(requests.len(), 4, "unexpected requests: {requests:?}");
+    }
+
+    const SERVER_ERROR: &[u8] = b"HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/plain\r\nContent-Length: 14\r\nConnection: close\r\n\r\nendpoint error";
+
+    #[tokio::test]
+    async fn error_answer_is_not_reported_as_logs() {
+        let (api, _requests) = fake_portainer_answering(SERVER_ERROR).await;
+        let orchestrator = PortainerDockerOrchestrator::new(Portainer {
+            api,
+            ..portainer(false)
+        });
+        let container = container();
+
+        let logs = orchestrator.logs(&container, &connector()).await;
+        assert!(logs.is_none(), "{logs:?}");
+    }
 }
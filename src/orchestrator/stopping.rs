@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+// Stops issued by the composer, kept across orchestration cycles so a container
+// slow to stop is not asked to stop again on every cycle
+#[derive(Debug)]
+pub struct PendingStops {
+    grace_period: Duration,
+    entries: HashMap<String, Instant>,
+}
+
+impl Default for PendingStops {
+    fn default() -> Self {
+        Self::new(DEFAULT_GRACE_PERIOD)
+    }
+}
+
+impl PendingStops {
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn record_stop(&mut self, connector_id: &str, now: Instant) {
+        self.entries.insert(connector_id.to_string(), now);
+    }
+
+    // Time elapsed since the stop was issued, None if no stop is still within its grace period
+    pub fn in_progress(&self, connector_id: &str, now: Instant) -> Option<Duration> {
+        self.entries
+            .get(connector_id)
+            .map(|issued_at| now.saturating_duration_since(*issued_at))
+            .filter(|elapsed| *elapsed < self.grace_period)
+    }
+
+    pub fn can_stop(&self, connector_id: &str, now: Instant) -> bool {
+        self.in_progress(connector_id, now).is_none()
+    }
+
+    // Forget the stop once the container is stopped or a start is requested
+    pub fn clear(&mut self, connector_id: &str) -> bool {
+        self.entries.remove(connector_id).is_some()
+    }
+
+    // Removed connectors have no stop left to wait for
+    pub fn retain(&mut self, is_managed: impl Fn(&str) -> bool) {
+        self.entries
+            .retain(|connector_id, _| is_managed(connector_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_is_not_repeated_within_grace_period() {
+        let mut pending_stops = PendingStops::new(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(pending_stops.can_stop("A", now));
+
+        pending_stops.record_stop("A", now);
+        assert!(!pending_stops.can_stop("A", now + Duration::from_secs(59)));
+        assert_eq!(
+            pending_stops.in_progress("A", now + Duration::from_secs(20)),
+            Some(Duration::from_secs(20))
+        );
+        // Other connectors are not affected
+        assert!(pending_stops.can_stop("B", now));
+    }
+
+    #[test]
+    fn stop_can_be_reissued_once_grace_period_elapsed() {
+        let mut pending_stops = PendingStops::new(Duration::from_secs(60));
+        let now = Instant::now();
+        pending_stops.record_stop("A", now);
+
+        let later = now + Duration::from_secs(60);
+        assert!(pending_stops.can_stop("A", later));
+        assert_eq!(pending_stops.in_progress("A", later), None);
+
+        pending_stops.record_stop("A", later);
+        assert!(!pending_stops.can_stop("A", later + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn clear_forgets_pending_stop() {
+        let mut pending_stops = PendingStops::new(Duration::from_secs(60));
+        let now = Instant::now();
+        pending_stops.record_stop("A", now);
+
+        assert!(pending_stops.clear("A"));
+        assert!(pending_stops.can_stop("A", now));
+        assert!(!pending_stops.clear("A"));
+    }
+
+    #[test]
+    fn retain_forgets_stops_of_removed_connectors() {
+        let mut pending_stops = PendingStops::new(Duration::from_secs(60));
+        let now = Instant::now();
+        pending_stops.record_stop("A", now);
+        pending_stops.record_stop("B", now);
+
+        pending_stops.retain(|connector_id| connector_id == "B");
+        assert!(pending_stops.can_stop("A", now));
+        assert!(!pending_stops.can_stop("B", now));
+    }
+}
@@ -1,8 +1,15 @@
 use crate::api::{ApiConnector, ConnectorStatus};
 use crate::api::PROXY_CA_CERT_MOUNT_PATH;
-use crate::orchestrator::image::Image;
+use crate::config::settings::Swarm;
+use crate::orchestrator::docker::DockerOrchestrator;
+use crate::orchestrator::image::{
+    Image, current_registry_config, pull_error_status, pull_permit, report_pull_failure,
+};
 use crate::orchestrator::swarm::SwarmOrchestrator;
-use crate::orchestrator::ensure_proxy_ca_file;
+use crate::orchestrator::{
+    build_contract_keys_label, build_owner_label, container_hostname, ensure_proxy_ca_file,
+    unrecognized_state_status,
+};
 use crate::orchestrator::{Orchestrator, OrchestratorContainer};
 use async_trait::async_trait;
 use bollard::auth::DockerCredentials;
@@ -15,7 +22,7 @@ use bollard::models::{
     TaskSpecRestartPolicyConditionEnum,
 };
 use bollard::query_parameters::{
-    CreateImageOptions, InspectServiceOptions, ListServicesOptions, ListTasksOptions, LogsOptions,
+    InspectServiceOptions, ListServicesOptions, ListTasksOptions, LogsOptions,
     UpdateServiceOptions,
 };
 use bollard::Docker;
@@ -30,6 +37,45 @@ impl SwarmOrchestrator {
         Self { docker, config }
     }
 
+    // Global constraints merged with the connector specific ones (heavy connectors on dedicated nodes)
+    pub fn build_placement(config: &Swarm, connector_id: &str) -> Option<TaskSpecPlacement> {
+        let connector_constraints = config
+            .connector_placement_constraints
+            .as_ref()
+            .and_then(|overrides| overrides.get(connector_id));
+        let mut constraints: Option<Vec<String>> = config.placement_constraints.clone();
+        if let Some(connector_constraints) = connector_constraints {
+            let merged = constraints.get_or_insert_with(Vec::new);
+            for constraint in connector_constraints {
+                if !merged.contains(constraint) {
+                    merged.push(constraint.clone());
+                }
+            }
+        }
+        let preferences = config.placement_preferences.as_ref().map(|prefs| {
+            prefs
+                .iter()
+                .map(|p| TaskSpecPlacementPreferences {
+                    spread: Some(TaskSpecPlacementSpread {
+                        spread_descriptor: Some(p.clone()),
+                    }),
+                })
+                .collect()
+        });
+        if constraints.is_none() && preferences.is_none() {
+            return None;
+        }
+        Some(TaskSpecPlacement {
+            constraints,
+            preferences,
+            ..Default::default()
+        })
+    }
+
+    fn hostname(config: &Swarm, connector: &ApiConnector) -> String {
+        container_hostname(connector, config.hostname.as_ref())
+    }
+
     async fn get_task_info(&self, service_name: &str) -> (u32, Option<String>, String) {
         let filters = HashMap::from([(
             "service".to_string(),
@@ -72,6 +118,92 @@ impl SwarmOrchestrator {
             Err(_) => (0, None, "unknown".to_string()),
         }
     }
+
+    // Service states computed from its tasks, unknown when the tasks can't be listed
+    pub fn state_status(state: &str, distinct_unknown: bool) -> ConnectorStatus {
+        match state {
+            "running" => ConnectorStatus::Started,
+            "stopped" => ConnectorStatus::Stopped,
+            _ => unrecognized_state_status(distinct_unknown),
+        }
+    }
+
+    // Logs of the running task of the service
+    async fn service_logs(&self, service_name: String) -> Option<Vec<String>> {
+        // Retrieve logs via tasks: find the running task's container and get its logs
+        let filters = HashMap::from([(
+            "service".to_string(),
+            vec![service_name.clone()],
+        )]);
+        let task_options = Some(ListTasksOptions {
+            filters: Some(filters),
+            ..Default::default()
+        });
+
+        match self.docker.list_tasks(task_options).await {
+            Ok(tasks) => {
+                // Find a running task with a container ID
+                for task in &tasks {
+                    let is_running = task
+                        .status
+                        .as_ref()
+                        .and_then(|s| s.state.as_ref())
+                        .map(|s| {
+                            let state_str = format!("{:?}", s).to_lowercase();
+                            state_str == "running" || state_str.contains("running")
+                        })
+                        .unwrap_or(false);
+
+                    if !is_running {
+                        continue;
+                    }
+
+                    let container_id = task
+                        .status
+                        .as_ref()
+                        .and_then(|s| s.container_status.as_ref())
+                        .and_then(|cs| cs.container_id.as_ref());
+
+                    if let Some(cid) = container_id {
+                        let opts = Some(LogsOptions {
+                            follow: false,
+                            stdout: true,
+                            stderr: true,
+                            tail: "100".to_string(),
+                            ..Default::default()
+                        });
+                        let split_streams = crate::settings().manager.split_log_streams;
+                        let logs = self.docker.logs(cid.as_str(), opts);
+                        let mut logs_content = Vec::new();
+                        match logs
+                            .try_for_each(|log| {
+                                logs_content.push(DockerOrchestrator::log_line(log, split_streams));
+                                future::ok(())
+                            })
+                            .await
+                        {
+                            Ok(_) => return Some(logs_content),
+                            Err(err) => {
+                                debug!(
+                                    error = err.to_string(),
+                                    "Could not fetch logs from task container, trying next task"
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            Err(err) => {
+                error!(
+                    error = err.to_string(),
+                    "Error fetching tasks for swarm service"
+                );
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -126,6 +258,14 @@ impl Orchestrator for SwarmOrchestrator {
         }
     }
 
+    async fn ping(&self) -> Result<(), String> {
+        self.docker
+            .ping()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
     async fn list(&self) -> Vec<OrchestratorContainer> {
         let settings = crate::settings();
         let manager_label = format!("opencti-manager={}", settings.manager.id);
@@ -171,6 +311,9 @@ impl Orchestrator for SwarmOrchestrator {
         {
             let version = svc.version.as_ref().and_then(|v| v.index).unwrap_or(0) as i32;
             let mut spec = svc.spec.unwrap_or_default();
+            spec.labels
+                .get_or_insert_with(HashMap::new)
+                .extend(self.last_modified_by_label());
 
             if let Some(ref mut mode) = spec.mode {
                 if let Some(ref mut replicated) = mode.replicated {
@@ -205,6 +348,9 @@ impl Orchestrator for SwarmOrchestrator {
         {
             let version = svc.version.as_ref().and_then(|v| v.index).unwrap_or(0) as i32;
             let mut spec = svc.spec.unwrap_or_default();
+            spec.labels
+                .get_or_insert_with(HashMap::new)
+                .extend(self.last_modified_by_label());
 
             if let Some(ref mut mode) = spec.mode {
                 if let Some(ref mut replicated) = mode.replicated {
@@ -255,19 +401,16 @@ impl Orchestrator for SwarmOrchestrator {
     }
 
     async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
-        let settings = crate::settings();
-        let registry_config = settings.opencti.daemon.registry.clone();
+        let registry_config = current_registry_config();
         let resolver = Image::new(registry_config);
         let auth = resolver.get_credentials();
-        let image = resolver.build_name(connector.image.clone());
+        let image = resolver.build_name(connector.image());
 
+        let pull_permit = pull_permit(&image).await;
         let pull_result = self
             .docker
             .create_image(
-                Some(CreateImageOptions {
-                    from_image: Some(image.clone()),
-                    ..Default::default()
-                }),
+                Some(resolver.create_image_options(&image)),
                 None,
                 auth.clone(),
             )
@@ -281,6 +424,7 @@ impl Orchestrator for SwarmOrchestrator {
                 future::ok(())
             })
             .await;
+        drop(pull_permit);
 
         match pull_result {
             Ok(_) => {
@@ -289,7 +433,15 @@ impl Orchestrator for SwarmOrchestrator {
                     .into_iter()
                     .map(|config| format!("{}={}", config.key, config.value))
                     .collect();
-                let labels = self.labels(connector);
+                let mut labels = self.labels(connector);
+                labels.extend(self.requested_status_label(connector));
+                labels.extend(self.last_modified_by_label());
+                labels.extend(self.config_hash_label(connector));
+                labels.extend([
+                    self.contract_hash_label(connector),
+                    build_owner_label(),
+                    build_contract_keys_label(connector),
+                ]);
                 let swarm_opts = &self.config;
 
                 // Build container spec with all swarm options
@@ -341,9 +493,7 @@ impl Orchestrator for SwarmOrchestrator {
                         container_spec.ulimits = Some(ulimits_vec);
                     }
                 }
-                if let Some(hostname) = &swarm_opts.hostname {
-                    container_spec.hostname = Some(hostname.clone());
-                }
+                container_spec.hostname = Some(Self::hostname(swarm_opts, connector));
                 if let Some(user) = &swarm_opts.user {
                     container_spec.user = Some(user.clone());
                 }
@@ -407,31 +557,7 @@ impl Orchestrator for SwarmOrchestrator {
                 });
 
                 // Build placement constraints and preferences
-                let placement = if swarm_opts.placement_constraints.is_some()
-                    || swarm_opts.placement_preferences.is_some()
-                {
-                    let preferences =
-                        swarm_opts
-                            .placement_preferences
-                            .as_ref()
-                            .map(|prefs| {
-                                prefs
-                                    .iter()
-                                    .map(|p| TaskSpecPlacementPreferences {
-                                        spread: Some(TaskSpecPlacementSpread {
-                                            spread_descriptor: Some(p.clone()),
-                                        }),
-                                    })
-                                    .collect()
-                            });
-                    Some(TaskSpecPlacement {
-                        constraints: swarm_opts.placement_constraints.clone(),
-                        preferences,
-                        ..Default::default()
-                    })
-                } else {
-                    None
-                };
+                let placement = Self::build_placement(swarm_opts, &connector.id);
 
                 // Build restart policy
                 let restart_policy = if swarm_opts.restart_condition.is_some()
@@ -498,11 +624,7 @@ impl Orchestrator for SwarmOrchestrator {
                 self.get(connector).await
             }
             Err(e) => {
-                error!(
-                    image = image,
-                    error = e.to_string(),
-                    "Error fetching container image"
-                );
+                report_pull_failure(&image, pull_error_status(&e), &e.to_string());
                 None
             }
         }
@@ -513,86 +635,80 @@ impl Orchestrator for SwarmOrchestrator {
         _container: &OrchestratorContainer,
         connector: &ApiConnector,
     ) -> Option<Vec<String>> {
-        let service_name = connector.container_name();
+        self.service_logs(connector.container_name()).await
+    }
 
-        // Retrieve logs via tasks: find the running task's container and get its logs
-        let filters = HashMap::from([(
-            "service".to_string(),
-            vec![service_name.clone()],
-        )]);
-        let task_options = Some(ListTasksOptions {
-            filters: Some(filters),
-            ..Default::default()
-        });
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        self.service_logs(container.name.clone()).await
+    }
 
-        match self.docker.list_tasks(task_options).await {
-            Ok(tasks) => {
-                // Find a running task with a container ID
-                for task in &tasks {
-                    let is_running = task
-                        .status
-                        .as_ref()
-                        .and_then(|s| s.state.as_ref())
-                        .map(|s| {
-                            let state_str = format!("{:?}", s).to_lowercase();
-                            state_str == "running" || state_str.contains("running")
-                        })
-                        .unwrap_or(false);
+    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
+        let distinct_unknown = crate::settings().manager.distinct_unknown_status;
+        Self::state_status(&container.state, distinct_unknown)
+    }
+}
 
-                    if !is_running {
-                        continue;
-                    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                    let container_id = task
-                        .status
-                        .as_ref()
-                        .and_then(|s| s.container_status.as_ref())
-                        .and_then(|cs| cs.container_id.as_ref());
+    fn swarm_config(value: serde_json::Value) -> Swarm {
+        serde_json::from_value(value).unwrap()
+    }
 
-                    if let Some(cid) = container_id {
-                        let opts = Some(LogsOptions {
-                            follow: false,
-                            stdout: true,
-                            stderr: true,
-                            tail: "100".to_string(),
-                            ..Default::default()
-                        });
-                        let logs = self.docker.logs(cid.as_str(), opts);
-                        let mut logs_content = Vec::new();
-                        match logs
-                            .try_for_each(|log| {
-                                logs_content.push(log.to_string());
-                                future::ok(())
-                            })
-                            .await
-                        {
-                            Ok(_) => return Some(logs_content),
-                            Err(err) => {
-                                debug!(
-                                    error = err.to_string(),
-                                    "Could not fetch logs from task container, trying next task"
-                                );
-                                continue;
-                            }
-                        }
-                    }
-                }
-                None
-            }
-            Err(err) => {
-                error!(
-                    error = err.to_string(),
-                    "Error fetching tasks for swarm service"
-                );
-                None
+    #[test]
+    fn service_hostname_prefers_configured_value_over_container_name() {
+        let connector = ApiConnector::for_test("swarm-hostname");
+        assert_eq!(
+            SwarmOrchestrator::hostname(&swarm_config(serde_json::json!({})), &connector),
+            connector.container_name()
+        );
+        assert_eq!(
+            SwarmOrchestrator::hostname(
+                &swarm_config(serde_json::json!({ "hostname": "connectors" })),
+                &connector
+            ),
+            "connectors"
+        );
+    }
+
+    #[test]
+    fn placement_merges_connector_constraints_with_global_ones() {
+        let config = swarm_config(serde_json::json!({
+            "placement_constraints": ["node.role==worker"],
+            "connector_placement_constraints": {
+                "heavy-connector": ["node.labels.size==large", "node.role==worker"]
             }
-        }
+        }));
+
+        let heavy = SwarmOrchestrator::build_placement(&config, "heavy-connector").unwrap();
+        assert_eq!(
+            heavy.constraints,
+            Some(vec![
+                "node.role==worker".to_string(),
+                "node.labels.size==large".to_string()
+            ])
+        );
+        let other = SwarmOrchestrator::build_placement(&config, "other-connector").unwrap();
+        assert_eq!(
+            other.constraints,
+            Some(vec!["node.role==worker".to_string()])
+        );
     }
 
-    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
-        match container.state.as_str() {
-            "running" => ConnectorStatus::Started,
-            _ => ConnectorStatus::Stopped,
-        }
+    #[test]
+    fn placement_with_only_connector_constraints() {
+        let config = swarm_config(serde_json::json!({
+            "connector_placement_constraints": {
+                "heavy-connector": ["node.hostname==big-node"]
+            }
+        }));
+
+        let heavy = SwarmOrchestrator::build_placement(&config, "heavy-connector").unwrap();
+        assert_eq!(
+            heavy.constraints,
+            Some(vec!["node.hostname==big-node".to_string()])
+        );
+        assert!(SwarmOrchestrator::build_placement(&config, "other-connector").is_none());
     }
 }
@@ -0,0 +1,384 @@
+use crate::api::{ApiConnector, ConnectorStatus};
+use crate::orchestrator::{Orchestrator, OrchestratorContainer};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+// Orchestrator decorator bounding the backend operations that can hang the orchestration loop
+pub struct TimeoutOrchestrator {
+    inner: Box<dyn Orchestrator + Send + Sync>,
+    operation_timeout: Duration,
+    deploy_timeout: Duration,
+}
+
+impl TimeoutOrchestrator {
+    pub fn new(inner: Box<dyn Orchestrator + Send + Sync>, operation_timeout: Duration) -> Self {
+        Self {
+            inner,
+            operation_timeout,
+            deploy_timeout: operation_timeout,
+        }
+    }
+
+    // Deploys include the image pull and may need a different bound than the other operations
+    pub fn with_deploy_timeout(mut self, deploy_timeout: Duration) -> Self {
+        self.deploy_timeout = deploy_timeout;
+        self
+    }
+
+    async fn bounded_by<T>(
+        &self,
+        operation_timeout: Duration,
+        operation: &str,
+        connector_id: Option<&str>,
+        future: impl Future<Output = T>,
+    ) -> Option<T> {
+        match timeout(operation_timeout, future).await {
+            Ok(result) => Some(result),
+            Err(_) => {
+                warn!(
+                    operation,
+                    id = connector_id.unwrap_or_default(),
+                    timeout = operation_timeout.as_secs(),
+                    "Orchestrator operation timed out"
+                );
+                None
+            }
+        }
+    }
+
+    async fn bounded<T>(
+        &self,
+        operation: &str,
+        connector_id: Option<&str>,
+        future: impl Future<Output = T>,
+    ) -> Option<T> {
+        self.bounded_by(self.operation_timeout, operation, connector_id, future)
+            .await
+    }
+
+    // An aborted deploy may leave a created but never started container behind, remove it
+    async fn rollback_deploy(&self, connector: &ApiConnector) {
+        let partial = self
+            .bounded("get", Some(&connector.id), self.inner.get(connector))
+            .await
+            .flatten();
+        if let Some(container) = partial {
+            info!(
+                id = connector.id,
+                name = container.name,
+                "Rolling back partially deployed connector"
+            );
+            self.bounded("remove", Some(&connector.id), self.inner.remove(&container))
+                .await;
+        }
+    }
+}
+
+#[async_trait]
+impl Orchestrator for TimeoutOrchestrator {
+    fn labels(&self, connector: &ApiConnector) -> HashMap<String, String> {
+        self.inner.labels(connector)
+    }
+
+    fn requested_status_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        self.inner.requested_status_label(connector)
+    }
+
+    fn last_modified_by_label(&self) -> Option<(String, String)> {
+        self.inner.last_modified_by_label()
+    }
+
+    fn config_hash_label(&self, connector: &ApiConnector) -> Option<(String, String)> {
+        self.inner.config_hash_label(connector)
+    }
+
+    fn contract_hash_label(&self, connector: &ApiConnector) -> (String, String) {
+        self.inner.contract_hash_label(connector)
+    }
+
+    async fn get(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.bounded("get", Some(&connector.id), self.inner.get(connector))
+            .await
+            .flatten()
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        self.bounded("ping", None, self.inner.ping())
+            .await
+            .unwrap_or_else(|| Err("ping timed out".to_string()))
+    }
+
+    async fn list(&self) -> Vec<OrchestratorContainer> {
+        // An empty list on timeout is safe, nothing gets cleaned up this cycle
+        self.bounded("list", None, self.inner.list())
+            .await
+            .unwrap_or_default()
+    }
+
+    async fn start(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.inner.start(container, connector).await
+    }
+
+    async fn stop(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.inner.stop(container, connector).await
+    }
+
+    async fn pause(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.inner.pause(container, connector).await
+    }
+
+    async fn resume(&self, container: &OrchestratorContainer, connector: &ApiConnector) -> () {
+        self.inner.resume(container, connector).await
+    }
+
+    async fn remove(&self, container: &OrchestratorContainer) -> () {
+        self.inner.remove(container).await
+    }
+
+    async fn refresh(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        self.bounded(
+            "refresh",
+            Some(&connector.id),
+            self.inner.refresh(connector),
+        )
+        .await
+        .flatten()
+    }
+
+    async fn spec_drift(&self, connector: &ApiConnector) -> Vec<String> {
+        self.bounded(
+            "spec_drift",
+            Some(&connector.id),
+            self.inner.spec_drift(connector),
+        )
+        .await
+        .unwrap_or_default()
+    }
+
+    async fn deploy(&self, connector: &ApiConnector) -> Option<OrchestratorContainer> {
+        let deploy = self
+            .bounded_by(
+                self.deploy_timeout,
+                "deploy",
+                Some(&connector.id),
+                self.inner.deploy(connector),
+            )
+            .await;
+        match deploy {
+            Some(container) => container,
+            None => {
+                self.rollback_deploy(connector).await;
+                None
+            }
+        }
+    }
+
+    async fn logs(
+        &self,
+        container: &OrchestratorContainer,
+        connector: &ApiConnector,
+    ) -> Option<Vec<String>> {
+        self.inner.logs(container, connector).await
+    }
+
+    async fn removal_logs(&self, container: &OrchestratorContainer) -> Option<Vec<String>> {
+        self.inner.removal_logs(container).await
+    }
+
+    fn state_converter(&self, container: &OrchestratorContainer) -> ConnectorStatus {
+        self.inner.state_converter(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct SlowOrchestrator {
+        delay: Duration,
+        deploy_delay: Duration,
+        remove_delay: Duration,
+        removed: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SlowOrchestrator {
+        fn container() -> OrchestratorContainer {
+            OrchestratorContainer {
+                id: "container-A".to_string(),
+                name: "connector-a".to_string(),
+                state: "running".to_string(),
+                labels: HashMap::new(),
+                envs: HashMap::new(),
+                restart_count: 0,
+                started_at: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Orchestrator for SlowOrchestrator {
+        async fn get(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            tokio::time::sleep(self.delay).await;
+            Some(Self::container())
+        }
+
+        async fn list(&self) -> Vec<OrchestratorContainer> {
+            tokio::time::sleep(self.delay).await;
+            vec![Self::container()]
+        }
+
+        async fn start(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {
+        }
+
+        async fn stop(&self, _container: &OrchestratorContainer, _connector: &ApiConnector) -> () {}
+
+        async fn remove(&self, container: &OrchestratorContainer) -> () {
+            tokio::time::sleep(self.remove_delay).await;
+            self.removed
+                .lock()
+                .expect("mutex should not be poisoned")
+                .push(container.name.clone());
+        }
+
+        async fn refresh(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            tokio::time::sleep(self.delay).await;
+            Some(Self::container())
+        }
+
+        async fn deploy(&self, _connector: &ApiConnector) -> Option<OrchestratorContainer> {
+            tokio::time::sleep(self.deploy_delay).await;
+            Some(Self::container())
+        }
+
+        async fn logs(
+            &self,
+            _container: &OrchestratorContainer,
+            _connector: &ApiConnector,
+        ) -> Option<Vec<String>> {
+            None
+        }
+
+        fn state_converter(&self, _container: &OrchestratorContainer) -> ConnectorStatus {
+            ConnectorStatus::Started
+        }
+    }
+
+    fn orchestrator(delay: Duration) -> TimeoutOrchestrator {
+        TimeoutOrchestrator::new(
+            Box::new(SlowOrchestrator {
+                delay,
+                deploy_delay: delay,
+                remove_delay: Duration::ZERO,
+                removed: Arc::new(Mutex::new(Vec::new())),
+            }),
+            Duration::from_millis(50),
+        )
+    }
+
+    fn slow_deploy_orchestrator(
+        deploy_delay: Duration,
+        deploy_timeout: Duration,
+    ) -> (TimeoutOrchestrator, Arc<Mutex<Vec<String>>>) {
+        slow_rollback_orchestrator(deploy_delay, deploy_timeout, Duration::ZERO)
+    }
+
+    fn slow_rollback_orchestrator(
+        deploy_delay: Duration,
+        deploy_timeout: Duration,
+        remove_delay: Duration,
+    ) -> (TimeoutOrchestrator, Arc<Mutex<Vec<String>>>) {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let orchestrator = TimeoutOrchestrator::new(
+            Box::new(SlowOrchestrator {
+                delay: Duration::ZERO,
+                deploy_delay,
+                remove_delay,
+                removed: Arc::clone(&removed),
+            }),
+            Duration::from_millis(50),
+        )
+        .with_deploy_timeout(deploy_timeout);
+        (orchestrator, removed)
+    }
+
+    fn connector() -> ApiConnector {
+        ApiConnector {
+            name: "connector-a".to_string(),
+            ..ApiConnector::for_test("A")
+        }
+    }
+
+    #[tokio::test]
+    async fn operations_pass_through_within_timeout() {
+        let orchestrator = orchestrator(Duration::ZERO);
+        let connector = connector();
+
+        assert!(orchestrator.get(&connector).await.is_some());
+        assert_eq!(orchestrator.list().await.len(), 1);
+        assert!(orchestrator.refresh(&connector).await.is_some());
+        assert!(orchestrator.deploy(&connector).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn operations_are_cancelled_on_timeout() {
+        let orchestrator = orchestrator(Duration::from_secs(10));
+        let connector = connector();
+        let started = std::time::Instant::now();
+
+        assert!(orchestrator.get(&connector).await.is_none());
+        assert!(orchestrator.list().await.is_empty());
+        assert!(orchestrator.refresh(&connector).await.is_none());
+        assert!(orchestrator.deploy(&connector).await.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn deploy_timeout_rolls_back_partial_deployment() {
+        let (orchestrator, removed) =
+            slow_deploy_orchestrator(Duration::from_secs(10), Duration::from_millis(50));
+
+        assert!(orchestrator.deploy(&connector()).await.is_none());
+        assert_eq!(
+            *removed.lock().expect("mutex should not be poisoned"),
+            vec!["connector-a".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn rollback_removal_is_bounded_by_operation_timeout() {
+        let (orchestrator, removed) = slow_rollback_orchestrator(
+            Duration::from_secs(10),
+            Duration::from_millis(50),
+            Duration::from_secs(10),
+        );
+        let started = std::time::Instant::now();
+
+        assert!(orchestrator.deploy(&connector()).await.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(
+            removed
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn deploy_timeout_is_independent_of_operation_timeout() {
+        let (orchestrator, removed) =
+            slow_deploy_orchestrator(Duration::from_millis(100), Duration::from_secs(5));
+
+        assert!(orchestrator.deploy(&connector()).await.is_some());
+        assert!(
+            removed
+                .lock()
+                .expect("mutex should not be poisoned")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::api::ConnectorStatus;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tracing::info;
+
+// Counter xtm_connector_transitions_total, per from / to states
+static TRANSITIONS: Mutex<BTreeMap<(&'static str, &'static str), u64>> =
+    Mutex::new(BTreeMap::new());
+
+fn state_name(status: ConnectorStatus) -> &'static str {
+    match status {
+        ConnectorStatus::Started => "started",
+        ConnectorStatus::Stopped => "stopped",
+        ConnectorStatus::Paused => "paused",
+        ConnectorStatus::Unknown => "unknown",
+    }
+}
+
+// Container state seen by the previous cycles, so a stable fleet only logs its changes
+#[derive(Debug, Default)]
+pub struct ConnectorTransitions {
+    enabled: bool,
+    states: HashMap<String, ConnectorStatus>,
+}
+
+impl ConnectorTransitions {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            states: HashMap::new(),
+        }
+    }
+
+    // Previous state when it differs, the first observation of a connector is no transition
+    pub fn record(
+        &mut self,
+        connector_id: &str,
+        status: ConnectorStatus,
+    ) -> Option<ConnectorStatus> {
+        if !self.enabled {
+            return None;
+        }
+        let previous = self.states.insert(connector_id.to_string(), status)?;
+        if previous == status {
+            return None;
+        }
+        let (from, to) = (state_name(previous), state_name(status));
+        let total = {
+            let mut transitions = TRANSITIONS.lock().unwrap();
+            let count = transitions.entry((from, to)).or_default();
+            *count += 1;
+            *count
+        };
+        info!(
+            id = connector_id,
+            from,
+            to,
+            xtm_connector_transitions_total = total,
+            "Connector state changed"
+        );
+        Some(previous)
+    }
+
+    // Removed connectors start over if they come back
+    pub fn retain(&mut self, is_managed: impl Fn(&str) -> bool) {
+        self.states
+            .retain(|connector_id, _| is_managed(connector_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_is_reported_only_on_change() {
+        let mut transitions = ConnectorTransitions::new(true);
+        assert_eq!(transitions.record("A", ConnectorStatus::Stopped), None);
+        assert_eq!(transitions.record("A", ConnectorStatus::Stopped), None);
+
+        assert_eq!(
+            transitions.record("A", ConnectorStatus::Started),
+            Some(ConnectorStatus::Stopped)
+        );
+        assert_eq!(transitions.record("A", ConnectorStatus::Started), None);
+        // Connectors are tracked independently
+        assert_eq!(transitions.record("B", ConnectorStatus::Started), None);
+        assert!(TRANSITIONS.lock().unwrap()[&("stopped", "started")] >= 1);
+
+        transitions.retain(|connector_id| connector_id != "A");
+        assert_eq!(transitions.record("A", ConnectorStatus::Paused), None);
+    }
+
+    #[test]
+    fn disabled_tracking_reports_nothing() {
+        let mut transitions = ConnectorTransitions::default();
+        transitions.record("A", ConnectorStatus::Stopped);
+        assert_eq!(transitions.record("A", ConnectorStatus::Started), None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+// Event formatter producing logfmt lines (ts=... level=... message="..." key=value)
+pub struct Logfmt;
+
+#[derive(Default)]
+struct LogfmtVisitor {
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for LogfmtVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '=' || c == '"' || c.is_control());
+    if needs_quotes {
+        format!("{:?}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for Logfmt
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        write!(
+            writer,
+            "ts={} level={} target={}",
+            chrono::Utc::now().to_rfc3339(),
+            metadata.level().to_string().to_lowercase(),
+            quote(metadata.target())
+        )?;
+        let mut visitor = LogfmtVisitor::default();
+        event.record(&mut visitor);
+        for (key, value) in visitor.fields {
+            write!(writer, " {}={}", key, quote(&value))?;
+        }
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_keeps_simple_values_bare() {
+        assert_eq!(quote("info"), "info");
+        assert_eq!(quote("xtm_composer::engine"), "xtm_composer::engine");
+    }
+
+    #[test]
+    fn quote_escapes_values_with_spaces_or_separators() {
+        assert_eq!(quote("Starting XTM composer"), "\"Starting XTM composer\"");
+        assert_eq!(quote("a=b"), "\"a=b\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote(""), "\"\"");
+    }
+}
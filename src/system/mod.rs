@@ -1 +1,2 @@
+pub mod logfmt;
 pub mod signals;
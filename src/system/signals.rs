@@ -15,6 +15,40 @@ pub async fn handle_stop_signals() -> Option<()> {
     }
 }
 
+// SIGHUP asking to reload the files read at runtime, only handled once created
+#[cfg(unix)]
+pub struct ReloadSignal(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl ReloadSignal {
+    pub fn install() -> Self {
+        Self(unix_signal(SignalKind::hangup()).ok())
+    }
+
+    pub async fn recv(&mut self) {
+        match self.0.as_mut() {
+            Some(hangup) => {
+                hangup.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub struct ReloadSignal;
+
+#[cfg(not(unix))]
+impl ReloadSignal {
+    pub fn install() -> Self {
+        Self
+    }
+
+    pub async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
+
 #[cfg(not(unix))]
 pub async fn handle_stop_signals() -> Option<()> {
     use tokio::signal;